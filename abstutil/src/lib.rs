@@ -120,6 +120,39 @@ pub fn path_all_saves(map_name: &str, edits_name: &str, run_name: &str) -> Strin
     )
 }
 
+pub fn path_exports(map_name: &str) -> String {
+    format!("../data/player/exports/{}", map_name)
+}
+
+pub fn path_map_diff(map_name: &str) -> String {
+    format!("../data/player/map_diffs/{}.json", map_name)
+}
+pub fn path_raw_map_diff(map_name: &str) -> String {
+    format!("../data/player/map_diffs/raw_{}.json", map_name)
+}
+
+pub fn path_validation_report(map_name: &str) -> String {
+    format!("../data/player/validation/{}.json", map_name)
+}
+
+pub fn path_result_set(map_name: &str, name: &str) -> String {
+    format!("../data/player/results/{}/{}.bin", map_name, name)
+}
+pub fn path_all_result_sets(map_name: &str) -> String {
+    format!("../data/player/results/{}", map_name)
+}
+
+pub fn path_trajectories(map_name: &str, run_name: &str) -> String {
+    format!("../data/player/trajectories/{}/{}.bin", map_name, run_name)
+}
+pub fn path_all_trajectories(map_name: &str) -> String {
+    format!("../data/player/trajectories/{}", map_name)
+}
+
+pub fn path_regional_results(city_name: &str, name: &str) -> String {
+    format!("../data/player/regional/{}/{}.json", city_name, name)
+}
+
 // Input data (For developers to build maps, not needed at runtime)
 
 pub fn path_pending_screenshots(map_name: &str) -> String {
@@ -1,7 +1,10 @@
 mod clip;
 mod osm_reader;
+mod plazas;
+mod sidewalks;
 mod split_ways;
 mod srtm;
+mod turn_pockets;
 
 use abstutil::Timer;
 use geom::{Distance, FindClosest, PolyLine, Pt2D};
@@ -21,6 +24,12 @@ pub struct Options {
     pub public_offstreet_parking: Option<String>,
     pub private_offstreet_parking: PrivateOffstreetParking,
     pub sidewalks: Option<String>,
+    // Snap sidewalks mapped as separate footways to their roads, and only put crosswalks where
+    // crossings are mapped.
+    pub separate_sidewalks: bool,
+    // Collapse clusters of signalized intersections joined by short roads. This changes
+    // intersection IDs, so saved edits and signal configs for those intersections won't apply.
+    pub merge_short_roads: bool,
     pub gtfs: Option<String>,
     pub elevation: Option<String>,
     pub clip: Option<String>,
@@ -35,12 +44,13 @@ pub enum PrivateOffstreetParking {
 }
 
 pub fn convert(opts: Options, timer: &mut abstutil::Timer) -> RawMap {
-    let (mut map, amenities) = split_ways::split_up_roads(
+    let (mut map, amenities, separate_sidewalks) = split_ways::split_up_roads(
         osm_reader::extract_osm(
             &opts.osm_input,
             &opts.clip,
             &opts.city_name,
             &opts.name,
+            opts.separate_sidewalks,
             timer,
         ),
         timer,
@@ -51,10 +61,16 @@ pub fn convert(opts: Options, timer: &mut abstutil::Timer) -> RawMap {
     } else {
         DrivingSide::Left
     };
+    map.merge_short_roads = opts.merge_short_roads;
 
     // Need to do a first pass of removing cul-de-sacs here, or we wind up with loop PolyLines when
     // doing the parking hint matching.
     abstutil::retain_btreemap(&mut map.roads, |r, _| r.i1 != r.i2);
+    turn_pockets::merge_turn_pockets(&mut map, timer);
+    if opts.separate_sidewalks {
+        sidewalks::snap_separate_sidewalks(&mut map, separate_sidewalks, timer);
+        plazas::connect_plazas(&mut map, timer);
+    }
 
     use_amenities(&mut map, amenities, timer);
 
@@ -80,13 +96,9 @@ pub fn convert(opts: Options, timer: &mut abstutil::Timer) -> RawMap {
     map
 }
 
-fn use_parking_hints(map: &mut RawMap, path: String, timer: &mut Timer) {
-    timer.start("apply parking hints");
-    let shapes: ExtraShapes = abstutil::read_binary(path, timer);
-
-    // Match shapes with the nearest road + direction (true for forwards)
-    let mut closest: FindClosest<(OriginalRoad, bool)> =
-        FindClosest::new(&map.gps_bounds.to_bounds());
+// Match shapes with the nearest road + direction (true for forwards)
+fn closest_road_sides(map: &RawMap, timer: &mut Timer) -> FindClosest<(OriginalRoad, bool)> {
+    let mut closest = FindClosest::new(&map.gps_bounds.to_bounds());
     for (id, r) in &map.roads {
         if r.is_light_rail() {
             continue;
@@ -107,6 +119,14 @@ fn use_parking_hints(map: &mut RawMap, path: String, timer: &mut Timer) {
                 .points(),
         );
     }
+    closest
+}
+
+fn use_parking_hints(map: &mut RawMap, path: String, timer: &mut Timer) {
+    timer.start("apply parking hints");
+    let shapes: ExtraShapes = abstutil::read_binary(path, timer);
+
+    let closest = closest_road_sides(map, timer);
 
     for s in shapes.shapes.into_iter() {
         let pts = if let Some(pts) = map.gps_bounds.try_convert(&s.points) {
@@ -247,29 +267,7 @@ fn use_sidewalk_hints(map: &mut RawMap, path: String, timer: &mut Timer) {
     timer.start("apply sidewalk hints");
     let shapes: ExtraShapes = abstutil::read_binary(path, timer);
 
-    // Match shapes with the nearest road + direction (true for forwards)
-    let mut closest: FindClosest<(OriginalRoad, bool)> =
-        FindClosest::new(&map.gps_bounds.to_bounds());
-    for (id, r) in &map.roads {
-        if r.is_light_rail() {
-            continue;
-        }
-        let center = PolyLine::new(r.center_points.clone());
-        closest.add(
-            (*id, true),
-            map.driving_side
-                .right_shift(center.clone(), DIRECTED_ROAD_THICKNESS)
-                .get(timer)
-                .points(),
-        );
-        closest.add(
-            (*id, false),
-            map.driving_side
-                .left_shift(center, DIRECTED_ROAD_THICKNESS)
-                .get(timer)
-                .points(),
-        );
-    }
+    let closest = closest_road_sides(map, timer);
 
     for s in shapes.shapes.into_iter() {
         let pts = if let Some(pts) = map.gps_bounds.try_convert(&s.points) {
@@ -12,6 +12,7 @@ pub fn extract_osm(
     maybe_clip_path: &Option<String>,
     city_name: &str,
     map_name: &str,
+    separate_sidewalks: bool,
     timer: &mut Timer,
 ) -> (
     RawMap,
@@ -19,6 +20,10 @@ pub fn extract_osm(
     Vec<(i64, RawRoad)>,
     // Traffic signals
     HashSet<HashablePt2D>,
+    // Stop and yield signs: (the highway tag, if the sign faces forwards along its way)
+    HashMap<HashablePt2D, (String, Option<bool>)>,
+    // Crossings mapped along roads. Only filled out when using separate sidewalks.
+    HashSet<HashablePt2D>,
     // OSM Node IDs
     HashMap<HashablePt2D, i64>,
     // Simple turn restrictions: (restriction type, from way ID, via node ID, to way ID)
@@ -27,6 +32,8 @@ pub fn extract_osm(
     Vec<(i64, i64, i64)>,
     // Amenities (location, name, amenity type)
     Vec<(Pt2D, String, String)>,
+    // Sidewalks mapped as separate footways. Only filled out when using separate sidewalks.
+    Vec<Vec<Pt2D>>,
 ) {
     let (reader, done) = FileWithProgress::new(osm_path).unwrap();
     let doc = osm_xml::OSM::parse(reader).expect("OSM parsing failed");
@@ -61,6 +68,9 @@ pub fn extract_osm(
     let mut id_to_way: HashMap<i64, Vec<Pt2D>> = HashMap::new();
     let mut roads: Vec<(i64, RawRoad)> = Vec::new();
     let mut traffic_signals: HashSet<HashablePt2D> = HashSet::new();
+    let mut stop_signs: HashMap<HashablePt2D, (String, Option<bool>)> = HashMap::new();
+    let mut crossings: HashSet<HashablePt2D> = HashSet::new();
+    let mut sidewalks: Vec<Vec<Pt2D>> = Vec::new();
     let mut osm_node_ids = HashMap::new();
     let mut node_amenities = Vec::new();
 
@@ -74,6 +84,22 @@ pub fn extract_osm(
         if tags.get(osm::HIGHWAY) == Some(&"traffic_signals".to_string()) {
             traffic_signals.insert(pt.to_hashable());
         }
+        if tags.get(osm::HIGHWAY) == Some(&"stop".to_string())
+            || tags.get(osm::HIGHWAY) == Some(&"give_way".to_string())
+        {
+            let forwards = match tags.get("direction").map(|x| x.as_str()) {
+                Some("forward") => Some(true),
+                Some("backward") => Some(false),
+                _ => None,
+            };
+            stop_signs.insert(pt.to_hashable(), (tags[osm::HIGHWAY].clone(), forwards));
+        }
+        if separate_sidewalks
+            && (tags.get(osm::HIGHWAY) == Some(&"crossing".to_string())
+                || tags.get("footway") == Some(&"crossing".to_string()))
+        {
+            crossings.insert(pt.to_hashable());
+        }
         if let Some(amenity) = tags.get("amenity") {
             node_amenities.push((
                 pt,
@@ -168,6 +194,13 @@ pub fn extract_osm(
                     complicated_turn_restrictions: Vec::new(),
                 },
             ));
+        } else if separate_sidewalks
+            && tags.get(osm::HIGHWAY) == Some(&"footway".to_string())
+            && tags.get("footway") == Some(&"sidewalk".to_string())
+        {
+            // These get snapped to the road they're alongside, instead of becoming their own
+            // roads.
+            sidewalks.push(pts);
         } else if tags.get("railway") == Some(&"light_rail".to_string()) {
             roads.push((
                 way.id,
@@ -212,7 +245,7 @@ pub fn extract_osm(
                     amenities,
                 },
             );
-        } else if let Some(at) = get_area_type(&tags) {
+        } else if let Some(at) = get_area_type(&tags, separate_sidewalks) {
             if pts.len() < 3 {
                 continue;
             }
@@ -247,7 +280,7 @@ pub fn extract_osm(
         timer.next();
         let mut tags = tags_to_map(&rel.tags);
         tags.insert(osm::OSM_REL_ID.to_string(), rel.id.to_string());
-        if let Some(at) = get_area_type(&tags) {
+        if let Some(at) = get_area_type(&tags, separate_sidewalks) {
             if tags.get("type") == Some(&"multipolygon".to_string()) {
                 let mut ok = true;
                 let mut pts_per_way: Vec<(i64, Vec<Pt2D>)> = Vec::new();
@@ -407,10 +440,13 @@ pub fn extract_osm(
         map,
         roads,
         traffic_signals,
+        stop_signs,
+        crossings,
         osm_node_ids,
         simple_turn_restrictions,
         complicated_turn_restrictions,
         node_amenities,
+        sidewalks,
     )
 }
 
@@ -479,7 +515,7 @@ fn is_bldg(tags: &BTreeMap<String, String>) -> bool {
     tags.contains_key("building") && !tags.contains_key("abandoned:man_made")
 }
 
-fn get_area_type(tags: &BTreeMap<String, String>) -> Option<AreaType> {
+fn get_area_type(tags: &BTreeMap<String, String>, separate_sidewalks: bool) -> Option<AreaType> {
     if tags.get("leisure") == Some(&"park".to_string()) {
         return Some(AreaType::Park);
     }
@@ -500,16 +536,20 @@ fn get_area_type(tags: &BTreeMap<String, String>) -> Option<AreaType> {
     if tags.get("place") == Some(&"island".to_string()) {
         return Some(AreaType::Island);
     }
+    // Cities mapping sidewalks separately tend to map pedestrian areas too. These get connected
+    // to the sidewalks around them later.
+    if separate_sidewalks
+        && ((tags.get(osm::HIGHWAY) == Some(&"pedestrian".to_string())
+            && tags.get("area") == Some(&"yes".to_string()))
+            || tags.get("place") == Some(&"square".to_string()))
+    {
+        return Some(AreaType::Plaza);
+    }
     // TODO These just cover up poorly inferred road geometry now. Figure out how to use these.
     if false {
         if tags.get("traffic_calming") == Some(&"island".to_string()) {
             return Some(AreaType::PedestrianIsland);
         }
-        if tags.get("highway") == Some(&"pedestrian".to_string())
-            && tags.get("area") == Some(&"yes".to_string())
-        {
-            return Some(AreaType::PedestrianIsland);
-        }
     }
     None
 }
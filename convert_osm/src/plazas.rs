@@ -0,0 +1,104 @@
+use abstutil::Timer;
+use geom::{Distance, Pt2D};
+use map_model::raw::{OriginalIntersection, OriginalRoad, RawIntersection, RawMap, RawRoad};
+use map_model::{osm, AreaType, IntersectionType, LaneType};
+use std::collections::BTreeMap;
+
+// Intersections this close to the edge of a plaza lead into it
+const ENTRANCE_THRESHOLD: Distance = Distance::const_meters(3.0);
+
+// Pedestrians can cross plazas and pedestrian areas in any direction, but the sidewalk graph only
+// has lanes. Connect every sidewalk meeting the edge of a plaza to a hub in the middle with a
+// footway, so people can walk from any entrance to any other.
+pub fn connect_plazas(map: &mut RawMap, timer: &mut Timer) {
+    timer.start("connect plazas");
+    let mut connected = 0;
+    for idx in 0..map.areas.len() {
+        if map.areas[idx].area_type != AreaType::Plaza {
+            continue;
+        }
+        let hub_pt = map.areas[idx].polygon.polylabel();
+        let entrances: Vec<(OriginalIntersection, Pt2D)> = find_entrances(map, idx)
+            .into_iter()
+            .filter(|(_, pt)| pt.dist_to(hub_pt) >= ENTRANCE_THRESHOLD)
+            .collect();
+        if entrances.len() < 2 {
+            continue;
+        }
+
+        let area = &map.areas[idx];
+        let name = area.osm_tags.get(osm::NAME).cloned();
+        let hub = OriginalIntersection {
+            osm_node_id: map.new_osm_node_id(-1),
+        };
+        map.intersections.insert(
+            hub,
+            RawIntersection {
+                point: hub_pt,
+                intersection_type: IntersectionType::StopSign,
+                elevation: Distance::ZERO,
+            },
+        );
+
+        for (i, pt) in entrances {
+            let id = OriginalRoad {
+                osm_way_id: map.new_osm_way_id(-1),
+                i1: hub,
+                i2: i,
+            };
+            let mut osm_tags = BTreeMap::new();
+            osm_tags.insert(osm::HIGHWAY.to_string(), "footway".to_string());
+            osm_tags.insert(osm::SYNTHETIC.to_string(), "true".to_string());
+            osm_tags.insert(osm::ENDPT_FWD.to_string(), "true".to_string());
+            osm_tags.insert(osm::ENDPT_BACK.to_string(), "true".to_string());
+            osm_tags.insert(osm::OSM_WAY_ID.to_string(), id.osm_way_id.to_string());
+            if let Some(ref name) = name {
+                osm_tags.insert(osm::NAME.to_string(), name.clone());
+            }
+            map.roads.insert(
+                id,
+                RawRoad {
+                    center_points: vec![hub_pt, pt],
+                    osm_tags,
+                    turn_restrictions: Vec::new(),
+                    complicated_turn_restrictions: Vec::new(),
+                },
+            );
+        }
+        connected += 1;
+    }
+    timer.note(format!(
+        "Connected {} plazas to the sidewalks around them",
+        connected
+    ));
+    timer.stop("connect plazas");
+}
+
+// Intersections on or inside the plaza with some sidewalk
+fn find_entrances(map: &RawMap, idx: usize) -> Vec<(OriginalIntersection, Pt2D)> {
+    let polygon = &map.areas[idx].polygon;
+    let edge = match polygon.maybe_to_outline(ENTRANCE_THRESHOLD * 2.0) {
+        Some(edge) => edge,
+        None => {
+            return Vec::new();
+        }
+    };
+
+    let mut entrances = Vec::new();
+    for (id, i) in &map.intersections {
+        if i.intersection_type == IntersectionType::Border {
+            continue;
+        }
+        if !polygon.contains_pt(i.point) && !edge.contains_pt(i.point) {
+            continue;
+        }
+        let has_sidewalk = map.roads_per_intersection(*id).into_iter().any(|r| {
+            let spec = map.roads[&r].get_spec();
+            spec.fwd.contains(&LaneType::Sidewalk) || spec.back.contains(&LaneType::Sidewalk)
+        });
+        if has_sidewalk {
+            entrances.push((*id, i.point));
+        }
+    }
+    entrances
+}
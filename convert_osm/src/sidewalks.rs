@@ -0,0 +1,81 @@
+use crate::{closest_road_sides, DIRECTED_ROAD_THICKNESS};
+use abstutil::Timer;
+use geom::{Distance, PolyLine, Pt2D};
+use map_model::osm;
+use map_model::raw::{OriginalRoad, RawMap};
+use std::collections::BTreeMap;
+
+// How often to check which road a sidewalk is alongside. Long footways often follow a few blocks.
+const SAMPLE_EVERY: Distance = Distance::const_meters(10.0);
+
+// Some places map sidewalks as their own footways, tagging the road itself with sidewalk=separate
+// (or nothing at all). Instead of making the footways their own roads, figure out which side of
+// which road they follow, and put sidewalks there.
+pub fn snap_separate_sidewalks(map: &mut RawMap, sidewalks: Vec<Vec<Pt2D>>, timer: &mut Timer) {
+    timer.start("snap separate sidewalks");
+    let closest = closest_road_sides(map, timer);
+
+    // (forwards, backwards)
+    let mut found: BTreeMap<OriginalRoad, (bool, bool)> = BTreeMap::new();
+    for pts in sidewalks {
+        let pl = if let Some(pl) = PolyLine::maybe_new(pts) {
+            pl
+        } else {
+            continue;
+        };
+        // The endpoints are close to other roads, so skip them.
+        let mut samples = Vec::new();
+        let mut dist = SAMPLE_EVERY;
+        while dist < pl.length() - SAMPLE_EVERY {
+            samples.push(pl.dist_along(dist).0);
+            dist += SAMPLE_EVERY;
+        }
+        if samples.is_empty() {
+            samples.push(pl.middle());
+        }
+
+        for pt in samples {
+            if let Some(((r, fwds), _)) = closest.closest_pt(pt, DIRECTED_ROAD_THICKNESS * 5.0) {
+                let entry = found.entry(r).or_insert((false, false));
+                if fwds {
+                    entry.0 = true;
+                } else {
+                    entry.1 = true;
+                }
+            }
+        }
+    }
+
+    let mut snapped = 0;
+    for (r, (fwd, back)) in found {
+        let osm_tags = &mut map.roads.get_mut(&r).unwrap().osm_tags;
+        // Trust anything explicitly mapped on the road itself.
+        if osm_tags.get(osm::SIDEWALK) != Some(&"separate".to_string())
+            && !osm_tags.contains_key(osm::INFERRED_SIDEWALKS)
+        {
+            continue;
+        }
+        let definitely_no_sidewalks = match osm_tags.get(osm::HIGHWAY) {
+            Some(hwy) => hwy == "motorway" || hwy == "motorway_link",
+            None => false,
+        };
+        if definitely_no_sidewalks {
+            continue;
+        }
+
+        let value = match (fwd, back) {
+            (true, true) => "both",
+            (true, false) => "right",
+            (false, true) => "left",
+            (false, false) => unreachable!(),
+        };
+        osm_tags.insert(osm::SIDEWALK.to_string(), value.to_string());
+        osm_tags.remove(osm::INFERRED_SIDEWALKS);
+        snapped += 1;
+    }
+    timer.note(format!(
+        "Snapped separately mapped sidewalks to {} roads",
+        snapped
+    ));
+    timer.stop("snap separate sidewalks");
+}
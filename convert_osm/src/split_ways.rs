@@ -4,28 +4,34 @@ use map_model::raw::{
     OriginalIntersection, OriginalRoad, RawIntersection, RawMap, RawRoad, RestrictionType,
 };
 use map_model::{osm, IntersectionType};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 pub fn split_up_roads(
     (
         mut map,
         roads,
         traffic_signals,
+        stop_signs,
+        crossings,
         osm_node_ids,
         simple_turn_restrictions,
         complicated_turn_restrictions,
         amenities,
+        sidewalks,
     ): (
         RawMap,
         Vec<(i64, RawRoad)>,
         HashSet<HashablePt2D>,
+        HashMap<HashablePt2D, (String, Option<bool>)>,
+        HashSet<HashablePt2D>,
         HashMap<HashablePt2D, i64>,
         Vec<(RestrictionType, i64, i64, i64)>,
         Vec<(i64, i64, i64)>,
         Vec<(Pt2D, String, String)>,
+        Vec<Vec<Pt2D>>,
     ),
     timer: &mut Timer,
-) -> (RawMap, Vec<(Pt2D, String, String)>) {
+) -> (RawMap, Vec<(Pt2D, String, String)>, Vec<Vec<Pt2D>>) {
     timer.start("splitting up roads");
 
     let mut pt_to_intersection: HashMap<HashablePt2D, OriginalIntersection> = HashMap::new();
@@ -87,6 +93,8 @@ pub fn split_up_roads(
                     r.osm_tags
                         .insert(osm::ENDPT_FWD.to_string(), "true".to_string());
                 }
+                find_stop_signs(&pts, &stop_signs, &mut r.osm_tags);
+                find_crossings(&pts, &crossings, &mut r.osm_tags);
                 r.center_points = dedupe_angles(std::mem::replace(&mut pts, Vec::new()));
                 // Start a new road
                 map.roads.insert(
@@ -99,6 +107,10 @@ pub fn split_up_roads(
                 );
                 r.osm_tags.remove(osm::ENDPT_FWD);
                 r.osm_tags.remove(osm::ENDPT_BACK);
+                r.osm_tags.remove(osm::STOP_SIGN_FWD);
+                r.osm_tags.remove(osm::STOP_SIGN_BACK);
+                r.osm_tags.remove(osm::CROSSING_FWD);
+                r.osm_tags.remove(osm::CROSSING_BACK);
                 i1 = *i2;
                 pts.push(*pt);
             }
@@ -186,7 +198,7 @@ pub fn split_up_roads(
     }
 
     timer.stop("splitting up roads");
-    (map, amenities)
+    (map, amenities, sidewalks)
 }
 
 // TODO Consider doing this in PolyLine::new always. extend() there does this too.
@@ -208,3 +220,65 @@ fn dedupe_angles(pts: Vec<Pt2D>) -> Vec<Pt2D> {
     }
     result
 }
+
+// Figure out which end of one piece of a split road any stop or yield signs along it apply to.
+fn find_stop_signs(
+    pts: &[Pt2D],
+    stop_signs: &HashMap<HashablePt2D, (String, Option<bool>)>,
+    tags: &mut BTreeMap<String, String>,
+) {
+    let first = pts[0];
+    let last = *pts.last().unwrap();
+    for (idx, pt) in pts.iter().enumerate() {
+        if let Some((highway, forwards)) = stop_signs.get(&pt.to_hashable()) {
+            let forwards = if idx == 0 || idx == pts.len() - 1 {
+                // A sign mapped on the intersection itself without a direction applies to every
+                // road entering it. One pointing away from this piece belongs to the neighboring
+                // piece.
+                match forwards {
+                    Some(true) if idx != 0 => true,
+                    Some(false) if idx == 0 => false,
+                    None => idx != 0,
+                    _ => continue,
+                }
+            } else if let Some(fwd) = forwards {
+                *fwd
+            } else {
+                // Assume the sign is closest to the intersection it's for
+                pt.dist_to(last) < pt.dist_to(first)
+            };
+            tags.insert(
+                if forwards {
+                    osm::STOP_SIGN_FWD
+                } else {
+                    osm::STOP_SIGN_BACK
+                }
+                .to_string(),
+                highway.clone(),
+            );
+        }
+    }
+}
+
+// Crossings are usually mapped where the footway meets the road, just before the intersection.
+fn find_crossings(
+    pts: &[Pt2D],
+    crossings: &HashSet<HashablePt2D>,
+    tags: &mut BTreeMap<String, String>,
+) {
+    let first = pts[0];
+    let last = *pts.last().unwrap();
+    for pt in pts {
+        if crossings.contains(&pt.to_hashable()) {
+            tags.insert(
+                if pt.dist_to(last) < pt.dist_to(first) {
+                    osm::CROSSING_FWD
+                } else {
+                    osm::CROSSING_BACK
+                }
+                .to_string(),
+                "true".to_string(),
+            );
+        }
+    }
+}
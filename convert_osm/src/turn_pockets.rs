@@ -0,0 +1,375 @@
+use abstutil::Timer;
+use geom::{Distance, PolyLine};
+use map_model::raw::{OriginalIntersection, OriginalRoad, RawMap};
+use map_model::{osm, IntersectionType, LaneType};
+
+// Turn pockets are usually mapped in OSM by splitting a way shortly before an intersection and
+// giving the short piece extra lanes. Left alone, that produces a tiny road and a degenerate
+// intersection. Instead, merge the short piece into the road leading up to it, and mark the extra
+// lanes as only existing for the last stretch. Pockets can be in either direction, but both ways
+// have to be drawn the same way.
+const MAX_POCKET_LENGTH: Distance = Distance::const_meters(100.0);
+
+pub fn merge_turn_pockets(map: &mut RawMap, timer: &mut Timer) {
+    timer.start("merge turn pockets");
+    let mut merged = 0;
+    let all_intersections: Vec<OriginalIntersection> = map.intersections.keys().cloned().collect();
+    for i in all_intersections {
+        if let Some(pocket) = find_pocket(map, i, timer) {
+            merge(map, i, pocket);
+            merged += 1;
+        }
+    }
+    timer.note(format!(
+        "Merged {} turn pockets into their approach",
+        merged
+    ));
+    timer.stop("merge turn pockets");
+}
+
+struct Pocket {
+    // The road ending at the intersection
+    first: OriginalRoad,
+    // The road starting at the intersection
+    second: OriginalRoad,
+    // True if the extra lanes are on the second road going forwards. Otherwise, they're on the
+    // first road going backwards.
+    forwards: bool,
+    // Offsets of the extra lanes, in the direction they're on
+    lanes: Vec<usize>,
+}
+
+fn find_pocket(map: &RawMap, i: OriginalIntersection, timer: &mut Timer) -> Option<Pocket> {
+    if map.intersections[&i].intersection_type != IntersectionType::StopSign {
+        return None;
+    }
+    let roads = map.roads_per_intersection(i);
+    if roads.len() != 2 {
+        return None;
+    }
+    let (first, second) = if roads[0].i2 == i && roads[1].i1 == i {
+        (roads[0], roads[1])
+    } else if roads[1].i2 == i && roads[0].i1 == i {
+        (roads[1], roads[0])
+    } else {
+        // Merging would mean reversing one way, including all of its directional tags. Don't.
+        let spec1 = map.roads[&roads[0]].get_spec();
+        let spec2 = map.roads[&roads[1]].get_spec();
+        if spec1.fwd.len() + spec1.back.len() != spec2.fwd.len() + spec2.back.len() {
+            timer.warn(format!(
+                "Not looking for a turn pocket at {}; {} and {} are drawn in opposite directions",
+                i, roads[0], roads[1]
+            ));
+        }
+        return None;
+    };
+    if first.i1 == second.i2
+        || map
+            .roads_per_intersection(first.i1)
+            .into_iter()
+            .any(|r| r.i1 == second.i2 || r.i2 == second.i2)
+    {
+        return None;
+    }
+
+    let r1 = &map.roads[&first];
+    let r2 = &map.roads[&second];
+    if r1.synthetic() || r2.synthetic() || r1.is_light_rail() || r2.is_light_rail() {
+        return None;
+    }
+    if r1.osm_tags.get(osm::NAME) != r2.osm_tags.get(osm::NAME) {
+        return None;
+    }
+    for r in &[r1, r2] {
+        if r.osm_tags.contains_key(osm::TURN_POCKETS_FWD)
+            || r.osm_tags.contains_key(osm::TURN_POCKETS_BACK)
+        {
+            return None;
+        }
+    }
+
+    let spec1 = r1.get_spec();
+    let spec2 = r2.get_spec();
+    if spec1.fwd == spec2.fwd && spec1.back == spec2.back {
+        return None;
+    }
+    let short1 = PolyLine::new(r1.center_points.clone()).length() <= MAX_POCKET_LENGTH;
+    let short2 = PolyLine::new(r2.center_points.clone()).length() <= MAX_POCKET_LENGTH;
+    // Forwards, the pocket is at the end of the second road. Backwards, it's at the end of the
+    // first road, near first.i1.
+    if spec1.back == spec2.back && short2 {
+        if let Some(lanes) = extra_driving_lanes(&spec2.fwd, &spec1.fwd) {
+            return Some(Pocket {
+                first,
+                second,
+                forwards: true,
+                lanes,
+            });
+        }
+    }
+    if spec1.fwd == spec2.fwd && short1 {
+        if let Some(lanes) = extra_driving_lanes(&spec1.back, &spec2.back) {
+            return Some(Pocket {
+                first,
+                second,
+                forwards: false,
+                lanes,
+            });
+        }
+    }
+    if short1 || short2 {
+        timer.warn(format!(
+            "Lanes change from {} to {} at {}, but not like a turn pocket, so not merging",
+            spec1, spec2, i
+        ));
+    }
+    None
+}
+
+// If removing some driving lanes from wider yields narrower, returns their indices. Handles
+// several pocket lanes at once, like 4 lanes becoming 2.
+fn extra_driving_lanes(wider: &Vec<LaneType>, narrower: &Vec<LaneType>) -> Option<Vec<usize>> {
+    if wider.len() <= narrower.len() {
+        return None;
+    }
+    let mut extra = Vec::new();
+    let mut matched = 0;
+    for (idx, lt) in wider.iter().enumerate() {
+        if matched < narrower.len() && narrower[matched] == *lt {
+            matched += 1;
+        } else if *lt == LaneType::Driving {
+            extra.push(idx);
+        } else {
+            return None;
+        }
+    }
+    if matched == narrower.len() {
+        Some(extra)
+    } else {
+        None
+    }
+}
+
+fn merge(map: &mut RawMap, i: OriginalIntersection, pocket: Pocket) {
+    let first_road = map.roads.remove(&pocket.first).unwrap();
+    let second_road = map.roads.remove(&pocket.second).unwrap();
+    let (mut road, other_road, keys, tag_key) = if pocket.forwards {
+        (
+            second_road,
+            first_road,
+            [osm::ENDPT_BACK, osm::STOP_SIGN_BACK, osm::CROSSING_BACK],
+            osm::TURN_POCKETS_FWD,
+        )
+    } else {
+        (
+            first_road,
+            second_road,
+            [osm::ENDPT_FWD, osm::STOP_SIGN_FWD, osm::CROSSING_FWD],
+            osm::TURN_POCKETS_BACK,
+        )
+    };
+    let pocket_length = PolyLine::new(road.center_points.clone()).length();
+    let (pocket_id, other_id) = if pocket.forwards {
+        (pocket.second, pocket.first)
+    } else {
+        (pocket.first, pocket.second)
+    };
+    let id = OriginalRoad {
+        osm_way_id: pocket_id.osm_way_id,
+        i1: pocket.first.i1,
+        i2: pocket.second.i2,
+    };
+
+    // The pocket has all of the lanes, so keep its tags. But the far end of the merged road comes
+    // from the other piece.
+    for key in &keys {
+        road.osm_tags.remove(*key);
+        if let Some(value) = other_road.osm_tags.get(*key) {
+            road.osm_tags.insert(key.to_string(), value.clone());
+        }
+    }
+    road.osm_tags.insert(
+        tag_key.to_string(),
+        pocket
+            .lanes
+            .iter()
+            .map(|idx| format!("{}:{}", idx, pocket_length.inner_meters()))
+            .collect::<Vec<_>>()
+            .join(";"),
+    );
+
+    let (mut pts, rest) = if pocket.forwards {
+        (other_road.center_points.clone(), &road.center_points)
+    } else {
+        (road.center_points.clone(), &other_road.center_points)
+    };
+    pts.extend(rest.iter().skip(1).cloned());
+    road.center_points = pts;
+
+    // Restrictions between the two halves don't mean anything anymore.
+    road.turn_restrictions.retain(|(_, to)| *to != other_id);
+    road.turn_restrictions.extend(
+        other_road
+            .turn_restrictions
+            .into_iter()
+            .filter(|(_, to)| *to != pocket_id),
+    );
+    road.complicated_turn_restrictions
+        .retain(|(via, to)| *via != other_id && *to != other_id);
+    road.complicated_turn_restrictions.extend(
+        other_road
+            .complicated_turn_restrictions
+            .into_iter()
+            .filter(|(via, to)| *via != pocket_id && *to != pocket_id),
+    );
+
+    // Other roads might refer to either half.
+    let fix = |r: &mut OriginalRoad| {
+        if *r == pocket.first || *r == pocket.second {
+            *r = id;
+        }
+    };
+    for other in map.roads.values_mut() {
+        for (_, to) in other.turn_restrictions.iter_mut() {
+            fix(to);
+        }
+        for (via, to) in other.complicated_turn_restrictions.iter_mut() {
+            fix(via);
+            fix(to);
+        }
+    }
+
+    map.roads.insert(id, road);
+    map.intersections.remove(&i).unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geom::Pt2D;
+    use map_model::raw::{RawIntersection, RawRoad};
+    use std::collections::BTreeMap;
+
+    fn intersection(map: &mut RawMap, id: i64, x: f64) -> OriginalIntersection {
+        let i = OriginalIntersection { osm_node_id: id };
+        map.intersections.insert(
+            i,
+            RawIntersection {
+                point: Pt2D::new(x, 0.0),
+                intersection_type: IntersectionType::StopSign,
+                elevation: Distance::ZERO,
+            },
+        );
+        i
+    }
+
+    fn road(
+        map: &mut RawMap,
+        osm_way_id: i64,
+        i1: OriginalIntersection,
+        i2: OriginalIntersection,
+        tags: Vec<(&str, &str)>,
+    ) {
+        let mut osm_tags = BTreeMap::new();
+        osm_tags.insert(osm::HIGHWAY.to_string(), "primary".to_string());
+        osm_tags.insert(osm::NAME.to_string(), "Main".to_string());
+        osm_tags.insert("sidewalk".to_string(), "both".to_string());
+        for (k, v) in tags {
+            osm_tags.insert(k.to_string(), v.to_string());
+        }
+        let pts = vec![map.intersections[&i1].point, map.intersections[&i2].point];
+        map.roads.insert(
+            OriginalRoad { osm_way_id, i1, i2 },
+            RawRoad {
+                center_points: pts,
+                osm_tags,
+                turn_restrictions: Vec::new(),
+                complicated_turn_restrictions: Vec::new(),
+            },
+        );
+    }
+
+    #[test]
+    fn test_extra_driving_lanes() {
+        use LaneType::{Biking, Driving, Sidewalk};
+
+        assert_eq!(
+            extra_driving_lanes(&vec![Driving, Driving, Sidewalk], &vec![Driving, Sidewalk]),
+            Some(vec![1])
+        );
+        assert_eq!(
+            extra_driving_lanes(
+                &vec![Driving, Driving, Driving, Driving],
+                &vec![Driving, Driving]
+            ),
+            Some(vec![2, 3])
+        );
+        // Only driving lanes can appear
+        assert_eq!(
+            extra_driving_lanes(&vec![Driving, Biking, Sidewalk], &vec![Driving, Sidewalk]),
+            None
+        );
+        assert_eq!(
+            extra_driving_lanes(&vec![Driving, Sidewalk], &vec![Driving, Sidewalk]),
+            None
+        );
+        assert_eq!(
+            extra_driving_lanes(&vec![Driving, Sidewalk], &vec![Driving, Driving, Sidewalk]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_merge_forwards_pocket() {
+        let mut map = RawMap::blank("test", "test");
+        let a = intersection(&mut map, 1, 0.0);
+        let b = intersection(&mut map, 2, 200.0);
+        let c = intersection(&mut map, 3, 250.0);
+        road(&mut map, 10, a, b, vec![("lanes", "2")]);
+        road(
+            &mut map,
+            11,
+            b,
+            c,
+            vec![("lanes:forward", "2"), ("lanes:backward", "1")],
+        );
+
+        merge_turn_pockets(&mut map, &mut Timer::throwaway());
+
+        assert!(!map.intersections.contains_key(&b));
+        assert_eq!(map.roads.len(), 1);
+        let id = OriginalRoad {
+            osm_way_id: 11,
+            i1: a,
+            i2: c,
+        };
+        let r = &map.roads[&id];
+        assert_eq!(
+            r.osm_tags.get(osm::TURN_POCKETS_FWD),
+            Some(&"1:50".to_string())
+        );
+        assert_eq!(r.center_points.len(), 3);
+        assert_eq!(r.get_spec().fwd.len(), 3);
+    }
+
+    #[test]
+    fn test_opposite_directions_not_merged() {
+        let mut map = RawMap::blank("test", "test");
+        let a = intersection(&mut map, 1, 0.0);
+        let b = intersection(&mut map, 2, 200.0);
+        let c = intersection(&mut map, 3, 250.0);
+        road(&mut map, 10, a, b, vec![("lanes", "2")]);
+        road(
+            &mut map,
+            11,
+            c,
+            b,
+            vec![("lanes:forward", "1"), ("lanes:backward", "2")],
+        );
+
+        merge_turn_pockets(&mut map, &mut Timer::throwaway());
+
+        assert!(map.intersections.contains_key(&b));
+        assert_eq!(map.roads.len(), 2);
+    }
+}
@@ -54,6 +54,18 @@ impl GeomBatch {
         self.list
     }
 
+    /// Draws everything inside the bounds to an SVG file, scaled by zoom. This doesn't need a GPU
+    /// or a window.
+    pub fn write_svg(&self, bounds: &Bounds, zoom: f64, path: &str) -> Result<(), std::io::Error> {
+        std::fs::create_dir_all(std::path::Path::new(path).parent().unwrap())?;
+        std::fs::write(path, crate::tools::render::to_svg(self, bounds, zoom))
+    }
+
+    /// Like `write_svg`, but rasterizes to a PNG file in software.
+    pub fn write_png(&self, bounds: &Bounds, zoom: f64, path: &str) -> Result<(), String> {
+        crate::tools::render::to_png(self, bounds, zoom, path)
+    }
+
     /// Draws the batch, consuming it. Only use this for drawing things once.
     pub fn draw(self, g: &mut GfxCtx) {
         let refs = self
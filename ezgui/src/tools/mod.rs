@@ -1,3 +1,4 @@
+pub(crate) mod render;
 pub mod screenshot;
 pub mod warper;
 pub mod wizard;
@@ -0,0 +1,179 @@
+use crate::{Color, FancyColor, GeomBatch};
+use geom::{Bounds, Polygon, Pt2D};
+use std::fmt::Write;
+
+// Draws a GeomBatch entirely in software, so figures can be produced on machines without a GPU
+// or display. Everything inside the bounds is scaled by zoom, with the top-left corner of the
+// bounds winding up at (0, 0).
+
+pub(crate) fn to_svg(batch: &GeomBatch, bounds: &Bounds, zoom: f64) -> String {
+    let mut defs = String::new();
+    let mut body = String::new();
+    for (idx, (color, poly)) in batch.list.iter().enumerate() {
+        if !overlaps(bounds, poly) {
+            continue;
+        }
+        let fill = match color {
+            FancyColor::RGBA(c) => svg_fill(*c),
+            FancyColor::LinearGradient(lg) => {
+                let (x1, y1) = transform(lg.line.pt1(), bounds, zoom);
+                let (x2, y2) = transform(lg.line.pt2(), bounds, zoom);
+                write!(
+                    defs,
+                    "<linearGradient id=\"gradient{}\" gradientUnits=\"userSpaceOnUse\" \
+                     x1=\"{:.2}\" y1=\"{:.2}\" x2=\"{:.2}\" y2=\"{:.2}\">",
+                    idx, x1, y1, x2, y2
+                )
+                .unwrap();
+                for (offset, c) in &lg.stops {
+                    write!(
+                        defs,
+                        r#"<stop offset="{}" stop-color="{}" stop-opacity="{}"/>"#,
+                        offset,
+                        c.to_hex(),
+                        c.a
+                    )
+                    .unwrap();
+                }
+                defs.push_str("</linearGradient>\n");
+                format!(r#"fill="url(#gradient{})""#, idx)
+            }
+        };
+
+        // Polygons are always triangulated, and they don't necessarily remember their outline
+        let mut path = String::new();
+        for tri in poly.triangles() {
+            let (x1, y1) = transform(tri.pt1, bounds, zoom);
+            let (x2, y2) = transform(tri.pt2, bounds, zoom);
+            let (x3, y3) = transform(tri.pt3, bounds, zoom);
+            write!(
+                path,
+                "M{:.2} {:.2}L{:.2} {:.2}L{:.2} {:.2}Z",
+                x1, y1, x2, y2, x3, y3
+            )
+            .unwrap();
+        }
+        writeln!(body, r#"<path d="{}" {}/>"#, path, fill).unwrap();
+    }
+
+    let (width, height) = (bounds.width() * zoom, bounds.height() * zoom);
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{:.0}\" height=\"{:.0}\" viewBox=\"0 0 \
+         {:.2} {:.2}\" shape-rendering=\"crispEdges\">\n<defs>\n{}</defs>\n{}</svg>\n",
+        width, height, width, height, defs, body
+    )
+}
+
+pub(crate) fn to_png(
+    batch: &GeomBatch,
+    bounds: &Bounds,
+    zoom: f64,
+    path: &str,
+) -> Result<(), String> {
+    let width = (bounds.width() * zoom).ceil() as u32;
+    let height = (bounds.height() * zoom).ceil() as u32;
+    // Starts fully transparent
+    let mut img = image::RgbaImage::new(width, height);
+
+    for (color, poly) in &batch.list {
+        if !overlaps(bounds, poly) {
+            continue;
+        }
+        for tri in poly.triangles() {
+            let pts = [
+                transform(tri.pt1, bounds, zoom),
+                transform(tri.pt2, bounds, zoom),
+                transform(tri.pt3, bounds, zoom),
+            ];
+            fill_triangle(&mut img, pts, |x, y| {
+                // Gradients are defined in the batch's coordinate space
+                color.style(Pt2D::new(x / zoom + bounds.min_x, y / zoom + bounds.min_y))
+            });
+        }
+    }
+
+    std::fs::create_dir_all(std::path::Path::new(path).parent().unwrap())
+        .map_err(|err| err.to_string())?;
+    img.save(path).map_err(|err| err.to_string())
+}
+
+fn transform(pt: Pt2D, bounds: &Bounds, zoom: f64) -> (f64, f64) {
+    (
+        (pt.x() - bounds.min_x) * zoom,
+        (pt.y() - bounds.min_y) * zoom,
+    )
+}
+
+fn overlaps(bounds: &Bounds, poly: &Polygon) -> bool {
+    let b = poly.get_bounds();
+    b.max_x >= bounds.min_x
+        && b.min_x <= bounds.max_x
+        && b.max_y >= bounds.min_y
+        && b.min_y <= bounds.max_y
+}
+
+fn svg_fill(c: Color) -> String {
+    if c.a >= 1.0 {
+        format!(r#"fill="{}""#, c.to_hex())
+    } else {
+        format!(r#"fill="{}" fill-opacity="{}""#, c.to_hex(), c.a)
+    }
+}
+
+// Fill every pixel whose center is inside the triangle, blending over what's already there. No
+// anti-aliasing; use a higher zoom for smoother figures.
+fn fill_triangle<F: Fn(f64, f64) -> [f32; 4]>(
+    img: &mut image::RgbaImage,
+    pts: [(f64, f64); 3],
+    color: F,
+) {
+    let edge = |(x1, y1): (f64, f64), (x2, y2): (f64, f64), x: f64, y: f64| {
+        (x2 - x1) * (y - y1) - (y2 - y1) * (x - x1)
+    };
+    let area = edge(pts[0], pts[1], pts[2].0, pts[2].1);
+    if area.abs() < std::f64::EPSILON {
+        return;
+    }
+
+    let min_x = pts.iter().map(|(x, _)| *x).fold(std::f64::MAX, f64::min);
+    let max_x = pts.iter().map(|(x, _)| *x).fold(std::f64::MIN, f64::max);
+    let min_y = pts.iter().map(|(_, y)| *y).fold(std::f64::MAX, f64::min);
+    let max_y = pts.iter().map(|(_, y)| *y).fold(std::f64::MIN, f64::max);
+    let x1 = min_x.floor().max(0.0) as u32;
+    let x2 = (max_x.ceil() as u32).min(img.width());
+    let y1 = min_y.floor().max(0.0) as u32;
+    let y2 = (max_y.ceil() as u32).min(img.height());
+
+    for y in y1..y2 {
+        for x in x1..x2 {
+            let (px, py) = (x as f64 + 0.5, y as f64 + 0.5);
+            let w1 = edge(pts[1], pts[2], px, py);
+            let w2 = edge(pts[2], pts[0], px, py);
+            let w3 = edge(pts[0], pts[1], px, py);
+            // Triangles can be wound either way
+            let inside = if area > 0.0 {
+                w1 >= 0.0 && w2 >= 0.0 && w3 >= 0.0
+            } else {
+                w1 <= 0.0 && w2 <= 0.0 && w3 <= 0.0
+            };
+            if inside {
+                blend(img.get_pixel_mut(x, y), color(px, py));
+            }
+        }
+    }
+}
+
+// The usual "over" operator
+fn blend(pixel: &mut image::Rgba<u8>, [r, g, b, a]: [f32; 4]) {
+    let dst_a = (pixel[3] as f32) / 255.0;
+    let out_a = a + dst_a * (1.0 - a);
+    if out_a <= 0.0 {
+        return;
+    }
+    for (idx, src) in [r, g, b].iter().enumerate() {
+        let dst = (pixel[idx] as f32) / 255.0;
+        let out = (src * a + dst * dst_a * (1.0 - a)) / out_a;
+        pixel[idx] = (out * 255.0).round() as u8;
+    }
+    pixel[3] = (out_a * 255.0).round() as u8;
+}
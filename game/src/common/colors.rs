@@ -1,4 +1,5 @@
 use crate::app::App;
+use crate::colors::ColorScheme;
 use abstutil::Counter;
 use ezgui::{Color, Drawable, EventCtx, FancyColor, GeomBatch, Line, LinearGradient, Text, Widget};
 use geom::{Circle, Distance, Line, Polygon, Pt2D};
@@ -80,6 +81,13 @@ impl<'a> ColorDiscrete<'a> {
             .push(color, Circle::new(pt, Distance::meters(15.0)).to_polygon());
     }
 
+    // For things that aren't in the current map at all
+    pub fn add_polygon(&mut self, polygon: Polygon, category: &'static str) {
+        let color = self.colors[category];
+        self.unzoomed.push(color, polygon.clone());
+        self.zoomed.push(color.alpha(0.4), polygon);
+    }
+
     pub fn build(self, ctx: &mut EventCtx) -> (Drawable, Drawable, Widget) {
         let legend = self
             .categories
@@ -231,13 +239,15 @@ pub struct ColorNetwork<'a> {
 
 impl<'a> ColorNetwork<'a> {
     pub fn new(app: &'a App) -> ColorNetwork {
+        ColorNetwork::for_map(&app.primary.map, &app.cs)
+    }
+
+    // Doesn't need the rest of App, so layers can be exported without a window
+    pub fn for_map(map: &'a Map, cs: &ColorScheme) -> ColorNetwork<'a> {
         let mut unzoomed = GeomBatch::new();
-        unzoomed.push(
-            app.cs.fade_map_dark,
-            app.primary.map.get_boundary_polygon().clone(),
-        );
+        unzoomed.push(cs.fade_map_dark, map.get_boundary_polygon().clone());
         ColorNetwork {
-            map: &app.primary.map,
+            map,
             unzoomed,
             zoomed: GeomBatch::new(),
         }
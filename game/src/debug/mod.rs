@@ -1,6 +1,7 @@
 mod floodfill;
 mod objects;
 mod polygons;
+mod replay;
 
 use crate::app::{App, ShowLayers, ShowObject};
 use crate::common::{tool_panel, CommonState, ContextualActions};
@@ -15,7 +16,7 @@ use ezgui::{
 };
 use geom::Pt2D;
 use map_model::{ControlTrafficSignal, NORMAL_LANE_THICKNESS};
-use sim::{AgentID, Sim};
+use sim::{AgentID, Sim, Trajectories};
 use std::collections::HashSet;
 
 pub struct DebugMode {
@@ -61,6 +62,8 @@ impl DebugMode {
                             (hotkey(Key::Y), "load previous sim state"),
                             (hotkey(Key::U), "load next sim state"),
                             (None, "pick a savestate to load"),
+                            (None, "save recorded trajectories"),
+                            (None, "replay trajectories"),
                             (None, "find bad traffic signals"),
                         ]
                         .into_iter()
@@ -181,6 +184,20 @@ impl State for DebugMode {
                 "pick a savestate to load" => {
                     return Transition::Push(WizardState::new(Box::new(load_savestate)));
                 }
+                "save recorded trajectories" => {
+                    return Transition::Push(match app.primary.sim.get_trajectories() {
+                        Some(t) => {
+                            msg("Saved", vec![format!("Trajectories saved to {}", t.save())])
+                        }
+                        None => msg(
+                            "Error",
+                            vec!["Trajectories aren't being recorded; use --record_trajectories"],
+                        ),
+                    });
+                }
+                "replay trajectories" => {
+                    return Transition::Push(WizardState::new(Box::new(load_trajectories)));
+                }
                 "unhide everything" => {
                     self.hidden.clear();
                     app.primary.current_selection = app.calculate_current_selection(
@@ -398,6 +415,30 @@ fn load_savestate(wiz: &mut Wizard, ctx: &mut EventCtx, app: &mut App) -> Option
     Some(Transition::Pop)
 }
 
+fn load_trajectories(wiz: &mut Wizard, ctx: &mut EventCtx, app: &mut App) -> Option<Transition> {
+    let name = wiz
+        .wrap(ctx)
+        .choose_string("Replay which trajectories?", || {
+            Trajectories::list_all(app.primary.map.get_name())
+        })?;
+    let loaded = ctx.loading_screen("load trajectories", |_, mut timer| {
+        Trajectories::load(app.primary.map.get_name(), &name, &mut timer)
+    });
+    match loaded {
+        Ok(t) if !t.frames.is_empty() => {
+            Some(Transition::Replace(replay::Replay::new(ctx, app, t)))
+        }
+        Ok(_) => Some(Transition::Replace(msg(
+            "Error",
+            vec![format!("{} has no recorded frames", name)],
+        ))),
+        Err(err) => Some(Transition::Replace(msg(
+            "Error",
+            vec![format!("Couldn't load {}: {}", name, err)],
+        ))),
+    }
+}
+
 fn calc_all_routes(ctx: &EventCtx, app: &mut App) -> (usize, Drawable) {
     let agents = app.primary.sim.active_agents();
     let mut batch = GeomBatch::new();
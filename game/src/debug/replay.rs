@@ -0,0 +1,188 @@
+use crate::app::{App, ShowEverything};
+use crate::game::{DrawBaselayer, State, Transition};
+use crate::render::DrawOptions;
+use ezgui::{
+    hotkey, Btn, Choice, Composite, EventCtx, EventLoopMode, GfxCtx, HorizontalAlignment, Key,
+    Line, Outcome, Slider, Text, TextExt, VerticalAlignment, Widget,
+};
+use geom::{Duration, Time};
+use sim::{GetDrawAgents, Trajectories, TrajectoryPlayback};
+
+// Scrubs through recorded trajectories without touching the live simulation.
+pub struct Replay {
+    playback: TrajectoryPlayback,
+    time: Time,
+    playing: bool,
+    composite: Composite,
+}
+
+impl Replay {
+    pub fn new(ctx: &mut EventCtx, app: &App, trajectories: Trajectories) -> Box<dyn State> {
+        // The live sim may have drawn agents at the same time
+        app.primary.draw_map.agents.borrow_mut().invalidate();
+
+        let playback = TrajectoryPlayback::new(trajectories);
+        let time = playback.trajectories.frames[0].time;
+        let mut replay = Replay {
+            composite: make_panel(ctx, app, &playback, false, 60.0),
+            playback,
+            time,
+            playing: false,
+        };
+        replay.set_time(ctx, time);
+        Box::new(replay)
+    }
+
+    fn set_time(&mut self, ctx: &mut EventCtx, time: Time) {
+        self.time = time;
+        self.playback.set_time(time);
+        self.composite.replace(
+            ctx,
+            "time",
+            Line(self.time.ampm_tostring())
+                .draw(ctx)
+                .named("time")
+                .margin_right(10),
+        );
+    }
+
+    fn percent(&self) -> f64 {
+        let start = self.playback.trajectories.frames[0].time;
+        let total = self.playback.trajectories.end_time() - start;
+        if total == Duration::ZERO {
+            return 0.0;
+        }
+        (self.time - start) / total
+    }
+}
+
+impl State for Replay {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        let speed: f64 = self.composite.dropdown_value("speed");
+        match self.composite.event(ctx) {
+            Some(Outcome::Clicked(x)) => match x.as_ref() {
+                "close" => {
+                    app.primary.draw_map.agents.borrow_mut().invalidate();
+                    return Transition::Pop;
+                }
+                "play" | "pause" => {
+                    self.playing = !self.playing;
+                    let pct = self.percent();
+                    self.composite = make_panel(ctx, app, &self.playback, self.playing, speed);
+                    self.composite.slider_mut("slider").set_percent(ctx, pct);
+                    let time = self.time;
+                    self.set_time(ctx, time);
+                }
+                _ => unreachable!(),
+            },
+            None => {}
+        }
+
+        let start = self.playback.trajectories.frames[0].time;
+        let end = self.playback.trajectories.end_time();
+        if self.playing {
+            if let Some(real_dt) = ctx.input.nonblocking_is_update_event() {
+                ctx.input.use_update_event();
+                let mut time = self.time + speed * real_dt;
+                if time >= end {
+                    time = end;
+                    self.playing = false;
+                }
+                self.set_time(ctx, time);
+                let pct = self.percent();
+                self.composite.slider_mut("slider").set_percent(ctx, pct);
+            }
+        } else {
+            // Dragging the slider
+            let pct = self.composite.slider("slider").get_percent();
+            if (pct - self.percent()).abs() > 0.001 {
+                self.set_time(ctx, start + pct * (end - start));
+            }
+        }
+
+        if self.playing {
+            Transition::KeepWithMode(EventLoopMode::Animation)
+        } else {
+            Transition::Keep
+        }
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::Custom
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        app.draw(
+            g,
+            DrawOptions::new(),
+            &self.playback,
+            &ShowEverything::new(),
+        );
+        self.composite.draw(g);
+    }
+}
+
+fn make_panel(
+    ctx: &mut EventCtx,
+    app: &App,
+    playback: &TrajectoryPlayback,
+    playing: bool,
+    speed: f64,
+) -> Composite {
+    let t = &playback.trajectories;
+    Composite::new(
+        Widget::col(vec![
+            Widget::row(vec![
+                Line(format!("Replay of {}", t.run_name))
+                    .small_heading()
+                    .draw(ctx)
+                    .margin(5),
+                Btn::text_fg("X")
+                    .build(ctx, "close", hotkey(Key::Escape))
+                    .align_right(),
+            ]),
+            Text::from(
+                Line(format!(
+                    "Recorded on \"{}\", one frame every {}",
+                    t.edits_name, t.interval
+                ))
+                .secondary(),
+            )
+            .draw(ctx)
+            .margin_below(5),
+            Widget::row(vec![
+                if playing {
+                    Btn::text_fg("pause").build_def(ctx, hotkey(Key::Space))
+                } else {
+                    Btn::text_fg("play").build_def(ctx, hotkey(Key::Space))
+                }
+                .margin_right(10),
+                Line(playback.time().ampm_tostring())
+                    .draw(ctx)
+                    .named("time")
+                    .margin_right(10),
+                Widget::dropdown(
+                    ctx,
+                    "speed",
+                    speed,
+                    vec![
+                        Choice::new("1x", 1.0),
+                        Choice::new("10x", 10.0),
+                        Choice::new("60x", 60.0),
+                        Choice::new("600x", 600.0),
+                    ],
+                ),
+            ])
+            .margin_below(5),
+            Slider::horizontal(ctx, 0.2 * ctx.canvas.window_width, 25.0, 0.0)
+                .named("slider")
+                .centered_horiz(),
+        ])
+        .bg(app.cs.panel_bg)
+        .padding(5),
+    )
+    .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
+    .build(ctx)
+}
@@ -2,7 +2,7 @@ use crate::app::App;
 use crate::common::{ColorDiscrete, CommonState};
 use crate::devtools::blocks::BlockMap;
 use crate::devtools::destinations::PopularDestinations;
-use crate::game::{State, Transition};
+use crate::game::{msg, State, Transition};
 use abstutil::prettyprint_usize;
 use ezgui::{
     hotkey, Btn, Color, Composite, Drawable, EventCtx, GfxCtx, HorizontalAlignment, Key, Line,
@@ -59,6 +59,7 @@ impl ScenarioManager {
                     ]),
                     Btn::text_fg("block map").build_def(ctx, hotkey(Key::B)),
                     Btn::text_fg("popular destinations").build_def(ctx, hotkey(Key::D)),
+                    Btn::text_fg("export to MATSim").build_def(ctx, None),
                     Text::from_multiline(vec![
                         Line(format!(
                             "{} people",
@@ -103,6 +104,19 @@ impl State for ScenarioManager {
                 "popular destinations" => {
                     return Transition::Push(PopularDestinations::new(ctx, app, &self.scenario));
                 }
+                "export to MATSim" => {
+                    let path = format!(
+                        "{}/{}_plans.xml",
+                        abstutil::path_exports(app.primary.map.get_name()),
+                        self.scenario.scenario_name
+                    );
+                    return Transition::Push(
+                        match sim::export_matsim_plans(&self.scenario, &app.primary.map, &path) {
+                            Ok(()) => msg("Exported", vec![format!("Wrote {}", path)]),
+                            Err(err) => msg("Export failed", vec![err.to_string()]),
+                        },
+                    );
+                }
                 _ => unreachable!(),
             },
             None => {}
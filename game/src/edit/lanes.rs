@@ -1,15 +1,18 @@
 use crate::app::App;
 use crate::common::CommonState;
-use crate::edit::{apply_map_edits, can_edit_lane, change_speed_limit, maybe_edit_intersection};
+use crate::edit::{
+    apply_map_edits, can_edit_lane, change_speed_limit, change_toll, maybe_edit_intersection,
+};
 use crate::game::{msg, State, Transition};
 use crate::helpers::ID;
 use crate::render::Renderable;
 use crate::sandbox::GameplayMode;
 use ezgui::{
-    hotkey, Btn, Color, Composite, EventCtx, GfxCtx, HorizontalAlignment, Key, Outcome,
+    hotkey, Btn, Choice, Color, Composite, EventCtx, GfxCtx, HorizontalAlignment, Key, Outcome,
     RewriteColor, TextExt, VerticalAlignment, Widget,
 };
-use map_model::{EditCmd, LaneID, LaneType, Map};
+use geom::Distance;
+use map_model::{EditCmd, Lane, LaneID, LaneType, Map};
 use std::collections::BTreeSet;
 
 pub struct LaneEditor {
@@ -74,17 +77,34 @@ impl LaneEditor {
         }
 
         let parent = app.primary.map.get_parent(l);
+        let lane = app.primary.map.get_l(l);
         let col = vec![
             format!("Convert this lane of {} to what type?", parent.get_name())
                 .draw_text(ctx)
                 .centered_horiz(),
             Widget::row(row).centered().margin_below(5),
             change_speed_limit(ctx, parent.speed_limit).margin_below(5),
+            change_toll(
+                ctx,
+                app.primary.map.get_pricing().get_toll(parent.id).cloned(),
+            )
+            .margin_below(5),
+            if lane.is_driving() || lane.is_bus() {
+                change_turn_pocket(ctx, lane).margin_below(5)
+            } else {
+                Widget::nothing()
+            },
             Widget::row(vec![
                 Btn::text_fg("Finish").build_def(ctx, hotkey(Key::Escape)),
                 // TODO Handle reverting speed limit too...
                 if app.primary.map.get_edits().original_lts.contains_key(&l)
                     || app.primary.map.get_edits().reversed_lanes.contains(&l)
+                    || app
+                        .primary
+                        .map
+                        .get_edits()
+                        .original_pockets
+                        .contains_key(&l)
                 {
                     Btn::text_fg("Revert").build_def(ctx, hotkey(Key::R))
                 } else {
@@ -165,6 +185,12 @@ impl State for LaneEditor {
                         // TODO It's hard to revert both changes at once.
                         if let Some(lt) = map.get_edits().original_lts.get(&self.l) {
                             try_change_lane_type(self.l, *lt, map)
+                        } else if let Some(len) = map.get_edits().original_pockets.get(&self.l) {
+                            Ok(EditCmd::ChangeTurnPocket {
+                                id: self.l,
+                                new: *len,
+                                old: map.get_l(self.l).pocket_length,
+                            })
                         } else {
                             try_reverse(self.l, map)
                         }
@@ -207,6 +233,46 @@ impl State for LaneEditor {
                         self.mode.clone(),
                     )));
                 }
+
+                let parent = app.primary.map.get_parent(self.l);
+                let new = self.composite.dropdown_value("toll");
+                let old = app.primary.map.get_pricing().get_toll(parent.id).cloned();
+                if new != old {
+                    let mut edits = app.primary.map.get_edits().clone();
+                    edits.commands.push(EditCmd::ChangeToll {
+                        id: parent.id,
+                        new,
+                        old,
+                    });
+                    apply_map_edits(ctx, app, edits);
+                    return Transition::Replace(Box::new(LaneEditor::new(
+                        ctx,
+                        app,
+                        self.l,
+                        self.mode.clone(),
+                    )));
+                }
+
+                let lane = app.primary.map.get_l(self.l);
+                if lane.is_driving() || lane.is_bus() {
+                    let new = self.composite.dropdown_value("turn pocket");
+                    let old = lane.pocket_length;
+                    if new != old {
+                        let mut edits = app.primary.map.get_edits().clone();
+                        edits.commands.push(EditCmd::ChangeTurnPocket {
+                            id: self.l,
+                            new,
+                            old,
+                        });
+                        apply_map_edits(ctx, app, edits);
+                        return Transition::Replace(Box::new(LaneEditor::new(
+                            ctx,
+                            app,
+                            self.l,
+                            self.mode.clone(),
+                        )));
+                    }
+                }
             }
         }
 
@@ -226,6 +292,29 @@ impl State for LaneEditor {
     }
 }
 
+fn change_turn_pocket(ctx: &mut EventCtx, lane: &Lane) -> Widget {
+    let mut choices = vec![Choice::new("whole road", None)];
+    for meters in vec![15.0, 30.0, 45.0, 60.0, 90.0] {
+        let len = Distance::meters(meters);
+        if len < lane.length() {
+            choices.push(Choice::new(format!("last {}", len), Some(len)));
+        }
+    }
+    if let Some(len) = lane.pocket_length {
+        if !choices.iter().any(|c| c.data == Some(len)) {
+            choices.push(Choice::new(format!("last {}", len), Some(len)));
+        }
+    }
+
+    Widget::row(vec![
+        "Lane starts mid-block:"
+            .draw_text(ctx)
+            .centered_vert()
+            .margin_right(15),
+        Widget::dropdown(ctx, "turn pocket", lane.pocket_length, choices),
+    ])
+}
+
 fn can_change_lane_type(l: LaneID, new_lt: LaneType, map: &Map) -> Option<String> {
     let r = map.get_parent(l);
     let (fwds, idx) = r.dir_and_offset(l);
@@ -22,10 +22,10 @@ use ezgui::{
     Key, Line, Outcome, PersistentSplit, RewriteColor, ScreenRectangle, Text, TextExt,
     VerticalAlignment, Widget, WrappedWizard,
 };
-use geom::Speed;
+use geom::{Duration, Speed, Time};
 use map_model::{
     connectivity, EditCmd, EditIntersection, IntersectionID, LaneID, LaneType, MapEdits,
-    PathConstraints, PermanentMapEdits,
+    PathConstraints, PermanentMapEdits, PriceSchedule,
 };
 use sim::DontDrawAgents;
 use std::collections::BTreeSet;
@@ -181,22 +181,29 @@ impl State for EditMode {
                 }
                 "undo" => {
                     let mut edits = app.primary.map.get_edits().clone();
-                    let id = cmd_to_id(&edits.commands.pop().unwrap());
+                    let maybe_id = cmd_to_id(&edits.commands.pop().unwrap());
                     apply_map_edits(ctx, app, edits);
-                    return Transition::Push(Warping::new(
-                        ctx,
-                        id.canonical_point(&app.primary).unwrap(),
-                        Some(10.0),
-                        Some(id),
-                        &mut app.primary,
-                    ));
+                    if let Some(id) = maybe_id {
+                        return Transition::Push(Warping::new(
+                            ctx,
+                            id.canonical_point(&app.primary).unwrap(),
+                            Some(10.0),
+                            Some(id),
+                            &mut app.primary,
+                        ));
+                    }
                 }
                 x => {
                     let idx = x["most recent change #".len()..].parse::<usize>().unwrap();
-                    let id = cmd_to_id(
+                    let id = match cmd_to_id(
                         &app.primary.map.get_edits().commands
                             [app.primary.map.get_edits().commands.len() - idx],
-                    );
+                    ) {
+                        Some(id) => id,
+                        None => {
+                            return Transition::Keep;
+                        }
+                    };
                     return Transition::Push(Warping::new(
                         ctx,
                         id.canonical_point(&app.primary).unwrap(),
@@ -351,22 +358,42 @@ fn make_load_edits(app: &App, btn: ScreenRectangle, mode: GameplayMode) -> Box<d
                     .chain(abstutil::load_all_objects::<PermanentMapEdits>(
                         "../data/system/proposals".to_string(),
                     ))
-                    .filter_map(|(path, perma)| {
-                        PermanentMapEdits::from_permanent(perma, &app.primary.map)
-                            .map(|edits| (path, edits))
-                            .ok()
+                    .filter(|(_, perma)| &perma.map_name == app.primary.map.get_name())
+                    .map(|(path, perma)| {
+                        // Edits made before the map was last regenerated might not match
+                        // anymore. Do the best we can, and explain what changed.
+                        match PermanentMapEdits::from_permanent(perma.clone(), &app.primary.map) {
+                            Ok(edits) => (path, (edits, Vec::new())),
+                            Err(_) => (
+                                format!("{} (needs migrating)", path),
+                                PermanentMapEdits::migrate(perma, &app.primary.map),
+                            ),
+                        }
                     })
-                    .filter(|(_, edits)| {
+                    .filter(|(_, (edits, _))| {
                         mode.allows(edits) && edits.edits_name != current_edits_name
                     })
                     .collect(),
                 );
-                list.push(Choice::new("start over with blank edits", MapEdits::new()));
+                list.push(Choice::new(
+                    "start over with blank edits",
+                    (MapEdits::new(), Vec::new()),
+                ));
                 list
             },
         )?;
+        let (new_edits, report) = new_edits;
         apply_map_edits(ctx, app, new_edits);
-        Some(Transition::Pop)
+        if report.is_empty() {
+            Some(Transition::Pop)
+        } else {
+            let mut lines = vec![
+                "These edits were made for an older version of the map.".to_string(),
+                "Save them to keep these changes:".to_string(),
+            ];
+            lines.extend(report);
+            Some(Transition::Replace(msg("Migrated edits", lines)))
+        }
     }))
 }
 
@@ -592,6 +619,43 @@ pub fn change_speed_limit(ctx: &mut EventCtx, default: Speed) -> Widget {
     ])
 }
 
+pub fn change_toll(ctx: &mut EventCtx, current: Option<PriceSchedule>) -> Widget {
+    let rush_hour = |price| PriceSchedule {
+        prices: vec![
+            (Time::START_OF_DAY + Duration::hours(7), price),
+            (Time::START_OF_DAY + Duration::hours(10), 0.0),
+            (Time::START_OF_DAY + Duration::hours(16), price),
+            (Time::START_OF_DAY + Duration::hours(19), 0.0),
+        ],
+    };
+    let mut choices = vec![Choice::new("none", None)];
+    for price in vec![1.0, 2.0, 5.0] {
+        choices.push(Choice::new(
+            format!("${:.2}", price),
+            Some(PriceSchedule::flat(price)),
+        ));
+    }
+    for price in vec![2.0, 5.0] {
+        choices.push(Choice::new(
+            format!("${:.2} at rush hour", price),
+            Some(rush_hour(price)),
+        ));
+    }
+    if let Some(ref schedule) = current {
+        if !choices.iter().any(|c| c.data == current) {
+            choices.push(Choice::new(schedule.describe(), current.clone()));
+        }
+    }
+
+    Widget::row(vec![
+        "Toll for cars:"
+            .draw_text(ctx)
+            .centered_vert()
+            .margin_right(15),
+        Widget::dropdown(ctx, "toll", current, choices),
+    ])
+}
+
 pub fn maybe_edit_intersection(
     ctx: &mut EventCtx,
     app: &mut App,
@@ -671,6 +735,10 @@ fn make_changelist(ctx: &mut EventCtx, app: &App) -> Composite {
         Text::from_multiline(vec![
             Line(format!("{} lane types changed", edits.original_lts.len())),
             Line(format!("{} lanes reversed", edits.reversed_lanes.len())),
+            Line(format!(
+                "{} turn pockets changed",
+                edits.original_pockets.len()
+            )),
             Line(format!(
                 "{} speed limits changed",
                 edits.changed_speed_limits.len()
@@ -702,11 +770,15 @@ fn make_changelist(ctx: &mut EventCtx, app: &App) -> Composite {
         .build(ctx)
 }
 
-fn cmd_to_id(cmd: &EditCmd) -> ID {
+// Cordons cover an area, so there's nothing in particular to point at
+fn cmd_to_id(cmd: &EditCmd) -> Option<ID> {
     match cmd {
-        EditCmd::ChangeLaneType { id, .. } => ID::Lane(*id),
-        EditCmd::ReverseLane { l, .. } => ID::Lane(*l),
-        EditCmd::ChangeSpeedLimit { id, .. } => ID::Road(*id),
-        EditCmd::ChangeIntersection { i, .. } => ID::Intersection(*i),
+        EditCmd::ChangeLaneType { id, .. } => Some(ID::Lane(*id)),
+        EditCmd::ReverseLane { l, .. } => Some(ID::Lane(*l)),
+        EditCmd::ChangeSpeedLimit { id, .. } => Some(ID::Road(*id)),
+        EditCmd::ChangeIntersection { i, .. } => Some(ID::Intersection(*i)),
+        EditCmd::ChangeTurnPocket { id, .. } => Some(ID::Lane(*id)),
+        EditCmd::ChangeToll { id, .. } => Some(ID::Road(*id)),
+        EditCmd::ChangeCordon { .. } => None,
     }
 }
@@ -12,6 +12,7 @@ use ezgui::{
 use geom::Polygon;
 use map_model::{
     ControlStopSign, ControlTrafficSignal, EditCmd, EditIntersection, IntersectionID, RoadID,
+    SignType,
 };
 use std::collections::HashMap;
 
@@ -49,7 +50,11 @@ impl StopSignEditor {
 
         let composite = Composite::new(
             Widget::col(vec![
-                "Stop sign editor".draw_text(ctx),
+                if app.primary.map.get_stop_sign(id).roundabout {
+                    "Roundabout editor".draw_text(ctx)
+                } else {
+                    "Stop sign editor".draw_text(ctx)
+                },
                 if ControlStopSign::new(&app.primary.map, id)
                     != app.primary.map.get_stop_sign(id).clone()
                 {
@@ -59,6 +64,11 @@ impl StopSignEditor {
                 },
                 Btn::text_fg("close intersection for construction").build_def(ctx, hotkey(Key::C)),
                 Btn::text_fg("convert to traffic signal").build_def(ctx, None),
+                if app.primary.map.get_stop_sign(id).roundabout {
+                    Btn::text_fg("convert to roundabout").inactive(ctx)
+                } else {
+                    Btn::text_fg("convert to roundabout").build_def(ctx, None)
+                },
                 Btn::text_fg("Finish").build_def(ctx, hotkey(Key::Escape)),
             ])
             .bg(app.cs.panel_bg)
@@ -95,13 +105,13 @@ impl State for StopSignEditor {
 
         if let Some(r) = self.selected_sign {
             let mut sign = app.primary.map.get_stop_sign(self.id).clone();
-            let label = if sign.roads[&r].must_stop {
-                "remove stop sign"
-            } else {
-                "add stop sign"
+            let label = match sign.roads[&r].sign_type {
+                SignType::Uncontrolled => "add stop sign",
+                SignType::Stop => "change to yield sign",
+                SignType::Yield => "remove yield sign",
             };
             if app.per_obj.left_click(ctx, label) {
-                sign.flip_sign(r);
+                sign.cycle_sign(r);
 
                 let mut edits = app.primary.map.get_edits().clone();
                 edits.commands.push(EditCmd::ChangeIntersection {
@@ -164,6 +174,24 @@ impl State for StopSignEditor {
                         self.mode.clone(),
                     )));
                 }
+                "convert to roundabout" => {
+                    let mut edits = app.primary.map.get_edits().clone();
+                    edits.commands.push(EditCmd::ChangeIntersection {
+                        i: self.id,
+                        old: app.primary.map.get_i_edit(self.id),
+                        new: EditIntersection::StopSign(ControlStopSign::roundabout(
+                            &app.primary.map,
+                            self.id,
+                        )),
+                    });
+                    apply_map_edits(ctx, app, edits);
+                    return Transition::Replace(Box::new(StopSignEditor::new(
+                        ctx,
+                        app,
+                        self.id,
+                        self.mode.clone(),
+                    )));
+                }
                 _ => unreachable!(),
             },
             None => {}
@@ -181,10 +209,10 @@ impl State for StopSignEditor {
             // The intersection will already draw enabled stop signs
             if Some(*r) == self.selected_sign {
                 batch.push(app.cs.perma_selected_object, octagon.clone());
-                if !sign.roads[r].must_stop {
+                if sign.roads[r].sign_type == SignType::Uncontrolled {
                     batch.push(app.cs.stop_sign_pole.alpha(0.6), pole.clone());
                 }
-            } else if !sign.roads[r].must_stop {
+            } else if sign.roads[r].sign_type == SignType::Uncontrolled {
                 batch.push(app.cs.stop_sign.alpha(0.6), octagon.clone());
                 batch.push(app.cs.stop_sign_pole.alpha(0.6), pole.clone());
             }
@@ -509,6 +509,7 @@ fn edit_entire_signal(app: &App, i: IntersectionID, mode: GameplayMode) -> Box<d
         let use_template = "use template";
         let all_walk = "add an all-walk phase at the end";
         let stop_sign = "convert to stop signs";
+        let roundabout = "convert to roundabout";
         let close = "close intersection for construction";
         let offset = "edit signal offset";
         let reset = "reset to default";
@@ -520,6 +521,7 @@ fn edit_entire_signal(app: &App, i: IntersectionID, mode: GameplayMode) -> Box<d
         // TODO Conflating stop signs and construction here
         if mode.can_edit_stop_signs() {
             choices.push(stop_sign);
+            choices.push(roundabout);
             choices.push(close);
         }
         choices.push(offset);
@@ -576,6 +578,24 @@ fn edit_entire_signal(app: &App, i: IntersectionID, mode: GameplayMode) -> Box<d
                     mode.clone(),
                 ))))
             }
+            x if x == roundabout => {
+                let mut edits = app.primary.map.get_edits().clone();
+                edits.commands.push(EditCmd::ChangeIntersection {
+                    i,
+                    old: app.primary.map.get_i_edit(i),
+                    new: EditIntersection::StopSign(ControlStopSign::roundabout(
+                        &app.primary.map,
+                        i,
+                    )),
+                });
+                apply_map_edits(ctx, app, edits);
+                Some(Transition::PopThenReplace(Box::new(StopSignEditor::new(
+                    ctx,
+                    app,
+                    i,
+                    mode.clone(),
+                ))))
+            }
             x if x == close => Some(close_intersection(ctx, app, i, false)),
             x if x == offset => {
                 let new_duration = wizard.input_usize_prefilled(
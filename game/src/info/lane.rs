@@ -35,6 +35,9 @@ pub fn info(ctx: &EventCtx, app: &App, details: &mut Details, id: LaneID) -> Vec
     }
 
     kv.push(("Length", l.length().describe_rounded()));
+    if let Some(len) = l.pocket_length {
+        kv.push(("Turn pocket", format!("last {}", len.describe_rounded())));
+    }
 
     rows.extend(make_table(ctx, kv));
 
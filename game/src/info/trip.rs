@@ -8,7 +8,10 @@ use ezgui::{
 use geom::{ArrowCap, Distance, Duration, PolyLine, Polygon, Pt2D, Time};
 use map_model::{Map, Path, PathStep};
 use maplit::btreemap;
-use sim::{AgentID, PersonID, TripEndpoint, TripID, TripPhase, TripPhaseType, VehicleType};
+use sim::{
+    AgentID, PersonID, TripCounterfactuals, TripEndpoint, TripID, TripPhase, TripPhaseType,
+    VehicleType,
+};
 use std::collections::BTreeMap;
 
 #[derive(Clone)]
@@ -293,9 +296,46 @@ pub fn finished(
         None,
     ));
 
+    if let Some(cf) = app.primary.sim.trip_counterfactuals(trip, &app.primary.map) {
+        col.push(what_if(ctx, &cf));
+    }
+
     Widget::col(col)
 }
 
+// How long the trip might've taken other ways, in the traffic recorded that day
+fn what_if(ctx: &mut EventCtx, cf: &TripCounterfactuals) -> Widget {
+    let mut txt = Text::from(Line("What if...").small_heading());
+    for alt in &cf.alternatives {
+        let label = if alt.mode == cf.actual_mode {
+            format!(
+                "{} by the fastest route: {}",
+                alt.mode.ongoing_verb(),
+                alt.duration
+            )
+        } else {
+            format!("{}: {}", alt.mode.ongoing_verb(), alt.duration)
+        };
+        if alt.mode != cf.actual_mode && cf.saves_a_lot(alt) {
+            txt.add(Line(label).fg(Color::GREEN));
+        } else {
+            txt.add(Line(label));
+        }
+        for note in &alt.notes {
+            txt.add(Line(format!("  {}", note)).secondary());
+        }
+    }
+    if let Some(alt) = cf.much_faster() {
+        txt.add(Line(format!(
+            "{} would've been {} faster than {}",
+            alt.mode.ongoing_verb(),
+            cf.actual_duration - alt.duration,
+            cf.actual_mode.ongoing_verb()
+        )));
+    }
+    txt.draw(ctx).margin_above(10)
+}
+
 pub fn aborted(ctx: &mut EventCtx, app: &App, trip: TripID) -> Widget {
     let (start_time, trip_start, trip_end, _) = app.primary.sim.trip_info(trip);
 
@@ -1,4 +1,5 @@
 use crate::app::App;
+use crate::colors::ColorScheme;
 use crate::common::{ColorLegend, ColorNetwork};
 use crate::layer::{Layer, LayerOutcome};
 use ezgui::{
@@ -6,6 +7,7 @@ use ezgui::{
     Line, Text, TextExt, VerticalAlignment, Widget,
 };
 use geom::{ArrowCap, Distance, PolyLine};
+use map_model::Map;
 
 pub struct Elevation {
     unzoomed: Drawable,
@@ -40,14 +42,48 @@ impl Layer for Elevation {
 
 impl Elevation {
     pub fn new(ctx: &mut EventCtx, app: &App) -> Elevation {
-        let mut colorer = ColorNetwork::new(app);
+        let (colorer, max) = Elevation::make_colorer(&app.primary.map, &app.cs);
+
+        let composite = Composite::new(
+            Widget::col(vec![
+                Widget::row(vec![
+                    Widget::draw_svg(ctx, "../data/system/assets/tools/layers.svg")
+                        .margin_right(10),
+                    "Elevation change".draw_text(ctx),
+                    Btn::plaintext("X")
+                        .build(ctx, "close", hotkey(Key::Escape))
+                        .align_right(),
+                ]),
+                Text::from_multiline(vec![
+                    Line(format!("Steepest road: {:.0}% grade", max * 100.0)),
+                    Line("Note: elevation data is currently wrong!").secondary(),
+                ])
+                .draw(ctx),
+                ColorLegend::gradient(ctx, &app.cs.good_to_bad_red, vec!["flat", "steep"]),
+            ])
+            .padding(5)
+            .bg(app.cs.panel_bg),
+        )
+        .aligned(HorizontalAlignment::Right, VerticalAlignment::Center)
+        .build(ctx);
+
+        Elevation {
+            unzoomed: ctx.upload(colorer.unzoomed),
+            zoomed: ctx.upload(colorer.zoomed),
+            composite,
+        }
+    }
+
+    // Also returns the steepest grade
+    pub fn make_colorer<'a>(map: &'a Map, cs: &ColorScheme) -> (ColorNetwork<'a>, f64) {
+        let mut colorer = ColorNetwork::for_map(map, cs);
 
         let mut max = 0.0_f64;
-        for r in app.primary.map.all_roads() {
-            let pct = r.percent_grade(&app.primary.map).abs();
+        for r in map.all_roads() {
+            let pct = r.percent_grade(map).abs();
             max = max.max(pct);
 
-            let color = app.cs.good_to_bad_red.eval(
+            let color = cs.good_to_bad_red.eval(
                 // TODO Rescale based on a reasonable steepest grade, once the data doesn't suck
                 pct.max(0.0).min(1.0),
             );
@@ -58,10 +94,10 @@ impl Elevation {
         // Time for uphill arrows!
         // TODO Draw V's, not arrows.
         // TODO Or try gradient colors.
-        for r in app.primary.map.all_roads() {
-            let (mut pl, _) = r.get_thick_polyline(&app.primary.map).unwrap();
-            let e1 = app.primary.map.get_i(r.src_i).elevation;
-            let e2 = app.primary.map.get_i(r.dst_i).elevation;
+        for r in map.all_roads() {
+            let (mut pl, _) = r.get_thick_polyline(map).unwrap();
+            let e1 = map.get_i(r.src_i).elevation;
+            let e2 = map.get_i(r.dst_i).elevation;
             if (e1 - e2).abs() / pl.length() < 0.01 {
                 // Don't bother with ~flat roads
                 continue;
@@ -91,34 +127,6 @@ impl Elevation {
             }
         }
         colorer.unzoomed.append(batch);
-
-        let composite = Composite::new(
-            Widget::col(vec![
-                Widget::row(vec![
-                    Widget::draw_svg(ctx, "../data/system/assets/tools/layers.svg")
-                        .margin_right(10),
-                    "Elevation change".draw_text(ctx),
-                    Btn::plaintext("X")
-                        .build(ctx, "close", hotkey(Key::Escape))
-                        .align_right(),
-                ]),
-                Text::from_multiline(vec![
-                    Line(format!("Steepest road: {:.0}% grade", max * 100.0)),
-                    Line("Note: elevation data is currently wrong!").secondary(),
-                ])
-                .draw(ctx),
-                ColorLegend::gradient(ctx, &app.cs.good_to_bad_red, vec!["flat", "steep"]),
-            ])
-            .padding(5)
-            .bg(app.cs.panel_bg),
-        )
-        .aligned(HorizontalAlignment::Right, VerticalAlignment::Center)
-        .build(ctx);
-
-        Elevation {
-            unzoomed: ctx.upload(colorer.unzoomed),
-            zoomed: ctx.upload(colorer.zoomed),
-            composite,
-        }
+        (colorer, max)
     }
 }
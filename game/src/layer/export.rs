@@ -0,0 +1,98 @@
+use crate::colors::{ColorScheme, ColorSchemeChoice};
+use crate::layer::elevation::Elevation;
+use crate::layer::traffic::Throughput;
+use crate::render::{DrawArea, DrawMap};
+use abstutil::Timer;
+use ezgui::GeomBatch;
+use geom::{Circle, Pt2D, Time};
+use map_model::{Map, NORMAL_LANE_THICKNESS, SIDEWALK_THICKNESS};
+use sim::{Analytics, GetDrawAgents, Trajectories, TrajectoryPlayback, VehicleType};
+
+// Renders a layer over the unzoomed map to SVG and PNG, without opening a window or needing a GPU.
+// Throughput uses the prebaked results of a scenario. Returns the two files written.
+pub fn export_layer(layer: &str, map_name: &str, scenario: &str, zoom: f64) -> Vec<String> {
+    let mut timer = Timer::new(format!("export {} layer for {}", layer, map_name));
+    let map = Map::new(abstutil::path_map(map_name), &mut timer);
+    let cs = ColorScheme::new(ColorSchemeChoice::Standard);
+    let mut batch = draw_unzoomed_map(&map, &cs, &mut timer);
+
+    let colorer = match layer {
+        "elevation" => Elevation::make_colorer(&map, &cs).0,
+        "throughput" => {
+            let stats: Analytics = abstutil::read_binary(
+                abstutil::path_prebaked_results(map_name, scenario),
+                &mut timer,
+            );
+            Throughput::make_colorer(&map, &cs, &stats)
+        }
+        x => panic!("Can't export layer {}. Choices: elevation, throughput", x),
+    };
+    batch.append(colorer.unzoomed);
+
+    let path = format!("{}/layers/{}", abstutil::path_exports(map_name), layer);
+    let svg = format!("{}.svg", path);
+    let png = format!("{}.png", path);
+    timer.start("write SVG");
+    batch.write_svg(map.get_bounds(), zoom, &svg).unwrap();
+    timer.stop("write SVG");
+    timer.start("rasterize PNG");
+    batch.write_png(map.get_bounds(), zoom, &png).unwrap();
+    timer.stop("rasterize PNG");
+    vec![svg, png]
+}
+
+// Renders every frame of recorded trajectories as a PNG over the unzoomed map, to stitch into an
+// animation with something like ffmpeg. Returns the directory of frames.
+pub fn export_replay(name: &str, map_name: &str, zoom: f64) -> String {
+    let mut timer = Timer::new(format!("export replay {} for {}", name, map_name));
+    let map = Map::new(abstutil::path_map(map_name), &mut timer);
+    let cs = ColorScheme::new(ColorSchemeChoice::Standard);
+    let trajectories = Trajectories::load(map_name, name, &mut timer).unwrap();
+    let base = draw_unzoomed_map(&map, &cs, &mut timer);
+
+    // Same sizes as the unzoomed agents in the game
+    let car_circle = Circle::new(Pt2D::new(0.0, 0.0), 4.0 * NORMAL_LANE_THICKNESS).to_polygon();
+    let ped_circle = Circle::new(Pt2D::new(0.0, 0.0), 4.0 * SIDEWALK_THICKNESS).to_polygon();
+
+    let dir = format!("{}/replays/{}", abstutil::path_exports(map_name), name);
+    let mut playback = TrajectoryPlayback::new(trajectories);
+    let times: Vec<Time> = playback
+        .trajectories
+        .frames
+        .iter()
+        .map(|f| f.time)
+        .collect();
+    timer.start_iter("render frames", times.len());
+    for (idx, time) in times.into_iter().enumerate() {
+        timer.next();
+        playback.set_time(time);
+        let mut batch = base.clone();
+        for agent in playback.get_unzoomed_agents(&map) {
+            let (color, circle) = match agent.vehicle_type {
+                Some(VehicleType::Car) => (cs.unzoomed_car, &car_circle),
+                Some(VehicleType::Bike) => (cs.unzoomed_bike, &car_circle),
+                Some(VehicleType::Bus) => (cs.unzoomed_bus, &car_circle),
+                None => (cs.unzoomed_pedestrian, &ped_circle),
+            };
+            batch.push(color, circle.translate(agent.pos.x(), agent.pos.y()));
+        }
+        batch
+            .write_png(map.get_bounds(), zoom, &format!("{}/{:05}.png", dir, idx))
+            .unwrap();
+    }
+    dir
+}
+
+fn draw_unzoomed_map(map: &Map, cs: &ColorScheme, timer: &mut Timer) -> GeomBatch {
+    let mut batch = GeomBatch::new();
+    batch.push(cs.map_background, map.get_boundary_polygon().clone());
+    for a in map.all_areas() {
+        batch.push(DrawArea::color(a.area_type, cs), a.polygon.clone());
+    }
+    batch.append(DrawMap::make_all_thick_roads(map, cs, timer));
+    batch.append(DrawMap::make_all_unzoomed_intersections(map, cs));
+    for b in map.all_buildings() {
+        batch.push(cs.building, b.polygon.clone());
+    }
+    batch
+}
@@ -7,8 +7,8 @@ use ezgui::{
     hotkey, Btn, Color, Composite, Drawable, EventCtx, GfxCtx, HorizontalAlignment, Key, Line,
     Text, TextExt, VerticalAlignment, Widget,
 };
-use geom::{Distance, Time};
-use map_model::LaneType;
+use geom::{Circle, Distance, PolyLine, Pt2D, Time};
+use map_model::{LaneType, MapDiff};
 use sim::TripMode;
 
 pub struct BikeNetwork {
@@ -254,13 +254,18 @@ impl Static {
         );
 
         let edits = app.primary.map.get_edits();
-        for l in edits.original_lts.keys().chain(&edits.reversed_lanes) {
+        for l in edits
+            .original_lts
+            .keys()
+            .chain(&edits.reversed_lanes)
+            .chain(edits.original_pockets.keys())
+        {
             colorer.add_l(*l, "modified lane/intersection");
         }
         for i in edits.original_intersections.keys() {
             colorer.add_i(*i, "modified lane/intersection");
         }
-        for r in &edits.changed_speed_limits {
+        for r in edits.changed_speed_limits.iter().chain(&edits.tolled_roads) {
             colorer.add_r(*r, "modified lane/intersection");
         }
 
@@ -273,6 +278,10 @@ impl Static {
             Text::from_multiline(vec![
                 Line(format!("{} lane types changed", edits.original_lts.len())),
                 Line(format!("{} lanes reversed", edits.reversed_lanes.len())),
+                Line(format!(
+                    "{} turn pockets changed",
+                    edits.original_pockets.len()
+                )),
                 Line(format!(
                     "{} speed limits changed",
                     edits.changed_speed_limits.len()
@@ -281,6 +290,8 @@ impl Static {
                     "{} intersections changed",
                     edits.original_intersections.len()
                 )),
+                Line(format!("{} roads tolled", edits.tolled_roads.len())),
+                Line(format!("{} cordons", edits.cordons.len())),
             ])
             .draw(ctx),
         )
@@ -327,4 +338,83 @@ impl Static {
             Widget::nothing(),
         )
     }
+
+    // Differences from the previous version of this map, as found by the importer
+    pub fn map_diff(ctx: &mut EventCtx, app: &App, diff: MapDiff) -> Static {
+        let mut colorer = ColorDiscrete::new(
+            app,
+            vec![
+                ("added", Color::GREEN),
+                ("removed", Color::RED),
+                ("changed", Color::YELLOW),
+                ("moved", Color::PURPLE),
+            ],
+        );
+
+        let map = &app.primary.map;
+        for r in &diff.added_roads {
+            if let Ok(r) = map.find_r_by_osm_id(r.osm_way_id, (r.i1.osm_node_id, r.i2.osm_node_id))
+            {
+                colorer.add_r(r, "added");
+            }
+        }
+        for (r, _) in &diff.changed_roads {
+            if let Ok(r) = map.find_r_by_osm_id(r.osm_way_id, (r.i1.osm_node_id, r.i2.osm_node_id))
+            {
+                colorer.add_r(r, "changed");
+            }
+        }
+        for (_, pts) in &diff.removed_roads {
+            let pts = pts
+                .iter()
+                .map(|pt| Pt2D::forcibly_from_gps(*pt, map.get_gps_bounds()))
+                .collect();
+            if let Some(pl) = PolyLine::maybe_new(pts) {
+                colorer.add_polygon(pl.make_polygons(Distance::meters(5.0)), "removed");
+            }
+        }
+        for i in &diff.added_intersections {
+            if let Ok(i) = map.find_i_by_osm_id(i.osm_node_id) {
+                colorer.add_i(i, "added");
+            }
+        }
+        for (i, _) in &diff.moved_intersections {
+            if let Ok(i) = map.find_i_by_osm_id(i.osm_node_id) {
+                colorer.add_i(i, "moved");
+            }
+        }
+        for (_, pt) in &diff.removed_intersections {
+            let pt = Pt2D::forcibly_from_gps(*pt, map.get_gps_bounds());
+            colorer.add_polygon(
+                Circle::new(pt, Distance::meters(10.0)).to_polygon(),
+                "removed",
+            );
+        }
+        for b in map.all_buildings() {
+            if diff
+                .added_buildings
+                .iter()
+                .any(|orig| orig.osm_way_id == b.osm_way_id)
+            {
+                colorer.add_b(b.id, "added");
+            }
+        }
+
+        let mut txt = Text::new();
+        for line in diff.summary() {
+            txt.add(Line(line));
+        }
+        for (name, _) in &diff.stale_edits {
+            txt.add(Line(format!("Edits \"{}\" need fixing", name)).secondary());
+        }
+
+        Static::new(
+            ctx,
+            app,
+            colorer,
+            "map diff",
+            "Changes since the last import".to_string(),
+            txt.draw(ctx),
+        )
+    }
 }
@@ -1,5 +1,6 @@
 pub mod bus;
 mod elevation;
+pub mod export;
 pub mod map;
 mod pandemic;
 mod parking;
@@ -10,6 +11,7 @@ use crate::app::App;
 use crate::common::HeatmapOptions;
 use crate::game::{DrawBaselayer, State, Transition};
 use crate::helpers::hotkey_btn;
+use abstutil::Timer;
 use ezgui::{hotkey, Btn, Composite, EventCtx, GfxCtx, Key, Line, Outcome, TextExt, Widget};
 
 // TODO Good ideas in
@@ -125,6 +127,9 @@ impl PickLayer {
             btn("backpressure", Key::Z),
             btn("elevation", Key::S),
         ]);
+        if abstutil::file_exists(abstutil::path_map_diff(app.primary.map.get_name())) {
+            col.push(btn("map diff", Key::F));
+        }
         if app.primary.sim.get_pandemic_model().is_some() {
             col.push(btn("pandemic model", Key::Y));
         }
@@ -177,6 +182,13 @@ impl State for PickLayer {
                 "amenities" => {
                     app.layer = Some(Box::new(map::Static::amenities(ctx, app)));
                 }
+                "map diff" => {
+                    let diff = abstutil::read_json(
+                        abstutil::path_map_diff(app.primary.map.get_name()),
+                        &mut Timer::throwaway(),
+                    );
+                    app.layer = Some(Box::new(map::Static::map_diff(ctx, app, diff)));
+                }
                 "population map" => {
                     app.layer = Some(Box::new(population::PopulationMap::new(
                         ctx,
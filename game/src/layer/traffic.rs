@@ -1,15 +1,17 @@
 use crate::app::App;
+use crate::colors::ColorScheme;
 use crate::common::{ColorLegend, ColorNetwork, ColorScale, DivergingScale};
 use crate::layer::{Layer, LayerOutcome};
 use abstutil::Counter;
 use ezgui::{
-    hotkey, Btn, Checkbox, Color, Composite, Drawable, EventCtx, GeomBatch, GfxCtx,
+    hotkey, Btn, Checkbox, Choice, Color, Composite, Drawable, EventCtx, GeomBatch, GfxCtx,
     HorizontalAlignment, Key, Line, Outcome, Text, TextExt, VerticalAlignment, Widget,
 };
 use geom::{Distance, Duration, Polygon, Time};
-use map_model::{IntersectionID, Map, Traversable};
+use map_model::{IntersectionID, Map, RoadID, Traversable};
 use maplit::btreeset;
-use std::collections::BTreeSet;
+use sim::{Analytics, RunComparison};
+use std::collections::{BTreeMap, BTreeSet};
 
 pub struct Backpressure {
     time: Time,
@@ -196,17 +198,9 @@ impl Throughput {
         .aligned(HorizontalAlignment::Right, VerticalAlignment::Center)
         .build(ctx);
 
-        let mut colorer = ColorNetwork::new(app);
-        let stats = &app.primary.sim.get_analytics();
-        colorer.ranked_roads(
-            stats.road_thruput.all_total_counts(),
-            &app.cs.good_to_bad_red,
-        );
-        colorer.ranked_intersections(
-            stats.intersection_thruput.all_total_counts(),
-            &app.cs.good_to_bad_red,
-        );
-        let (unzoomed, zoomed) = colorer.build(ctx);
+        let (unzoomed, zoomed) =
+            Throughput::make_colorer(&app.primary.map, &app.cs, app.primary.sim.get_analytics())
+                .build(ctx);
 
         Throughput {
             time: app.primary.sim.time(),
@@ -217,6 +211,16 @@ impl Throughput {
         }
     }
 
+    pub fn make_colorer<'a>(map: &'a Map, cs: &ColorScheme, stats: &Analytics) -> ColorNetwork<'a> {
+        let mut colorer = ColorNetwork::for_map(map, cs);
+        colorer.ranked_roads(stats.road_thruput.all_total_counts(), &cs.good_to_bad_red);
+        colorer.ranked_intersections(
+            stats.intersection_thruput.all_total_counts(),
+            &cs.good_to_bad_red,
+        );
+        colorer
+    }
+
     fn compare_throughput(ctx: &mut EventCtx, app: &App) -> Throughput {
         let after = app.primary.sim.get_analytics();
         let before = app.prebaked();
@@ -472,6 +476,193 @@ impl Delay {
     }
 }
 
+// Compares any two saved result sets, instead of just the current run against prebaked results
+pub struct RunDifference {
+    comparison: RunComparison,
+    metric: DiffMetric,
+    unzoomed: Drawable,
+    zoomed: Drawable,
+    composite: Composite,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum DiffMetric {
+    Throughput,
+    Delay,
+    Parking,
+}
+
+impl Layer for RunDifference {
+    fn name(&self) -> Option<&'static str> {
+        Some("run difference")
+    }
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        minimap: &Composite,
+    ) -> Option<LayerOutcome> {
+        self.composite.align_above(ctx, minimap);
+        match self.composite.event(ctx) {
+            Some(Outcome::Clicked(x)) => match x.as_ref() {
+                "close" => {
+                    return Some(LayerOutcome::Close);
+                }
+                _ => unreachable!(),
+            },
+            None => {
+                let new_metric = self.composite.dropdown_value("metric");
+                if new_metric != self.metric {
+                    let (unzoomed, zoomed, composite) =
+                        RunDifference::build(ctx, app, &self.comparison, new_metric);
+                    self.metric = new_metric;
+                    self.unzoomed = unzoomed;
+                    self.zoomed = zoomed;
+                    self.composite = composite;
+                    self.composite.align_above(ctx, minimap);
+                }
+            }
+        }
+        None
+    }
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        self.composite.draw(g);
+        if g.canvas.cam_zoom < app.opts.min_zoom_for_detail {
+            g.redraw(&self.unzoomed);
+        } else {
+            g.redraw(&self.zoomed);
+        }
+    }
+    fn draw_minimap(&self, g: &mut GfxCtx) {
+        g.redraw(&self.unzoomed);
+    }
+}
+
+impl RunDifference {
+    pub fn new(ctx: &mut EventCtx, app: &App, comparison: RunComparison) -> RunDifference {
+        let metric = DiffMetric::Throughput;
+        let (unzoomed, zoomed, composite) = RunDifference::build(ctx, app, &comparison, metric);
+        RunDifference {
+            comparison,
+            metric,
+            unzoomed,
+            zoomed,
+            composite,
+        }
+    }
+
+    fn build(
+        ctx: &mut EventCtx,
+        app: &App,
+        comparison: &RunComparison,
+        metric: DiffMetric,
+    ) -> (Drawable, Drawable, Composite) {
+        let mut colorer = ColorNetwork::new(app);
+        let legend = match metric {
+            DiffMetric::Throughput | DiffMetric::Delay => {
+                // Same scale as comparing against prebaked results
+                let scale =
+                    DivergingScale::new(Color::hex("#5D9630"), Color::WHITE, Color::hex("#A32015"))
+                        .range(0.0, 2.0)
+                        .ignore(0.7, 1.3);
+                if metric == DiffMetric::Throughput {
+                    for (r, before, after) in &comparison.road_thruput {
+                        if let Some(c) = scale.eval(ratio(*before as f64, *after as f64)) {
+                            colorer.add_r(*r, c);
+                        }
+                    }
+                    scale.make_legend(ctx, vec!["less traffic", "same", "more"])
+                } else {
+                    for (i, before, after) in &comparison.intersection_delay {
+                        if let Some(c) =
+                            scale.eval(ratio(before.inner_seconds(), after.inner_seconds()))
+                        {
+                            colorer.add_i(*i, c);
+                        }
+                    }
+                    scale.make_legend(ctx, vec!["less delay", "same", "more"])
+                }
+            }
+            DiffMetric::Parking => {
+                // Absolute differences; a few cars matter on a small lane
+                let scale =
+                    DivergingScale::new(Color::hex("#5D9630"), Color::WHITE, Color::hex("#A32015"))
+                        .range(-20.0, 20.0);
+                let mut per_road: BTreeMap<RoadID, isize> = BTreeMap::new();
+                for (l, before, after) in &comparison.parking_lanes {
+                    *per_road
+                        .entry(app.primary.map.get_l(*l).parent)
+                        .or_insert(0) += after - before;
+                }
+                for (r, diff) in per_road {
+                    if diff != 0 {
+                        colorer.add_r(r, scale.eval(diff as f64).unwrap());
+                    }
+                }
+                for (pl, before, after) in &comparison.parking_lots {
+                    colorer.add_pl(*pl, scale.eval((after - before) as f64).unwrap());
+                }
+                scale.make_legend(ctx, vec!["fewer parked cars", "same", "more"])
+            }
+        };
+
+        let composite = Composite::new(
+            Widget::col(vec![
+                Widget::row(vec![
+                    Widget::draw_svg(ctx, "../data/system/assets/tools/layers.svg")
+                        .margin_right(10),
+                    "Run difference".draw_text(ctx),
+                    Btn::plaintext("X")
+                        .build(ctx, "close", hotkey(Key::Escape))
+                        .align_right(),
+                ]),
+                Text::from(
+                    Line(format!(
+                        "\"{}\" compared to \"{}\", until {}",
+                        comparison.experiment, comparison.baseline, comparison.time
+                    ))
+                    .secondary(),
+                )
+                .wrap_to_pct(ctx, 15)
+                .draw(ctx),
+                Widget::dropdown(
+                    ctx,
+                    "metric",
+                    metric,
+                    vec![
+                        Choice::new("throughput", DiffMetric::Throughput),
+                        Choice::new("intersection delay", DiffMetric::Delay),
+                        Choice::new("parking occupancy", DiffMetric::Parking),
+                    ],
+                )
+                .margin_below(5),
+                legend,
+            ])
+            .padding(5)
+            .bg(app.cs.panel_bg),
+        )
+        .aligned(HorizontalAlignment::Right, VerticalAlignment::Center)
+        .build(ctx);
+        let (unzoomed, zoomed) = colorer.build(ctx);
+
+        (unzoomed, zoomed, composite)
+    }
+}
+
+// after / before, but something appearing from nothing counts as the biggest increase on the
+// (0, 2) scale
+fn ratio(before: f64, after: f64) -> f64 {
+    if before == 0.0 {
+        if after == 0.0 {
+            1.0
+        } else {
+            2.0
+        }
+    } else {
+        after / before
+    }
+}
+
 pub struct TrafficJams {
     time: Time,
     unzoomed: Drawable,
@@ -27,6 +27,34 @@ fn main() {
         challenges::prebake_all();
         return;
     }
+    // Like --export_layer=elevation --map=montlake. Works without a display.
+    if let Some(layer) = args.optional("--export_layer") {
+        let map = args.required("--map");
+        let scenario = args
+            .optional("--scenario")
+            .unwrap_or_else(|| "weekday".to_string());
+        let zoom = args
+            .optional_parse("--zoom", |s| s.parse::<f64>())
+            .unwrap_or(1.0);
+        args.done();
+        for path in layer::export::export_layer(&layer, &map, &scenario, zoom) {
+            println!("Wrote {}", path);
+        }
+        return;
+    }
+    // Like --export_replay=run_name --map=montlake, after recording with --record_trajectories
+    if let Some(name) = args.optional("--export_replay") {
+        let map = args.required("--map");
+        let zoom = args
+            .optional_parse("--zoom", |s| s.parse::<f64>())
+            .unwrap_or(1.0);
+        args.done();
+        println!(
+            "Wrote frames to {}",
+            layer::export::export_replay(&name, &map, zoom)
+        );
+        return;
+    }
 
     let mut flags = Flags {
         sim_flags: SimFlags::from_args(&mut args),
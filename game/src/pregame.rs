@@ -407,35 +407,44 @@ impl State for Proposals {
                     // Apply edits before setting up the sandbox, for simplicity
                     let map_name = edits.map_name.clone();
                     let edits = edits.clone();
-                    let maybe_err = ctx.loading_screen("apply edits", |ctx, mut timer| {
+                    let report = ctx.loading_screen("apply edits", |ctx, mut timer| {
                         if &edits.map_name != app.primary.map.get_name() {
                             app.switch_map(ctx, abstutil::path_map(&edits.map_name));
                         }
-                        match PermanentMapEdits::from_permanent(edits, &app.primary.map) {
-                            Ok(edits) => {
-                                apply_map_edits(ctx, app, edits);
-                                app.primary
-                                    .map
-                                    .recalculate_pathfinding_after_edits(&mut timer);
-                                None
-                            }
-                            Err(err) => Some(err),
-                        }
+                        // The map might've been regenerated since the proposal was made
+                        let (edits, report) = match PermanentMapEdits::from_permanent(
+                            edits.clone(),
+                            &app.primary.map,
+                        ) {
+                            Ok(edits) => (edits, Vec::new()),
+                            Err(_) => PermanentMapEdits::migrate(edits, &app.primary.map),
+                        };
+                        apply_map_edits(ctx, app, edits);
+                        app.primary
+                            .map
+                            .recalculate_pathfinding_after_edits(&mut timer);
+                        report
                     });
-                    if let Some(err) = maybe_err {
-                        return Transition::Push(msg("Can't load proposal", vec![err]));
-                    } else {
-                        app.layer = Some(Box::new(crate::layer::map::Static::edits(ctx, app)));
-                        return Transition::Push(Box::new(SandboxMode::new(
-                            ctx,
-                            app,
-                            GameplayMode::PlayScenario(
-                                abstutil::path_map(&map_name),
-                                "weekday".to_string(),
-                                Vec::new(),
-                            ),
-                        )));
+                    app.layer = Some(Box::new(crate::layer::map::Static::edits(ctx, app)));
+                    let sandbox = Box::new(SandboxMode::new(
+                        ctx,
+                        app,
+                        GameplayMode::PlayScenario(
+                            abstutil::path_map(&map_name),
+                            "weekday".to_string(),
+                            Vec::new(),
+                        ),
+                    ));
+                    if report.is_empty() {
+                        return Transition::Push(sandbox);
                     }
+                    return Transition::PushTwice(
+                        sandbox,
+                        msg(
+                            "This proposal was made for an older version of the map",
+                            report,
+                        ),
+                    );
                 }
                 "Read detailed write-up" => {
                     let link = self.proposals[self.current.as_ref().unwrap()]
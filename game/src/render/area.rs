@@ -22,6 +22,7 @@ impl DrawArea {
             AreaType::Water => cs.water,
             AreaType::PedestrianIsland => Color::grey(0.3),
             AreaType::Island => cs.map_background,
+            AreaType::Plaza => cs.sidewalk,
         }
     }
 }
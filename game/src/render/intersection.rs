@@ -10,7 +10,8 @@ use ezgui::{Color, Drawable, GeomBatch, GfxCtx, Line, Prerender, RewriteColor, T
 use geom::{Angle, ArrowCap, Distance, Line, PolyLine, Polygon, Pt2D, Time, EPSILON_DIST};
 use map_model::raw::DrivingSide;
 use map_model::{
-    Intersection, IntersectionID, IntersectionType, Map, Road, RoadWithStopSign, Turn, TurnType,
+    Intersection, IntersectionID, IntersectionType, Map, Road, RoadWithStopSign, SignType, Turn,
+    TurnType,
 };
 use std::cell::RefCell;
 
@@ -59,7 +60,7 @@ impl DrawIntersection {
             }
             IntersectionType::StopSign => {
                 for ss in map.get_stop_sign(i.id).roads.values() {
-                    if ss.must_stop {
+                    if ss.sign_type != SignType::Uncontrolled {
                         if let Some((octagon, pole)) = DrawIntersection::stop_sign_geom(ss, map) {
                             default_geom.push(cs.stop_sign, octagon);
                             default_geom.push(cs.stop_sign_pole, pole);
@@ -112,7 +113,11 @@ impl DrawIntersection {
             rightmost.width,
         );
 
-        let octagon = make_octagon(last_line.pt2(), Distance::meters(1.0), last_line.angle());
+        let sign = if ss.sign_type == SignType::Yield {
+            make_yield_triangle(last_line.pt2(), Distance::meters(1.0), last_line.angle())
+        } else {
+            make_octagon(last_line.pt2(), Distance::meters(1.0), last_line.angle())
+        };
         let pole = Line::new(
             last_line
                 .pt2()
@@ -123,7 +128,7 @@ impl DrawIntersection {
                 .project_away(Distance::meters(0.9), last_line.angle().opposite()),
         )
         .make_polygons(Distance::meters(0.3));
-        Some((octagon, pole))
+        Some((sign, pole))
     }
 }
 
@@ -322,6 +327,15 @@ fn make_octagon(center: Pt2D, radius: Distance, facing: Angle) -> Polygon {
     )
 }
 
+// Points back towards the approaching drivers
+fn make_yield_triangle(center: Pt2D, radius: Distance, facing: Angle) -> Polygon {
+    Polygon::new(
+        &(0..3)
+            .map(|i| center.project_away(radius, facing.rotate_degs(180.0 + f64::from(i * 120))))
+            .collect(),
+    )
+}
+
 pub fn make_crosswalk(batch: &mut GeomBatch, turn: &Turn, map: &Map, cs: &ColorScheme) {
     if make_rainbow_crosswalk(batch, turn, map) {
         return;
@@ -93,9 +93,20 @@ impl DrawLane {
         timer: &mut Timer,
     ) -> AlmostDrawLane {
         let road = map.get_r(lane.parent);
-        let polygon = lane.lane_center_pts.make_polygons(lane.width);
+        let polygon = lane.physical_pts().make_polygons(lane.width);
 
         let mut draw = GeomBatch::new();
+        if let Some(start) = lane.pocket_start() {
+            // Pave over the gap before the pocket opens up; the markings hatch it.
+            if start > Distance::ZERO && start < lane.length() {
+                draw.push(
+                    cs.driving_lane,
+                    lane.lane_center_pts
+                        .exact_slice(Distance::ZERO, start)
+                        .make_polygons(lane.width),
+                );
+            }
+        }
         if lane.lane_type != LaneType::LightRail {
             draw.push(
                 match lane.lane_type {
@@ -132,6 +143,7 @@ impl DrawLane {
                         cs.general_road_marking,
                         calculate_one_way_markings(lane, road),
                     );
+                    draw.extend(cs.general_road_marking, calculate_pocket_markings(lane));
                 }
                 LaneType::Biking => {}
                 LaneType::SharedLeftTurn => {
@@ -208,7 +220,7 @@ impl Renderable for DrawLane {
 
     fn get_outline(&self, map: &Map) -> Polygon {
         let lane = map.get_l(self.id);
-        lane.lane_center_pts
+        lane.physical_pts()
             .to_thick_boundary(lane.width, OUTLINE_THICKNESS)
             .unwrap_or_else(|| self.polygon.clone())
     }
@@ -346,6 +358,46 @@ fn calculate_turn_markings(map: &Map, lane: &Lane, timer: &mut Timer) -> Vec<Pol
     results
 }
 
+// Hatch the part of a turn pocket that doesn't exist yet, like the painted gore before the pocket
+// opens up.
+fn calculate_pocket_markings(lane: &Lane) -> Vec<Polygon> {
+    let mut results = Vec::new();
+    let start = match lane.pocket_start() {
+        Some(d) if d > Distance::ZERO => d,
+        _ => {
+            return results;
+        }
+    };
+
+    let thickness = Distance::meters(0.25);
+    // Reuse perp_line. Project away an arbitrary amount
+    let (pt, angle) = lane.dist_along(start);
+    results.push(
+        perp_line(
+            Line::new(pt, pt.project_away(Distance::meters(1.0), angle)),
+            lane.width,
+        )
+        .make_polygons(thickness),
+    );
+
+    let btwn = Distance::meters(3.0);
+    let mut dist = btwn;
+    while dist + btwn <= start {
+        let (pt1, angle) = lane.dist_along(dist - btwn / 2.0);
+        let (pt2, _) = lane.dist_along(dist + btwn / 2.0);
+        let perp = angle.rotate_degs(90.0);
+        results.push(
+            Line::new(
+                pt1.project_away(lane.width / 2.0, perp),
+                pt2.project_away(lane.width / 2.0, perp.opposite()),
+            )
+            .make_polygons(thickness),
+        );
+        dist += btwn;
+    }
+    results
+}
+
 fn calculate_one_way_markings(lane: &Lane, parent: &Road) -> Vec<Polygon> {
     let mut results = Vec::new();
     if parent
@@ -61,28 +61,7 @@ impl DrawMap {
         }
 
         timer.start("generate thick roads");
-        let mut road_refs: Vec<&Road> = map.all_roads().iter().collect();
-        road_refs.sort_by_key(|r| r.zorder);
-        let mut all_roads = GeomBatch::new();
-        for r in road_refs {
-            all_roads.push(
-                if r.is_light_rail() {
-                    cs.light_rail_track
-                } else if r.is_private() {
-                    cs.private_road
-                } else {
-                    osm_rank_to_color(cs, r.get_rank())
-                },
-                r.get_thick_polygon(map).get(timer),
-            );
-            /*if false {
-                all_roads.push(
-                    color,
-                    roads[r.id.0].get_outline(map),
-                );
-            }*/
-        }
-        let draw_all_thick_roads = all_roads.upload(ctx);
+        let draw_all_thick_roads = DrawMap::make_all_thick_roads(map, cs, timer).upload(ctx);
         timer.stop("generate thick roads");
 
         let almost_lanes =
@@ -113,34 +92,8 @@ impl DrawMap {
         }
 
         timer.start("generate unzoomed intersections");
-        let mut intersection_refs: Vec<&Intersection> = map.all_intersections().iter().collect();
-        intersection_refs.sort_by_key(|i| i.get_zorder(map));
-        let mut all_intersections = GeomBatch::new();
-        for i in intersection_refs {
-            // TODO Would be neat to show closed intersections here, but then edits need to
-            // regenerate this
-            if i.is_stop_sign() {
-                all_intersections.push(
-                    if i.is_light_rail(map) {
-                        cs.light_rail_track
-                    } else if i.is_private(map) {
-                        cs.private_road
-                    } else {
-                        osm_rank_to_color(cs, i.get_rank(map))
-                    },
-                    i.polygon.clone(),
-                );
-            /*if false {
-                all_intersections.push(
-                    color,
-                    intersections[i.id.0].get_outline(map),
-                );
-            }*/
-            } else {
-                all_intersections.push(cs.unzoomed_interesting_intersection, i.polygon.clone());
-            }
-        }
-        let draw_all_unzoomed_intersections = all_intersections.upload(ctx);
+        let draw_all_unzoomed_intersections =
+            DrawMap::make_all_unzoomed_intersections(map, cs).upload(ctx);
         timer.stop("generate unzoomed intersections");
 
         let mut buildings: Vec<DrawBuilding> = Vec::new();
@@ -258,6 +211,63 @@ impl DrawMap {
         }
     }
 
+    // These don't need a GPU, so they can also be used to export figures without a window
+    pub fn make_all_thick_roads(map: &Map, cs: &ColorScheme, timer: &mut Timer) -> GeomBatch {
+        let mut road_refs: Vec<&Road> = map.all_roads().iter().collect();
+        road_refs.sort_by_key(|r| r.zorder);
+        let mut all_roads = GeomBatch::new();
+        for r in road_refs {
+            all_roads.push(
+                if r.is_light_rail() {
+                    cs.light_rail_track
+                } else if r.is_private() {
+                    cs.private_road
+                } else {
+                    osm_rank_to_color(cs, r.get_rank())
+                },
+                r.get_thick_polygon(map).get(timer),
+            );
+            /*if false {
+                all_roads.push(
+                    color,
+                    roads[r.id.0].get_outline(map),
+                );
+            }*/
+        }
+        all_roads
+    }
+
+    pub fn make_all_unzoomed_intersections(map: &Map, cs: &ColorScheme) -> GeomBatch {
+        let mut intersection_refs: Vec<&Intersection> = map.all_intersections().iter().collect();
+        intersection_refs.sort_by_key(|i| i.get_zorder(map));
+        let mut all_intersections = GeomBatch::new();
+        for i in intersection_refs {
+            // TODO Would be neat to show closed intersections here, but then edits need to
+            // regenerate this
+            if i.is_stop_sign() {
+                all_intersections.push(
+                    if i.is_light_rail(map) {
+                        cs.light_rail_track
+                    } else if i.is_private(map) {
+                        cs.private_road
+                    } else {
+                        osm_rank_to_color(cs, i.get_rank(map))
+                    },
+                    i.polygon.clone(),
+                );
+            /*if false {
+                all_intersections.push(
+                    color,
+                    intersections[i.id.0].get_outline(map),
+                );
+            }*/
+            } else {
+                all_intersections.push(cs.unzoomed_interesting_intersection, i.polygon.clone());
+            }
+        }
+        all_intersections
+    }
+
     // The alt to these is implementing std::ops::Index, but that's way more verbose!
     pub fn get_r(&self, id: RoadID) -> &DrawRoad {
         &self.roads[id.0]
@@ -369,6 +379,13 @@ impl AgentCache {
             .collect()
     }
 
+    // Needed when switching between sources of agents that might share the same time
+    pub fn invalidate(&mut self) {
+        self.time = None;
+        self.agents_per_on.clear();
+        self.unzoomed = None;
+    }
+
     pub fn populate_if_needed(
         &mut self,
         on: Traversable,
@@ -0,0 +1,294 @@
+use crate::app::App;
+use crate::game::{msg, DrawBaselayer, State, Transition, WizardState};
+use crate::layer::traffic::RunDifference;
+use crate::sandbox::dashboards::DashTab;
+use abstutil::{prettyprint_usize, Timer};
+use ezgui::{Btn, Choice, Composite, EventCtx, GfxCtx, Line, Outcome, Text, TextExt, Widget};
+use geom::Duration;
+use sim::{ResultSet, RunComparison, TripMode};
+
+// How many of the biggest changes to list per category
+const TOP_N: usize = 10;
+
+pub struct CompareRuns {
+    composite: Composite,
+    baseline: Option<String>,
+    experiment: Option<String>,
+    comparison: Option<RunComparison>,
+    // Shown as soon as possible, since constructing can't push a popup
+    load_error: Option<String>,
+}
+
+impl CompareRuns {
+    pub fn new(
+        ctx: &mut EventCtx,
+        app: &App,
+        baseline: Option<String>,
+        experiment: Option<String>,
+    ) -> Box<dyn State> {
+        let map_name = app.primary.map.get_name();
+        let names = ResultSet::list_all(map_name);
+        let choices = || {
+            let mut list = vec![Choice::new("none", None)];
+            for name in &names {
+                list.push(Choice::new(name.clone(), Some(name.clone())));
+            }
+            list
+        };
+
+        let mut load_error = None;
+        let comparison = match (&baseline, &experiment) {
+            (Some(b), Some(e)) => {
+                let mut timer = Timer::throwaway();
+                match (
+                    ResultSet::load(map_name, b, &mut timer),
+                    ResultSet::load(map_name, e, &mut timer),
+                ) {
+                    (Ok(b), Ok(e)) => Some(RunComparison::new(&b, &e)),
+                    (Err(err), _) | (_, Err(err)) => {
+                        load_error = Some(format!("Couldn't load result sets: {}", err));
+                        None
+                    }
+                }
+            }
+            _ => None,
+        };
+
+        Box::new(CompareRuns {
+            composite: Composite::new(
+                Widget::col(vec![
+                    DashTab::CompareRuns.picker(ctx, app),
+                    Widget::row(vec![
+                        "Save the current run as".draw_text(ctx).margin_right(10),
+                        Widget::text_entry(
+                            ctx,
+                            app.primary.map.get_edits().edits_name.clone(),
+                            false,
+                        )
+                        .named("result name")
+                        .margin_right(10),
+                        Btn::text_fg("save current results").build_def(ctx, None),
+                    ])
+                    .margin_below(10),
+                    Widget::row(vec![
+                        "Baseline:".draw_text(ctx).margin_right(10),
+                        Widget::dropdown(ctx, "baseline", baseline.clone(), choices())
+                            .margin_right(20),
+                        "Experiment:".draw_text(ctx).margin_right(10),
+                        Widget::dropdown(ctx, "experiment", experiment.clone(), choices()),
+                    ])
+                    .margin_below(10),
+                    if let Some(ref c) = comparison {
+                        summary(ctx, app, c)
+                    } else {
+                        "Pick two saved runs to compare them".draw_text(ctx)
+                    },
+                ])
+                .bg(app.cs.panel_bg)
+                .padding(10),
+            )
+            .exact_size_percent(90, 90)
+            .build(ctx),
+            baseline,
+            experiment,
+            comparison,
+            load_error,
+        })
+    }
+}
+
+impl State for CompareRuns {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        if let Some(err) = self.load_error.take() {
+            return Transition::Push(msg("Error", vec![err]));
+        }
+
+        match self.composite.event(ctx) {
+            Some(Outcome::Clicked(x)) => match x.as_ref() {
+                "save current results" => {
+                    let name = self.composite.text_box("result name").trim().to_string();
+                    if name.is_empty() || name.contains('/') || name.contains("..") {
+                        return Transition::Push(msg(
+                            "Error",
+                            vec!["Result set names can't be blank or contain / or .."],
+                        ));
+                    }
+                    let (baseline, experiment) = (self.baseline.clone(), self.experiment.clone());
+                    if !abstutil::file_exists(abstutil::path_result_set(
+                        app.primary.map.get_name(),
+                        &name,
+                    )) {
+                        let (state, saved) = save_results(ctx, app, name, baseline, experiment);
+                        return Transition::ReplaceThenPush(state, saved);
+                    }
+                    Transition::Push(WizardState::new(Box::new(move |wiz, ctx, app| {
+                        let overwrite = "Overwrite";
+                        let cancel = "Cancel";
+                        if wiz
+                            .wrap(ctx)
+                            .choose_string(
+                                &format!("Results named {} already exist", name),
+                                || vec![overwrite, cancel],
+                            )?
+                            .as_str()
+                            == cancel
+                        {
+                            return Some(Transition::Pop);
+                        }
+                        let (state, saved) = save_results(
+                            ctx,
+                            app,
+                            name.clone(),
+                            baseline.clone(),
+                            experiment.clone(),
+                        );
+                        Some(Transition::PopThenReplaceThenPush(state, saved))
+                    })))
+                }
+                "show on map" => {
+                    let comparison = self.comparison.take().unwrap();
+                    app.layer = Some(Box::new(RunDifference::new(ctx, app, comparison)));
+                    Transition::Pop
+                }
+                _ => DashTab::CompareRuns.transition(ctx, app, &x),
+            },
+            None => {
+                let baseline: Option<String> = self.composite.dropdown_value("baseline");
+                let experiment: Option<String> = self.composite.dropdown_value("experiment");
+                if baseline != self.baseline || experiment != self.experiment {
+                    Transition::Replace(CompareRuns::new(ctx, app, baseline, experiment))
+                } else {
+                    Transition::Keep
+                }
+            }
+        }
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::Custom
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        g.clear(app.cs.grass);
+        self.composite.draw(g);
+    }
+}
+
+// Returns the refreshed dashboard and a popup saying where the results went
+fn save_results(
+    ctx: &mut EventCtx,
+    app: &App,
+    name: String,
+    baseline: Option<String>,
+    experiment: Option<String>,
+) -> (Box<dyn State>, Box<dyn State>) {
+    let path = ResultSet::new(name, &app.primary.map, &app.primary.sim).save();
+    (
+        CompareRuns::new(ctx, app, baseline, experiment),
+        msg("Saved", vec![format!("Results saved to {}", path)]),
+    )
+}
+
+fn summary(ctx: &mut EventCtx, app: &App, c: &RunComparison) -> Widget {
+    let mut mode_shift = Text::from(Line("Mode shift").small_heading());
+    for ((before, after), cnt) in &c.mode_shift {
+        if before == after {
+            continue;
+        }
+        mode_shift.add(Line(format!(
+            "{} trips: {} -> {}",
+            prettyprint_usize(*cnt),
+            describe_mode(*before),
+            describe_mode(*after)
+        )));
+    }
+    mode_shift.add(Line(format!(
+        "{} trips finished with a different mode",
+        prettyprint_usize(c.num_mode_changes())
+    )));
+
+    let mut roads = c.road_thruput.clone();
+    roads.sort_by_key(|(_, before, after)| -((*after as isize) - (*before as isize)).abs());
+    let mut thruput = Text::from(Line("Biggest throughput changes").small_heading());
+    for (r, before, after) in roads.into_iter().take(TOP_N) {
+        thruput.add(Line(format!(
+            "{}: {} -> {}",
+            app.primary.map.get_r(r).get_name(),
+            prettyprint_usize(before),
+            prettyprint_usize(after)
+        )));
+    }
+
+    let mut intersections = c.intersection_delay.clone();
+    intersections.sort_by(|(_, b1, a1), (_, b2, a2)| {
+        (*a2 - *b2)
+            .inner_seconds()
+            .abs()
+            .partial_cmp(&(*a1 - *b1).inner_seconds().abs())
+            .unwrap()
+    });
+    let (total_before, total_after) = total_delay(c);
+    let mut delay = Text::from(Line("Biggest delay changes").small_heading());
+    delay.add(Line(format!(
+        "All intersections: {} -> {}",
+        total_before, total_after
+    )));
+    for (i, before, after) in intersections.into_iter().take(TOP_N) {
+        delay.add(Line(format!(
+            "{}: {} -> {}",
+            app.primary.map.get_i(i).name(&app.primary.map),
+            before,
+            after
+        )));
+    }
+
+    let lanes: isize = c.parking_lanes.iter().map(|(_, b, a)| a - b).sum();
+    let lots: isize = c.parking_lots.iter().map(|(_, b, a)| a - b).sum();
+    let parking = Text::from_multiline(vec![
+        Line("Parking occupancy").small_heading(),
+        Line(format!(
+            "{} lanes and {} lots changed",
+            prettyprint_usize(c.parking_lanes.len()),
+            prettyprint_usize(c.parking_lots.len())
+        )),
+        Line(format!("{:+} cars parked on-street", lanes)),
+        Line(format!("{:+} cars parked in lots", lots)),
+        Line("Pricing").small_heading(),
+        Line(format!(
+            "${:.2} -> ${:.2} collected from tolls and cordons",
+            c.revenue.0, c.revenue.1
+        )),
+    ]);
+
+    Widget::col(vec![
+        Line(format!(
+            "\"{}\" compared to \"{}\", until {}",
+            c.experiment, c.baseline, c.time
+        ))
+        .draw(ctx)
+        .margin_below(10),
+        Widget::row(vec![
+            mode_shift.draw(ctx),
+            thruput.draw(ctx),
+            delay.draw(ctx),
+            parking.draw(ctx),
+        ])
+        .evenly_spaced()
+        .margin_below(10),
+        Btn::text_bg2("show on map").build_def(ctx, None),
+    ])
+}
+
+fn describe_mode(mode: Option<TripMode>) -> &'static str {
+    mode.map(|m| m.ongoing_verb()).unwrap_or("not finished")
+}
+
+fn total_delay(c: &RunComparison) -> (Duration, Duration) {
+    let mut before = Duration::ZERO;
+    let mut after = Duration::ZERO;
+    for (_, b, a) in &c.intersection_delay {
+        before += *b;
+        after += *a;
+    }
+    (before, after)
+}
@@ -4,7 +4,7 @@ use crate::game::{msg, DrawBaselayer, State, Transition};
 use crate::sandbox::dashboards::DashTab;
 use crate::sandbox::SandboxMode;
 use ezgui::{
-    Btn, Composite, EventCtx, GfxCtx, Line, LinePlot, Outcome, PlotOptions, Series, Widget,
+    Btn, Composite, EventCtx, GfxCtx, Line, LinePlot, Outcome, PlotOptions, Series, Text, Widget,
 };
 
 pub struct ActiveTraffic {
@@ -138,3 +138,73 @@ impl State for BusRoutes {
         self.composite.draw(g);
     }
 }
+
+pub struct RoadPricing {
+    composite: Composite,
+}
+
+impl RoadPricing {
+    pub fn new(ctx: &mut EventCtx, app: &App) -> Box<dyn State> {
+        let pricing = app.primary.map.get_pricing();
+        let mut txt = Text::new();
+        for (r, schedule) in pricing.all_tolls() {
+            txt.add(Line(format!(
+                "Toll on {}: {}",
+                app.primary.map.get_r(*r).get_name(),
+                schedule.describe()
+            )));
+        }
+        for cordon in pricing.all_cordons() {
+            txt.add(Line(format!(
+                "Cordon {}: {}",
+                cordon.name,
+                cordon.schedule.describe()
+            )));
+        }
+        txt.add(Line(""));
+        let before = if app.has_prebaked().is_some() {
+            Some(app.prebaked())
+        } else {
+            None
+        };
+        for line in app.primary.sim.get_analytics().pricing_report(
+            app.primary.sim.time(),
+            before,
+            &app.primary.map,
+        ) {
+            txt.add(Line(line));
+        }
+
+        Box::new(RoadPricing {
+            composite: Composite::new(
+                Widget::col(vec![
+                    DashTab::RoadPricing.picker(ctx, app),
+                    Line("Road pricing").small_heading().draw(ctx),
+                    txt.draw(ctx),
+                ])
+                .bg(app.cs.panel_bg)
+                .padding(10),
+            )
+            .exact_size_percent(90, 90)
+            .build(ctx),
+        })
+    }
+}
+
+impl State for RoadPricing {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        match self.composite.event(ctx) {
+            Some(Outcome::Clicked(x)) => DashTab::RoadPricing.transition(ctx, app, &x),
+            None => Transition::Keep,
+        }
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::Custom
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        g.clear(app.cs.grass);
+        self.composite.draw(g);
+    }
+}
@@ -1,3 +1,4 @@
+mod compare_runs;
 mod misc;
 mod parking_overhead;
 mod summaries;
@@ -16,6 +17,8 @@ pub enum DashTab {
     ParkingOverhead,
     ActiveTraffic,
     BusRoutes,
+    CompareRuns,
+    RoadPricing,
 }
 
 impl DashTab {
@@ -27,10 +30,15 @@ impl DashTab {
             ("parking overhead", DashTab::ParkingOverhead),
             ("active traffic", DashTab::ActiveTraffic),
             ("bus routes", DashTab::BusRoutes),
+            ("compare runs", DashTab::CompareRuns),
+            ("road pricing", DashTab::RoadPricing),
         ] {
             if tab == DashTab::TripSummaries && app.has_prebaked().is_none() {
                 continue;
             }
+            if tab == DashTab::RoadPricing && app.primary.map.get_pricing().is_empty() {
+                continue;
+            }
             if self == tab {
                 row.push(Btn::text_bg2(name).inactive(ctx));
             } else {
@@ -61,6 +69,10 @@ impl DashTab {
             }
             "active traffic" => Transition::Replace(misc::ActiveTraffic::new(ctx, app)),
             "bus routes" => Transition::Replace(misc::BusRoutes::new(ctx, app)),
+            "compare runs" => {
+                Transition::Replace(compare_runs::CompareRuns::new(ctx, app, None, None))
+            }
+            "road pricing" => Transition::Replace(misc::RoadPricing::new(ctx, app)),
             _ => unreachable!(),
         }
     }
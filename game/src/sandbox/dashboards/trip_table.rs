@@ -1,5 +1,5 @@
 use crate::app::App;
-use crate::game::{DrawBaselayer, State, Transition};
+use crate::game::{msg, DrawBaselayer, State, Transition};
 use crate::helpers::{cmp_duration_shorter, color_for_mode, color_for_trip_phase};
 use crate::info::{OpenTrip, Tab};
 use crate::sandbox::dashboards::DashTab;
@@ -111,6 +111,17 @@ impl State for TripTable {
                     self.opts.skip += ROWS;
                     self.recalc(ctx, app);
                 }
+                "export" => {
+                    return Transition::Push(
+                        match app.primary.sim.export_results(&app.primary.map) {
+                            Ok(dir) => msg(
+                                "Exported",
+                                vec![format!("Trips and road throughput written to {}", dir)],
+                            ),
+                            Err(err) => msg("Export failed", vec![err.to_string()]),
+                        },
+                    );
+                }
                 x => {
                     if let Ok(idx) = x.parse::<usize>() {
                         let trip = TripID(idx);
@@ -335,17 +346,20 @@ fn make(ctx: &mut EventCtx, app: &App, opts: &Options) -> Composite {
     );
     let (_, unfinished, _) = app.primary.sim.num_trips();
     col.push(
-        Text::from_multiline(vec![
-            Line(format!(
-                "{} trips aborted due to simulation glitch",
-                prettyprint_usize(aborted)
-            )),
-            Line(format!(
-                "{} unfinished trips remaining",
-                prettyprint_usize(unfinished)
-            )),
+        Widget::row(vec![
+            Text::from_multiline(vec![
+                Line(format!(
+                    "{} trips aborted due to simulation glitch",
+                    prettyprint_usize(aborted)
+                )),
+                Line(format!(
+                    "{} unfinished trips remaining",
+                    prettyprint_usize(unfinished)
+                )),
+            ])
+            .draw(ctx),
+            Btn::text_bg2("export").build_def(ctx, None).align_right(),
         ])
-        .draw(ctx)
         .margin_below(10),
     );
 
@@ -138,10 +138,18 @@ pub fn make_change_traffic(btn: ScreenRectangle, current: String) -> Box<dyn Sta
                              quiet, so you may need to fast-forward to morning rush hour. Data \
                              comes from Puget Sound Regional Council's Soundcast model.",
                         ));
-                    } else {
+                    } else if name != "synthetic" {
                         list.push(Choice::new(name.clone(), name));
                     }
                 }
+                // If the importer didn't already generate this, it'll happen on the fly
+                list.push(
+                    Choice::new("synthetic weekday traffic", "synthetic".to_string()).tooltip(
+                        "Trips will begin throughout the entire day. People live and work in \
+                         buildings based on their size and use, so this is plausible, but not \
+                         based on any real travel data.",
+                    ),
+                );
                 list.push(
                     Choice::new("random unrealistic trips", "random".to_string()).tooltip(
                         "Lots of trips will start at midnight, but not constantly appear through \
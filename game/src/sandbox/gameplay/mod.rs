@@ -114,6 +114,10 @@ impl GameplayMode {
                 ScenarioGenerator::small_run(map)
             })
             .generate(map, &mut rng, &mut Timer::new("generate scenario"))
+        } else if name == "synthetic"
+            && !abstutil::file_exists(abstutil::path_scenario(map.get_name(), &name))
+        {
+            sim::synthetic_weekday(map, &mut rng, &mut Timer::new("generate scenario"))
         } else {
             let path = abstutil::path_scenario(map.get_name(), &name);
             let mut scenario = match abstutil::maybe_read_binary(path.clone(), timer) {
@@ -126,7 +130,7 @@ impl GameplayMode {
             };
             if let GameplayMode::PlayScenario(_, _, ref modifiers) = self {
                 for m in modifiers {
-                    scenario = m.apply(scenario, &mut rng);
+                    scenario = m.apply(scenario, map, &mut rng, timer);
                 }
             }
             scenario
@@ -159,7 +163,10 @@ impl GameplayMode {
             match cmd {
                 EditCmd::ChangeLaneType { .. }
                 | EditCmd::ReverseLane { .. }
-                | EditCmd::ChangeSpeedLimit { .. } => {
+                | EditCmd::ChangeSpeedLimit { .. }
+                | EditCmd::ChangeTurnPocket { .. }
+                | EditCmd::ChangeToll { .. }
+                | EditCmd::ChangeCordon { .. } => {
                     if !self.can_edit_lanes() {
                         return false;
                     }
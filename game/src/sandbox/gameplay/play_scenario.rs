@@ -1,7 +1,7 @@
 use crate::app::App;
 use crate::common::CityPicker;
 use crate::edit::EditMode;
-use crate::game::{State, Transition, WizardState};
+use crate::game::{msg, State, Transition, WizardState};
 use crate::helpers::nice_map_name;
 use crate::sandbox::gameplay::freeform::make_change_traffic;
 use crate::sandbox::gameplay::{GameplayMode, GameplayState};
@@ -11,7 +11,8 @@ use ezgui::{
     Line, Outcome, Text, TextExt, VerticalAlignment, Widget,
 };
 use geom::Polygon;
-use sim::ScenarioModifier;
+use map_model::Map;
+use sim::{ModeShift, ResultSet, ScenarioModifier};
 
 pub struct PlayScenario {
     top_center: Composite,
@@ -83,6 +84,45 @@ impl GameplayState for PlayScenario {
                     self.scenario_name.clone(),
                     self.modifiers.clone(),
                 ))),
+                "mode shift" => {
+                    // Choose modes again on the map without any edits, and compare against that
+                    let mode = GameplayMode::PlayScenario(
+                        abstutil::path_map(app.primary.map.get_name()),
+                        self.scenario_name.clone(),
+                        self.modifiers.clone(),
+                    );
+                    let days = self
+                        .modifiers
+                        .iter()
+                        .map(|m| match m {
+                            ScenarioModifier::RepeatDays(n) => *n,
+                            _ => 1,
+                        })
+                        .product();
+                    let lines = ctx.loading_screen("calculate mode shift", |_, timer| {
+                        let flags = &app.primary.current_flags;
+                        let unedited =
+                            Map::new(abstutil::path_map(app.primary.map.get_name()), timer);
+                        let before = mode
+                            .scenario(
+                                &unedited,
+                                flags.num_agents,
+                                flags.sim_flags.make_rng(),
+                                timer,
+                            )
+                            .unwrap();
+                        let after = mode
+                            .scenario(
+                                &app.primary.map,
+                                flags.num_agents,
+                                flags.sim_flags.make_rng(),
+                                timer,
+                            )
+                            .unwrap();
+                        ModeShift::new(&before, &after, days).describe()
+                    });
+                    Some(Transition::Push(msg("Mode shift", lines)))
+                }
                 _ => unreachable!(),
             },
             None => None,
@@ -127,6 +167,13 @@ fn make_top_center(
                     .build(ctx, "edit traffic patterns", None)
                     .margin_right(15),
                 format!("{} modifications to traffic patterns", modifiers.len()).draw_text(ctx),
+                if modifiers.contains(&ScenarioModifier::ChooseModes) {
+                    Btn::text_fg("mode shift")
+                        .build_def(ctx, None)
+                        .margin_left(15)
+                } else {
+                    Widget::nothing()
+                },
             ])
             .centered_horiz()
         } else {
@@ -260,7 +307,12 @@ fn new_modifier(scenario_name: String, modifiers: Vec<ScenarioModifier>) -> Box<
         let mut wizard = wiz.wrap(ctx);
         let new_mod = match wizard
             .choose_string("", || {
-                vec!["repeat days", "cancel all trips for some people"]
+                vec![
+                    "repeat days",
+                    "cancel all trips for some people",
+                    "choose modes based on travel time and cost",
+                    "shift departures to avoid congestion",
+                ]
             })?
             .as_str()
         {
@@ -270,6 +322,12 @@ fn new_modifier(scenario_name: String, modifiers: Vec<ScenarioModifier>) -> Box<
             x if x == "cancel all trips for some people" => ScenarioModifier::CancelPeople(
                 wizard.input_percent("What percent of people should cancel trips? (0 to 100)")?,
             ),
+            x if x == "choose modes based on travel time and cost" => ScenarioModifier::ChooseModes,
+            x if x == "shift departures to avoid congestion" => ScenarioModifier::ShiftDepartures(
+                wizard.choose_string("Avoid congestion from which saved results?", || {
+                    ResultSet::list_all(app.primary.map.get_name())
+                })?,
+            ),
             _ => unreachable!(),
         };
         let mut mods = modifiers.clone();
@@ -82,6 +82,13 @@ impl SpeedControls {
             .margin_right(16),
         );
 
+        row.push(
+            Btn::text_fg("rewind")
+                .build_def(ctx, hotkey(Key::N))
+                .bg(app.cs.section_bg)
+                .margin_right(16),
+        );
+
         row.push(
             PersistentSplit::new(
                 ctx,
@@ -185,6 +192,25 @@ impl SpeedControls {
                         maybe_mode.cloned(),
                     ))));
                 }
+                "rewind" => {
+                    let dt = self.composite.persistent_split_value("step forwards");
+                    let now = app.primary.sim.time();
+                    let target = if now - Time::START_OF_DAY > dt {
+                        now - dt
+                    } else {
+                        Time::START_OF_DAY
+                    };
+                    return Some(match rewind(ctx, app, target) {
+                        Some(warp) => Transition::Push(warp),
+                        None => Transition::Push(msg(
+                            "Error",
+                            vec![
+                                "There's no checkpoint early enough to rewind to.",
+                                "Run with --checkpoint_minutes=10 to take them automatically.",
+                            ],
+                        )),
+                    });
+                }
                 "step forwards" => {
                     let dt = self.composite.persistent_split_value("step forwards");
                     if dt == Duration::seconds(0.1) {
@@ -416,6 +442,9 @@ impl State for JumpToTime {
                 }
                 "jump to time" => {
                     if self.target < app.primary.sim.time() {
+                        if let Some(warp) = rewind(ctx, app, self.target) {
+                            return Transition::Replace(warp);
+                        }
                         if let Some(mode) = self.maybe_mode.take() {
                             return Transition::ReplaceThenPush(
                                 Box::new(SandboxMode::new(ctx, app, mode)),
@@ -472,6 +501,14 @@ impl State for JumpToTime {
     }
 }
 
+// Restores the latest checkpoint before the target, then returns a screen to simulate the rest of
+// the way. None if there are no checkpoints early enough.
+fn rewind(ctx: &mut EventCtx, app: &mut App, target: Time) -> Option<Box<dyn State>> {
+    app.primary.sim.rewind_to_checkpoint(target)?;
+    app.recalculate_current_selection(ctx);
+    Some(TimeWarpScreen::new(ctx, app, target, false))
+}
+
 // Display a nicer screen for jumping forwards in time, allowing cancellation.
 pub struct TimeWarpScreen {
     target: Time,
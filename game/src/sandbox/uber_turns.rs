@@ -21,7 +21,12 @@ pub struct UberTurnPicker {
 impl UberTurnPicker {
     pub fn new(ctx: &mut EventCtx, app: &App, i: IntersectionID) -> Box<dyn State> {
         let mut members = BTreeSet::new();
-        if let Some(list) = IntersectionCluster::autodetect(i, &app.primary.map) {
+        if let Some(ic) = IntersectionCluster::find_roundabouts(&app.primary.map)
+            .into_iter()
+            .find(|ic| ic.members.contains(&i))
+        {
+            members.extend(ic.members);
+        } else if let Some(list) = IntersectionCluster::autodetect(i, &app.primary.map) {
             members.extend(list);
         } else {
             members.insert(i);
@@ -1,8 +1,12 @@
 use abstutil::{prettyprint_usize, CmdArgs, Timer};
-use map_model::Map;
+use geom::Duration;
+use map_model::{Map, MapEdits};
 use rand::SeedableRng;
 use rand_xorshift::XorShiftRng;
-use sim::{AlertHandler, Scenario, ScenarioModifier, Sim, SimFlags};
+use sim::{
+    AlertHandler, Divergence, ModeShift, RegionalSim, ResultSet, Scenario, ScenarioModifier, Sim,
+    SimFlags, SimOptions,
+};
 
 // This is specialized to experiment with running the pandemic model over long time periods.
 // Original functionality for profiling and debugging gridlock have been removed.
@@ -12,29 +16,180 @@ fn main() {
     let num_days = args
         .optional_parse("--days", |s| s.parse::<usize>())
         .unwrap_or(1);
+    // Simulate several adjacent maps together, like --regional=map1,map2
+    let regional = args.optional("--regional");
+    let scenario_name = args
+        .optional("--scenario")
+        .unwrap_or_else(|| "weekday".to_string());
+    // Save the Analytics afterwards, so the game can compare this run against others
+    let save_results = args.optional("--save_results");
+    // Run the scenario twice at once, checking that both runs stay identical
+    let verify_determinism = args.enabled("--verify_determinism");
+    // Write a savestate every few sim-minutes, so a later run (maybe of different code) can check
+    // against it with --compare_reference
+    let save_reference = args.optional_parse("--save_reference", |s| s.parse::<usize>());
+    let compare_reference = args.enabled("--compare_reference");
+    // Start with these map edits applied
+    let edits_name = args.optional("--edits");
+    // Let people re-decide their modes based on travel time and cost, then report what changed
+    // compared to choosing on the map without edits
+    let mode_choice = args.enabled("--mode_choice");
+    // Shift departure times to avoid the congestion in some saved results, then save the result as
+    // a new scenario instead of simulating
+    let shift_departures = args.optional("--shift_departures");
+    // Sample every agent's position this often (in seconds) and save the trajectories afterwards
+    let record_trajectories = args
+        .optional_parse("--record_trajectories", |s| s.parse::<f64>())
+        .map(Duration::seconds);
     args.done();
 
+    if let Some(dt) = record_trajectories {
+        if dt <= Duration::ZERO || !dt.inner_seconds().is_finite() {
+            println!("--record_trajectories must be over 0 seconds");
+            std::process::exit(1);
+        }
+    }
+
+    if let Some(maps) = regional {
+        // These all work on one map's Sim
+        if save_results.is_some()
+            || verify_determinism
+            || save_reference.is_some()
+            || compare_reference
+            || mode_choice
+            || shift_departures.is_some()
+            || record_trajectories.is_some()
+            || edits_name.is_some()
+            || num_days != 1
+        {
+            println!(
+                "--regional can't be combined with --save_results, --verify_determinism, \
+                 --save_reference, --compare_reference, --mode_choice, --shift_departures, \
+                 --record_trajectories, --edits or --days. Regional results are always saved."
+            );
+            std::process::exit(1);
+        }
+        run_regional(maps.split(',').collect(), &scenario_name);
+        return;
+    }
+
     let mut sim_flags = SimFlags::synthetic_test("montlake", "pandemic");
     sim_flags.opts.enable_pandemic_model = Some(XorShiftRng::from_seed([sim_flags.rng_seed; 16]));
     // Less spam
     sim_flags.opts.alerts = AlertHandler::Silence;
+    sim_flags.opts.record_trajectories = record_trajectories;
     let mut timer = Timer::new("setup headless");
     let (mut map, _, mut rng) = sim_flags.load(&mut timer);
     map.hack_override_offstreet_spots(num_days);
-    // After the map changes, have to re-create the (empty) Sim, because things like
-    // ParkingSimState are out of sync.
-    let mut sim = Sim::new(&map, sim_flags.opts.clone(), &mut timer);
 
     let base_scenario: Scenario = abstutil::read_binary(
-        abstutil::path_scenario(map.get_name(), "weekday"),
+        abstutil::path_scenario(map.get_name(), &scenario_name),
         &mut timer,
     );
-    ScenarioModifier::RepeatDays(num_days)
-        .apply(base_scenario)
-        .instantiate(&mut sim, &map, &mut rng, &mut timer);
+    let mut scenario = ScenarioModifier::RepeatDays(num_days).apply(
+        base_scenario.clone(),
+        &map,
+        &mut rng,
+        &mut timer,
+    );
+    // Before applying edits, choose modes once for a baseline. Both runs start from the same RNG,
+    // so without edits, nobody changes.
+    let baseline = if mode_choice {
+        Some(ScenarioModifier::ChooseModes.apply(
+            scenario.clone(),
+            &map,
+            &mut rng.clone(),
+            &mut timer,
+        ))
+    } else {
+        None
+    };
+    if let Some(ref name) = edits_name {
+        map.apply_edits(MapEdits::load(&map, name, &mut timer).unwrap(), &mut timer);
+        map.recalculate_pathfinding_after_edits(&mut timer);
+    }
+    // After the map changes, have to re-create the (empty) Sim, because things like
+    // ParkingSimState are out of sync.
+    let mut sim = Sim::new(&map, sim_flags.opts.clone(), &mut timer);
+
+    if let Some(results) = shift_departures {
+        let mut scenario = ScenarioModifier::ShiftDepartures(results).apply(
+            base_scenario,
+            &map,
+            &mut rng,
+            &mut timer,
+        );
+        scenario.scenario_name = format!("{}_shifted", scenario_name);
+        scenario.save();
+        timer.done();
+        println!(
+            "Saved {}",
+            abstutil::path_scenario(map.get_name(), &scenario.scenario_name)
+        );
+        return;
+    }
+    if let Some(baseline) = baseline {
+        scenario = ScenarioModifier::ChooseModes.apply(scenario, &map, &mut rng, &mut timer);
+        for line in ModeShift::new(&baseline, &scenario, num_days).describe() {
+            println!("{}", line);
+        }
+    }
+    // Modifying the scenario above advances the RNG, so when verifying determinism, start both
+    // sims from the same state.
+    scenario.instantiate(&mut sim, &map, &mut rng.clone(), &mut timer);
+
+    if verify_determinism {
+        let mut sim2 = Sim::new(&map, sim_flags.opts.clone(), &mut timer);
+        scenario.instantiate(&mut sim2, &map, &mut rng, &mut timer);
+        let end_time = sim.get_end_of_day();
+        let result = sim::find_first_divergence(
+            &mut sim,
+            &mut sim2,
+            &map,
+            Duration::minutes(1),
+            end_time,
+            &mut timer,
+        );
+        timer.done();
+        report_divergence(result);
+        return;
+    }
+    if let Some(minutes) = save_reference {
+        timer.done();
+        while !sim.is_done() {
+            sim.timed_step(
+                &map,
+                Duration::minutes(minutes),
+                &mut None,
+                &mut Timer::throwaway(),
+            );
+            println!("Saved {}", sim.save());
+        }
+        return;
+    }
+    if compare_reference {
+        let savestates = abstutil::list_dir(std::path::Path::new(&sim.save_dir()));
+        if savestates.is_empty() {
+            panic!(
+                "No savestates in {}; run with --save_reference first",
+                sim.save_dir()
+            );
+        }
+        let result = sim::compare_to_savestates(&mut sim, &map, savestates, &mut timer);
+        timer.done();
+        report_divergence(result.unwrap());
+        return;
+    }
     timer.done();
 
     run_experiment(&map, &mut sim);
+
+    if let Some(name) = save_results {
+        println!("Saved {}", ResultSet::new(name, &map, &sim).save());
+    }
+    if let Some(trajectories) = sim.get_trajectories() {
+        println!("Saved {}", trajectories.save());
+    }
 }
 
 fn run_experiment(map: &Map, sim: &mut Sim) {
@@ -55,4 +210,50 @@ fn run_experiment(map: &Map, sim: &mut Sim) {
     );
     timer.done();
     println!("Done at {}", sim.time());
+    if !map.get_pricing().is_empty() {
+        for line in sim.get_analytics().pricing_report(sim.time(), None, map) {
+            println!("{}", line);
+        }
+    }
+}
+
+fn report_divergence(result: Option<Divergence>) {
+    match result {
+        Some(d) => {
+            for line in d.describe() {
+                println!("{}", line);
+            }
+            std::process::exit(1);
+        }
+        None => {
+            println!("No divergence");
+        }
+    }
+}
+
+fn run_regional(map_names: Vec<&str>, scenario_name: &str) {
+    let mut timer = Timer::new("setup regional sim");
+    let regions = map_names
+        .into_iter()
+        .map(|name| {
+            let map = Map::new(abstutil::path_map(name), &mut timer);
+            let scenario: Scenario =
+                abstutil::read_binary(abstutil::path_scenario(name, scenario_name), &mut timer);
+            (map, scenario)
+        })
+        .collect();
+    let mut opts = SimOptions::new("regional");
+    opts.alerts = AlertHandler::Silence;
+    let mut regional =
+        RegionalSim::new(regions, opts, XorShiftRng::from_seed([42; 16]), &mut timer);
+    timer.done();
+
+    regional.run_until_done();
+    println!(
+        "Done at {}. {} handoffs between maps, {} lost. Results in {}",
+        regional.time(),
+        prettyprint_usize(regional.handoffs.len()),
+        prettyprint_usize(regional.lost_handoffs),
+        regional.save_results()
+    );
 }
@@ -0,0 +1,152 @@
+use crate::utils::{download, download_kml, osmconvert};
+use abstutil::Timer;
+use geom::{GPSBounds, LonLat};
+use map_model::raw::DrivingSide;
+use serde::Deserialize;
+
+// Describes how to import a city, read from data/input/$city/config.json. Every map in the city
+// comes from one clipping polygon in data/input/$city/polygons/.
+//
+// Each input is either a URL, downloaded once into data/input/$city/, or a local path used as-is.
+// Using only local paths, the import works entirely offline.
+#[derive(Deserialize)]
+pub struct GenericCityImporter {
+    // A .osm or .osm.pbf file covering every map in the city
+    pub osm: String,
+    pub driving_side: DrivingSide,
+
+    // A directory with GTFS files, or a URL to a .zip of them
+    #[serde(default)]
+    pub gtfs: Option<String>,
+    // An SRTM .hgt file
+    #[serde(default)]
+    pub elevation: Option<String>,
+    // KML files (or ExtraShapes already extracted from them) with blockface parking and public
+    // parking garages, in the format Seattle uses
+    #[serde(default)]
+    pub parking_shapes: Option<String>,
+    #[serde(default)]
+    pub public_offstreet_parking: Option<String>,
+    // How many private parking spots each building has. Defaults to 1.
+    #[serde(default)]
+    pub private_offstreet_parking_per_bldg: Option<usize>,
+    // Use sidewalks and crossings mapped separately in OSM, instead of inferring them.
+    #[serde(default)]
+    pub separate_sidewalks: bool,
+    // Merge clusters of signalized intersections joined by short roads into one intersection.
+    #[serde(default)]
+    pub merge_short_roads: bool,
+}
+
+impl GenericCityImporter {
+    pub fn load(city: &str) -> GenericCityImporter {
+        abstutil::maybe_read_json(
+            format!("../data/input/{}/config.json", city),
+            &mut Timer::throwaway(),
+        )
+        .unwrap_or_else(|err| {
+            panic!(
+                "Can't import {}: data/input/{}/config.json is missing or broken: {}",
+                city, city, err
+            )
+        })
+    }
+
+    pub fn osm_to_raw(&self, city: &str, name: &str) {
+        let osm = prepare(city, &self.osm, "osm/", None);
+        let clip = format!("../data/input/{}/polygons/{}.poly", city, name);
+        let clipped_osm = format!("../data/input/{}/osm/{}.osm", city, name);
+        osmconvert(&osm, clip.clone(), clipped_osm.clone());
+
+        // Only bother calculating this if there's KML to clip
+        let bounds = if self.parking_shapes.is_some() || self.public_offstreet_parking.is_some() {
+            Some(city_bounds(city))
+        } else {
+            None
+        };
+
+        println!("- Running convert_osm");
+        let map = convert_osm::convert(
+            convert_osm::Options {
+                osm_input: clipped_osm,
+                city_name: city.to_string(),
+                name: name.to_string(),
+
+                parking_shapes: self
+                    .parking_shapes
+                    .as_ref()
+                    .map(|x| prepare(city, x, "", bounds.as_ref())),
+                public_offstreet_parking: self
+                    .public_offstreet_parking
+                    .as_ref()
+                    .map(|x| prepare(city, x, "", bounds.as_ref())),
+                private_offstreet_parking: convert_osm::PrivateOffstreetParking::FixedPerBldg(
+                    self.private_offstreet_parking_per_bldg.unwrap_or(1),
+                ),
+                sidewalks: None,
+                separate_sidewalks: self.separate_sidewalks,
+                merge_short_roads: self.merge_short_roads,
+                gtfs: self.gtfs.as_ref().map(|x| {
+                    if is_url(x) {
+                        // The zip gets extracted into this directory
+                        let output = format!("../data/input/{}/gtfs/", city);
+                        download(&output, x);
+                        output
+                    } else {
+                        x.clone()
+                    }
+                }),
+                elevation: self.elevation.as_ref().map(|x| prepare(city, x, "", None)),
+                clip: Some(clip),
+                drive_on_right: self.driving_side == DrivingSide::Right,
+            },
+            &mut Timer::throwaway(),
+        );
+        let output = format!("../data/input/raw_maps/{}.bin", name);
+        println!("- Saving {}", output);
+        abstutil::write_binary(output, &map);
+    }
+}
+
+// Returns the local path to an input, downloading it into data/input/$city/$subdir first if needed.
+fn prepare(city: &str, input: &str, subdir: &str, bounds: Option<&GPSBounds>) -> String {
+    if !is_url(input) {
+        return input.to_string();
+    }
+
+    // Strip off the query string and compression
+    let mut filename = input.rsplit('/').next().unwrap();
+    filename = filename.split('?').next().unwrap();
+    filename = filename.trim_end_matches(".gz").trim_end_matches(".zip");
+    if filename.ends_with(".kml") {
+        let output = format!(
+            "../data/input/{}/{}{}",
+            city,
+            subdir,
+            filename.replace(".kml", ".bin")
+        );
+        download_kml(&output, input, bounds.unwrap());
+        return output;
+    }
+    let output = format!("../data/input/{}/{}{}", city, subdir, filename);
+    download(&output, input);
+    output
+}
+
+fn is_url(input: &str) -> bool {
+    input.starts_with("http://") || input.starts_with("https://")
+}
+
+// Covers all of the city's clipping polygons
+fn city_bounds(city: &str) -> GPSBounds {
+    let mut bounds = GPSBounds::new();
+    for name in abstutil::list_all_objects(format!("../data/input/{}/polygons", city)) {
+        for pt in
+            LonLat::read_osmosis_polygon(format!("../data/input/{}/polygons/{}.poly", city, name))
+                .unwrap()
+        {
+            bounds.update(pt);
+        }
+    }
+    bounds
+}
@@ -1,9 +1,13 @@
-mod austin;
+mod generic;
+mod od;
 mod seattle;
 #[cfg(feature = "scenarios")]
 mod soundcast;
 mod utils;
 
+use rand::SeedableRng;
+use rand_xorshift::XorShiftRng;
+
 // TODO Might be cleaner to express as a dependency graph?
 
 struct Job {
@@ -12,6 +16,14 @@ struct Job {
     raw_to_map: bool,
     scenario: bool,
     scenario_everyone: bool,
+    trip_list: Option<String>,
+    od_matrix: Option<String>,
+    od_zones: Option<String>,
+    geojson: bool,
+    edits: Option<String>,
+    diff: bool,
+    validate: bool,
+    strict: bool,
 
     skip_ch: bool,
 
@@ -20,6 +32,8 @@ struct Job {
     oneshot: Option<String>,
     oneshot_clip: Option<String>,
     oneshot_drive_on_left: bool,
+    oneshot_separate_sidewalks: bool,
+    oneshot_merge_short_roads: bool,
 }
 
 fn main() {
@@ -30,10 +44,31 @@ fn main() {
         osm_to_raw: args.enabled("--raw"),
         // Convert the RawMap to the final Map format.
         raw_to_map: args.enabled("--map"),
-        // Download trip demand data, then produce the typical weekday scenario.
+        // Download trip demand data, then produce the typical weekday scenario. Cities besides
+        // Seattle get a synthetic scenario instead, generated from the map.
         scenario: args.enabled("--scenario"),
         // Produce a variation of the weekday scenario including off-map trips.
         scenario_everyone: args.enabled("--scenario_everyone"),
+        // Produce a scenario from a CSV list of trips with lon/lat endpoints. See od.rs for the
+        // format.
+        trip_list: args.optional("--trip_list"),
+        // Produce a scenario from a CSV origin-destination matrix between zones. --od_zones is a
+        // directory of .poly files, one per zone.
+        od_matrix: args.optional("--od_matrix"),
+        od_zones: args.optional("--od_zones"),
+        // Export the map as GeoJSON layers to data/player/exports/$map/, for GIS tools. Apply the
+        // edits named by --edits first, if given.
+        geojson: args.enabled("--geojson"),
+        edits: args.optional("--edits"),
+        // Compare what --raw or --map produces against the previous version of the map, usually
+        // built from an older OSM snapshot. The differences are saved to
+        // data/player/map_diffs/$map.json, for the "map diff" layer in the game.
+        diff: args.enabled("--diff"),
+        // Check the map for problems, writing a report to data/player/validation/$map.json. This
+        // always happens after --map; --validate does it for existing maps.
+        validate: args.enabled("--validate"),
+        // Fail if validation finds any errors, instead of just warning.
+        strict: args.enabled("--strict"),
         // Skip the most expensive step of --map, building contraction hierarchies. The resulting
         // map won't be usable for simulation; as soon as you try to pathfind, it'll crash.
         skip_ch: args.enabled("--skip_ch"),
@@ -46,23 +81,37 @@ fn main() {
         oneshot: args.optional("--oneshot"),
         oneshot_clip: args.optional("--oneshot_clip"),
         oneshot_drive_on_left: args.enabled("--oneshot_drive_on_left"),
+        // Use sidewalks and crossings mapped separately in OSM, instead of inferring them.
+        oneshot_separate_sidewalks: args.enabled("--oneshot_separate_sidewalks"),
+        // Merge clusters of signalized intersections joined by short roads.
+        oneshot_merge_short_roads: args.enabled("--oneshot_merge_short_roads"),
     };
     args.done();
     if !job.osm_to_raw
         && !job.raw_to_map
         && !job.scenario
         && !job.scenario_everyone
+        && job.trip_list.is_none()
+        && job.od_matrix.is_none()
+        && !job.geojson
+        && !job.validate
         && job.oneshot.is_none()
     {
         println!(
             "Nothing to do! Pass some combination of --raw, --map, --scenario, \
-             --scenario_everyone or --oneshot"
+             --scenario_everyone, --trip_list, --od_matrix, --geojson, --validate or --oneshot"
         );
         std::process::exit(1);
     }
 
     if let Some(path) = job.oneshot {
-        oneshot(path, job.oneshot_clip, !job.oneshot_drive_on_left);
+        oneshot(
+            path,
+            job.oneshot_clip,
+            !job.oneshot_drive_on_left,
+            job.oneshot_separate_sidewalks,
+            job.oneshot_merge_short_roads,
+        );
         return;
     }
 
@@ -76,9 +125,15 @@ fn main() {
 
     let mut timer = abstutil::Timer::new("import map data");
 
-    let (maybe_popdat, maybe_huge_map) = if job.scenario || job.scenario_everyone {
-        assert_eq!(job.city, "seattle");
-
+    let (maybe_popdat, maybe_huge_map) = if job.city != "seattle" {
+        if job.scenario_everyone {
+            panic!(
+                "--scenario_everyone needs Soundcast travel demand, which only exists for Seattle"
+            );
+        }
+        // Without a travel demand model, --scenario synthesizes one from the map instead.
+        (None, None)
+    } else if job.scenario || job.scenario_everyone {
         #[cfg(feature = "scenarios")]
         {
             let (popdat, huge_map) = seattle::ensure_popdat_exists(&mut timer);
@@ -99,24 +154,90 @@ fn main() {
     };
 
     for name in names {
+        // Grab the previous versions before they're overwritten
+        let old_raw: Option<map_model::raw::RawMap> = if job.diff && job.osm_to_raw {
+            abstutil::maybe_read_binary(abstutil::path_raw_map(&name), &mut timer).ok()
+        } else {
+            None
+        };
+        let old_map: Option<map_model::Map> = if job.diff && job.raw_to_map {
+            abstutil::maybe_read_binary(abstutil::path_map(&name), &mut timer).ok()
+        } else {
+            None
+        };
+
         if job.osm_to_raw {
-            match job.city.as_ref() {
-                "austin" => austin::osm_to_raw(&name),
-                "seattle" => seattle::osm_to_raw(&name),
-                x => panic!("Unknown city {}", x),
+            if job.city == "seattle" {
+                seattle::osm_to_raw(&name);
+            } else {
+                generic::GenericCityImporter::load(&job.city).osm_to_raw(&job.city, &name);
             }
         }
 
+        if let Some(old) = old_raw {
+            let new = abstutil::read_binary(abstutil::path_raw_map(&name), &mut timer);
+            let diff = map_model::MapDiff::between_raw_maps(&old, &new);
+            report_diff(&diff, "raw map");
+            abstutil::write_json(abstutil::path_raw_map_diff(&name), &diff);
+        }
+
         let mut maybe_map = if job.raw_to_map {
             Some(utils::raw_to_map(&name, !job.skip_ch, &mut timer))
-        } else if job.scenario || job.scenario_everyone {
+        } else if job.scenario
+            || job.scenario_everyone
+            || job.trip_list.is_some()
+            || job.od_matrix.is_some()
+            || job.geojson
+            || job.validate
+        {
             Some(map_model::Map::new(abstutil::path_map(&name), &mut timer))
         } else {
             None
         };
 
+        if job.raw_to_map || job.validate {
+            validate(maybe_map.as_ref().unwrap(), job.strict, &mut timer);
+        }
+
+        if let Some(old) = old_map {
+            let diff =
+                map_model::MapDiff::between_maps(&old, maybe_map.as_ref().unwrap(), &mut timer);
+            report_diff(&diff, "map");
+            abstutil::write_json(abstutil::path_map_diff(&name), &diff);
+        }
+
+        if job.scenario && job.city != "seattle" {
+            timer.start(format!("synthetic scenario for {}", name));
+            sim::synthetic_weekday(
+                maybe_map.as_ref().unwrap(),
+                &mut XorShiftRng::from_seed([42; 16]),
+                &mut timer,
+            )
+            .save();
+            timer.stop(format!("synthetic scenario for {}", name));
+        }
+
+        if let Some(ref path) = job.trip_list {
+            timer.start(format!("import {} for {}", path, name));
+            od::import_trip_list(path, maybe_map.as_ref().unwrap(), &mut timer).save();
+            timer.stop(format!("import {} for {}", path, name));
+        }
+
+        if let Some(ref path) = job.od_matrix {
+            timer.start(format!("import {} for {}", path, name));
+            od::import_od_matrix(
+                path,
+                job.od_zones.as_ref().expect("--od_matrix needs --od_zones"),
+                maybe_map.as_ref().unwrap(),
+                &mut XorShiftRng::from_seed([42; 16]),
+                &mut timer,
+            )
+            .save();
+            timer.stop(format!("import {} for {}", path, name));
+        }
+
         #[cfg(feature = "scenarios")]
-        if job.scenario {
+        if job.scenario && job.city == "seattle" {
             timer.start(format!("scenario for {}", name));
             let scenario = soundcast::make_weekday_scenario(
                 maybe_map.as_ref().unwrap(),
@@ -146,10 +267,63 @@ fn main() {
             .save();
             timer.stop(format!("scenario_everyone for {}", name));
         }
+
+        if job.geojson {
+            let map = maybe_map.as_mut().unwrap();
+            let dir = if let Some(ref edits_name) = job.edits {
+                let edits = map_model::MapEdits::load(map, edits_name, &mut timer).unwrap();
+                map.apply_edits(edits, &mut timer);
+                format!("{}/{}", abstutil::path_exports(&name), edits_name)
+            } else {
+                abstutil::path_exports(&name)
+            };
+            for path in map.export_geojson(&dir).unwrap() {
+                println!("- Wrote {}", path);
+            }
+        }
+    }
+}
+
+fn validate(map: &map_model::Map, strict: bool, timer: &mut abstutil::Timer) {
+    let report = map_model::ValidationReport::new(map, timer);
+    for line in report.summary() {
+        timer.note(format!("{}: {}", report.map_name, line));
+    }
+    abstutil::write_json(abstutil::path_validation_report(&report.map_name), &report);
+    if strict && report.num_errors() > 0 {
+        panic!(
+            "{} has {} validation errors; see {}",
+            report.map_name,
+            report.num_errors(),
+            abstutil::path_validation_report(&report.map_name)
+        );
+    }
+}
+
+fn report_diff(diff: &map_model::MapDiff, what: &str) {
+    if diff.is_empty() {
+        println!(
+            "- No differences from the previous version of the {} {}",
+            what, diff.map_name
+        );
+    } else {
+        println!(
+            "- Differences from the previous version of the {} {}:",
+            what, diff.map_name
+        );
+        for line in diff.describe() {
+            println!("  {}", line);
+        }
     }
 }
 
-fn oneshot(osm_path: String, clip: Option<String>, drive_on_right: bool) {
+fn oneshot(
+    osm_path: String,
+    clip: Option<String>,
+    drive_on_right: bool,
+    separate_sidewalks: bool,
+    merge_short_roads: bool,
+) {
     let mut timer = abstutil::Timer::new("oneshot");
     println!("- Running convert_osm on {}", osm_path);
     let name = abstutil::basename(&osm_path);
@@ -163,6 +337,8 @@ fn oneshot(osm_path: String, clip: Option<String>, drive_on_right: bool) {
             public_offstreet_parking: None,
             private_offstreet_parking: convert_osm::PrivateOffstreetParking::FixedPerBldg(1),
             sidewalks: None,
+            separate_sidewalks,
+            merge_short_roads,
             gtfs: None,
             elevation: None,
             clip,
@@ -0,0 +1,318 @@
+use abstutil::{prettyprint_usize, Timer};
+use geom::{Distance, Duration, FindClosest, LonLat, Polygon, Pt2D, Time};
+use map_model::{BuildingID, Map, PathConstraints};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use rand_xorshift::XorShiftRng;
+use serde::Deserialize;
+use sim::{
+    IndividTrip, OffMapLocation, PersonID, PersonSpec, Scenario, SpawnTrip, TripEndpoint, TripMode,
+};
+use std::collections::BTreeMap;
+
+// Turns travel demand from some other model into a Scenario. Both formats are CSV files. Trip
+// modes are walk, bike, transit, or drive.
+//
+// A trip list has one row per trip: person, depart (HH:MM:SS), origin_lon, origin_lat,
+// destination_lon, destination_lat, mode. Trips by the same person are chained together in order
+// of departure.
+//
+// An origin-destination matrix has one row per zone pair, hour, and mode: origin, destination,
+// hour, mode, trips. Zones are Osmosis polygon files in one directory, named by their filename.
+// Each trip starts at a random building in the origin zone, at a random time during the hour.
+//
+// Endpoints outside the map start or end at the nearest border instead, remembering the real
+// location as an OffMapLocation.
+
+#[derive(Deserialize)]
+struct TripRecord {
+    person: String,
+    depart: String,
+    origin_lon: f64,
+    origin_lat: f64,
+    destination_lon: f64,
+    destination_lat: f64,
+    mode: String,
+}
+
+#[derive(Deserialize)]
+struct ODRecord {
+    origin: String,
+    destination: String,
+    hour: usize,
+    mode: String,
+    // Can be fractional
+    trips: f64,
+}
+
+pub fn import_trip_list(path: &str, map: &Map, timer: &mut Timer) -> Scenario {
+    let mut endpts = Endpoints::new(map, timer);
+    let mut trips_per_person: BTreeMap<String, Vec<IndividTrip>> = BTreeMap::new();
+    let mut skipped = 0;
+    timer.start(format!("read {}", path));
+    for rec in csv::Reader::from_path(path).unwrap().deserialize() {
+        let rec: TripRecord = rec.unwrap();
+        let mode = parse_mode(&rec.mode);
+        let depart = Time::parse(&rec.depart).unwrap();
+        let from = endpts.point(LonLat::new(rec.origin_lon, rec.origin_lat), mode, true);
+        let to = endpts.point(
+            LonLat::new(rec.destination_lon, rec.destination_lat),
+            mode,
+            false,
+        );
+        if let Some((from, to)) = usable(from, to) {
+            trips_per_person
+                .entry(rec.person)
+                .or_insert_with(Vec::new)
+                .push(IndividTrip {
+                    depart,
+                    trip: SpawnTrip::new(from, to, mode, map),
+                    cancelled: false,
+                });
+        } else {
+            skipped += 1;
+        }
+    }
+    timer.stop(format!("read {}", path));
+    timer.note(format!(
+        "Skipped {} trips that don't start or end in the map",
+        prettyprint_usize(skipped)
+    ));
+
+    let mut people = Vec::new();
+    for (_, mut trips) in trips_per_person {
+        trips.sort_by_key(|t| t.depart);
+        people.push(PersonSpec {
+            id: PersonID(people.len()),
+            orig_id: None,
+            trips,
+        });
+    }
+    Scenario {
+        scenario_name: abstutil::basename(path),
+        map_name: map.get_name().to_string(),
+        people,
+        only_seed_buses: None,
+    }
+    .remove_weird_schedules(map)
+}
+
+pub fn import_od_matrix(
+    path: &str,
+    zones_dir: &str,
+    map: &Map,
+    rng: &mut XorShiftRng,
+    timer: &mut Timer,
+) -> Scenario {
+    let endpts = Endpoints::new(map, timer);
+    let zones = read_zones(zones_dir, map);
+    timer.note(format!(
+        "{} of {} zones overlap the map",
+        zones.values().filter(|z| !z.bldgs.is_empty()).count(),
+        zones.len()
+    ));
+
+    let mut people = Vec::new();
+    let mut skipped = 0;
+    timer.start(format!("read {}", path));
+    for rec in csv::Reader::from_path(path).unwrap().deserialize() {
+        let rec: ODRecord = rec.unwrap();
+        let mode = parse_mode(&rec.mode);
+        let origin = zones
+            .get(&rec.origin)
+            .unwrap_or_else(|| panic!("Unknown zone {}", rec.origin));
+        let destination = zones
+            .get(&rec.destination)
+            .unwrap_or_else(|| panic!("Unknown zone {}", rec.destination));
+
+        // Round fractional trips up or down randomly, so the totals work out
+        let mut num_trips = rec.trips.floor() as usize;
+        if rng.gen_bool(rec.trips - rec.trips.floor()) {
+            num_trips += 1;
+        }
+        for _ in 0..num_trips {
+            let from = endpts.zone(origin, mode, true, rng);
+            let to = endpts.zone(destination, mode, false, rng);
+            if let Some((from, to)) = usable(from, to) {
+                let depart = Time::START_OF_DAY
+                    + Duration::hours(rec.hour)
+                    + Duration::seconds(rng.gen_range(0.0, 3600.0));
+                people.push(PersonSpec {
+                    id: PersonID(people.len()),
+                    orig_id: None,
+                    trips: vec![IndividTrip {
+                        depart,
+                        trip: SpawnTrip::new(from, to, mode, map),
+                        cancelled: false,
+                    }],
+                });
+            } else {
+                skipped += 1;
+            }
+        }
+    }
+    timer.stop(format!("read {}", path));
+    timer.note(format!(
+        "Skipped {} trips that don't start or end in the map",
+        prettyprint_usize(skipped)
+    ));
+
+    Scenario {
+        scenario_name: abstutil::basename(path),
+        map_name: map.get_name().to_string(),
+        people,
+        only_seed_buses: None,
+    }
+}
+
+struct Zone {
+    // Buildings in the map inside this zone
+    bldgs: Vec<BuildingID>,
+    center: LonLat,
+    parcel_id: usize,
+}
+
+// Like City.regions, every .poly file in the directory is one zone.
+fn read_zones(dir: &str, map: &Map) -> BTreeMap<String, Zone> {
+    let bounds = map.get_gps_bounds();
+    let mut zones = BTreeMap::new();
+    for (idx, name) in abstutil::list_all_objects(dir.to_string())
+        .into_iter()
+        .enumerate()
+    {
+        let pts = LonLat::read_osmosis_polygon(format!("{}/{}.poly", dir, name)).unwrap();
+        let center = LonLat::new(
+            pts.iter().map(|pt| pt.x()).sum::<f64>() / (pts.len() as f64),
+            pts.iter().map(|pt| pt.y()).sum::<f64>() / (pts.len() as f64),
+        );
+        let polygon = Polygon::new(&bounds.forcibly_convert(&pts));
+        let bldgs = map
+            .all_buildings()
+            .iter()
+            .filter(|b| polygon.contains_pt(b.polygon.center()))
+            .map(|b| b.id)
+            .collect();
+        zones.insert(
+            name,
+            Zone {
+                bldgs,
+                center,
+                parcel_id: idx,
+            },
+        );
+    }
+    zones
+}
+
+// Matches locations from the input to buildings or borders.
+struct Endpoints<'a> {
+    map: &'a Map,
+    bldgs: FindClosest<BuildingID>,
+    // Every distinct location off the map gets its own ID
+    off_map_ids: BTreeMap<LonLat, usize>,
+}
+
+impl<'a> Endpoints<'a> {
+    fn new(map: &'a Map, timer: &mut Timer) -> Endpoints<'a> {
+        timer.start("index buildings");
+        let mut bldgs = FindClosest::new(map.get_bounds());
+        for b in map.all_buildings() {
+            bldgs.add(b.id, b.polygon.points());
+        }
+        timer.stop("index buildings");
+        Endpoints {
+            map,
+            bldgs,
+            off_map_ids: BTreeMap::new(),
+        }
+    }
+
+    fn point(&mut self, gps: LonLat, mode: TripMode, origin: bool) -> Option<TripEndpoint> {
+        if let Some(pt) = Pt2D::from_gps(gps, self.map.get_gps_bounds()) {
+            if self.map.get_boundary_polygon().contains_pt(pt) {
+                return self
+                    .bldgs
+                    .closest_pt(pt, Distance::meters(500.0))
+                    .map(|(b, _)| TripEndpoint::Bldg(b));
+            }
+        }
+        let next_id = self.off_map_ids.len();
+        let parcel_id = *self.off_map_ids.entry(gps).or_insert(next_id);
+        self.border(gps, parcel_id, mode, origin)
+    }
+
+    fn zone(
+        &self,
+        zone: &Zone,
+        mode: TripMode,
+        origin: bool,
+        rng: &mut XorShiftRng,
+    ) -> Option<TripEndpoint> {
+        if let Some(b) = zone.bldgs.choose(rng) {
+            Some(TripEndpoint::Bldg(*b))
+        } else {
+            self.border(zone.center, zone.parcel_id, mode, origin)
+        }
+    }
+
+    // The closest border that can be used to enter or leave the map
+    fn border(
+        &self,
+        gps: LonLat,
+        parcel_id: usize,
+        mode: TripMode,
+        origin: bool,
+    ) -> Option<TripEndpoint> {
+        let constraints = match mode {
+            TripMode::Walk | TripMode::Transit => PathConstraints::Pedestrian,
+            TripMode::Drive => PathConstraints::Car,
+            TripMode::Bike => PathConstraints::Bike,
+        };
+        let bounds = self.map.get_gps_bounds();
+        let borders = if origin {
+            self.map
+                .all_incoming_borders()
+                .into_iter()
+                .filter(|i| !i.get_outgoing_lanes(self.map, constraints).is_empty())
+                .collect::<Vec<_>>()
+        } else {
+            self.map
+                .all_outgoing_borders()
+                .into_iter()
+                .filter(|i| !i.get_incoming_lanes(self.map, constraints).is_empty())
+                .collect::<Vec<_>>()
+        };
+        let i = borders
+            .into_iter()
+            .filter_map(|i| i.polygon.center().to_gps(bounds).map(|pt| (i.id, pt)))
+            .min_by_key(|(_, pt)| pt.fast_dist(gps))?
+            .0;
+        Some(TripEndpoint::Border(
+            i,
+            Some(OffMapLocation { parcel_id, gps }),
+        ))
+    }
+}
+
+// Trips passing through the map or between the same building aren't useful.
+// TODO Detect and handle pass-through trips
+fn usable(
+    from: Option<TripEndpoint>,
+    to: Option<TripEndpoint>,
+) -> Option<(TripEndpoint, TripEndpoint)> {
+    match (from?, to?) {
+        (TripEndpoint::Border(_, _), TripEndpoint::Border(_, _)) => None,
+        (TripEndpoint::Bldg(b1), TripEndpoint::Bldg(b2)) if b1 == b2 => None,
+        (from, to) => Some((from, to)),
+    }
+}
+
+fn parse_mode(mode: &str) -> TripMode {
+    match mode {
+        "walk" => TripMode::Walk,
+        "bike" => TripMode::Bike,
+        "transit" => TripMode::Transit,
+        "drive" => TripMode::Drive,
+        _ => panic!("Unknown mode {}; use walk, bike, transit, or drive", mode),
+    }
+}
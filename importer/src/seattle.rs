@@ -1,4 +1,5 @@
-use crate::utils::{download, osmconvert};
+use crate::utils::{download, download_kml, osmconvert};
+use geom::GPSBounds;
 use map_model::Map;
 use sim::Scenario;
 
@@ -21,18 +22,21 @@ fn input() {
         "https://www.dropbox.com/s/t9oug9lwhdwfc04/psrc_2014.zip?dl=0",
     );
 
+    let bounds = GPSBounds::seattle_bounds();
     // From http://data-seattlecitygis.opendata.arcgis.com/datasets/blockface
-    download(
+    download_kml(
         "../data/input/seattle/blockface.bin",
         "https://opendata.arcgis.com/datasets/a1458ad1abca41869b81f7c0db0cd777_0.kml",
+        &bounds,
     );
     // From https://data-seattlecitygis.opendata.arcgis.com/datasets/sidewalks
-    download(
+    download_kml(
         "../data/input/seattle/sidewalks.bin",
         "https://opendata.arcgis.com/datasets/ee6d0642d2a04e35892d0eab77d971d6_2.kml",
+        &bounds,
     );
     // From https://data.seattle.gov/Transportation/Public-Garages-or-Parking-Lots/xefx-khzm
-    download("../data/input/seattle/offstreet_parking.bin", "http://data-seattlecitygis.opendata.arcgis.com/datasets/8e52dfde6d5d45948f7a90654c8d50cd_0.kml");
+    download_kml("../data/input/seattle/offstreet_parking.bin", "http://data-seattlecitygis.opendata.arcgis.com/datasets/8e52dfde6d5d45948f7a90654c8d50cd_0.kml", &bounds);
 }
 
 pub fn osm_to_raw(name: &str) {
@@ -65,6 +69,9 @@ pub fn osm_to_raw(name: &str) {
             ),
             // TODO These're buggy.
             sidewalks: None,
+            separate_sidewalks: false,
+            // Existing edits and signal configs refer to the unmerged intersections
+            merge_short_roads: false,
             gtfs: Some("../data/input/seattle/google_transit".to_string()),
             elevation: Some("../data/input/seattle/N47W122.hgt".to_string()),
             clip: Some(format!("../data/input/seattle/polygons/{}.poly", name)),
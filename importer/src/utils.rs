@@ -1,11 +1,21 @@
 use abstutil::Timer;
+use geom::GPSBounds;
 use std::path::Path;
 use std::process::Command;
 
 // If the output file doesn't already exist, downloads the URL into that location. Automatically
-// uncompresses .zip and .gz files. .kml files are automatically clipped to a hardcoded boundary of
-// Seattle.
+// uncompresses .zip and .gz files.
 pub fn download(output: &str, url: &str) {
+    download_and_clip(output, url, None);
+}
+
+// Like download, but .kml files are also converted to ExtraShapes, keeping only what's within the
+// bounds.
+pub fn download_kml(output: &str, url: &str, bounds: &GPSBounds) {
+    download_and_clip(output, url, Some(bounds));
+}
+
+fn download_and_clip(output: &str, url: &str, bounds: Option<&GPSBounds>) {
     if Path::new(output).exists() {
         println!("- {} already exists", output);
         return;
@@ -48,7 +58,7 @@ pub fn download(output: &str, url: &str) {
 
         let shapes = kml::load(
             tmp,
-            &geom::GPSBounds::seattle_bounds(),
+            bounds.expect("Use download_kml for .kml files"),
             &mut abstutil::Timer::new("extracting shapes from KML"),
         )
         .unwrap();
@@ -19,6 +19,7 @@ pub enum AreaType {
     Water,
     PedestrianIsland,
     Island,
+    Plaza,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -34,6 +34,17 @@ pub struct OffstreetParking {
     pub driving_pos: Position,
 }
 
+// What a building is used for, guessed from OSM. Mostly useful for estimating travel demand.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum BuildingType {
+    Residential,
+    // Shops or offices on the ground floor, homes above
+    ResidentialCommercial,
+    Commercial,
+    // Garages, sheds, etc
+    Empty,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Building {
     pub id: BuildingID,
@@ -46,6 +57,9 @@ pub struct Building {
     pub label_center: Pt2D,
     // (Name, amenity)
     pub amenities: BTreeSet<(String, String)>,
+    pub bldg_type: BuildingType,
+    // From building:levels, defaulting to 1
+    pub levels: f64,
 
     pub front_path: FrontPath,
     // Every building can't have OffstreetParking, because the nearest usable driving lane (not in
@@ -0,0 +1,265 @@
+use crate::raw::{OriginalBuilding, OriginalIntersection, OriginalRoad, RawMap};
+use crate::{osm, Map, MapEdits, RoadSpec};
+use abstutil::Timer;
+use geom::{Distance, LonLat};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+// Intersections moving less than this are just noise from tweaking geometry
+const MOVED_THRESHOLD: Distance = Distance::const_meters(2.0);
+
+// Compares two versions of a map, usually built from different OSM snapshots. The map's own IDs
+// aren't stable between versions, so everything is keyed by OriginalRoad, OriginalIntersection,
+// and OriginalBuilding.
+#[derive(Serialize, Deserialize)]
+pub struct MapDiff {
+    pub map_name: String,
+
+    pub added_roads: Vec<OriginalRoad>,
+    // Also remember where the road used to be, since it's not in the new map
+    pub removed_roads: Vec<(OriginalRoad, Vec<LonLat>)>,
+    // Each change is described, like "lanes changed from dp/sp to ddp/sp"
+    pub changed_roads: Vec<(OriginalRoad, Vec<String>)>,
+
+    pub added_intersections: Vec<OriginalIntersection>,
+    pub removed_intersections: Vec<(OriginalIntersection, LonLat)>,
+    pub moved_intersections: Vec<(OriginalIntersection, Distance)>,
+
+    pub added_buildings: Vec<OriginalBuilding>,
+    pub removed_buildings: Vec<OriginalBuilding>,
+
+    // Saved edits that don't apply cleanly to the new map anymore, with the reason why. Only
+    // filled out when diffing a Map.
+    pub stale_edits: Vec<(String, String)>,
+}
+
+impl MapDiff {
+    pub fn between_maps(old: &Map, new: &Map, timer: &mut Timer) -> MapDiff {
+        timer.start("diff maps");
+        let mut diff = MapDiff::new(
+            new.get_name(),
+            Snapshot::from_map(old),
+            Snapshot::from_map(new),
+        );
+        timer.stop("diff maps");
+
+        timer.start("check saved edits");
+        for edits_name in abstutil::list_all_objects(abstutil::path_all_edits(new.get_name())) {
+            if let Err(err) = MapEdits::load(new, &edits_name, timer) {
+                diff.stale_edits.push((edits_name, err));
+            }
+        }
+        timer.stop("check saved edits");
+
+        diff
+    }
+
+    pub fn between_raw_maps(old: &RawMap, new: &RawMap) -> MapDiff {
+        MapDiff::new(&new.name, Snapshot::from_raw(old), Snapshot::from_raw(new))
+    }
+
+    fn new(map_name: &str, old: Snapshot, new: Snapshot) -> MapDiff {
+        let mut diff = MapDiff {
+            map_name: map_name.to_string(),
+            added_roads: Vec::new(),
+            removed_roads: Vec::new(),
+            changed_roads: Vec::new(),
+            added_intersections: Vec::new(),
+            removed_intersections: Vec::new(),
+            moved_intersections: Vec::new(),
+            added_buildings: Vec::new(),
+            removed_buildings: Vec::new(),
+            stale_edits: Vec::new(),
+        };
+
+        for (id, (pts, old_attribs)) in &old.roads {
+            if let Some((_, new_attribs)) = new.roads.get(id) {
+                let mut changes = Vec::new();
+                for (key, old_value) in old_attribs {
+                    let new_value = &new_attribs[key];
+                    if old_value != new_value {
+                        changes.push(format!(
+                            "{} changed from {} to {}",
+                            key, old_value, new_value
+                        ));
+                    }
+                }
+                if !changes.is_empty() {
+                    diff.changed_roads.push((*id, changes));
+                }
+            } else {
+                diff.removed_roads.push((*id, pts.clone()));
+            }
+        }
+        for id in new.roads.keys() {
+            if !old.roads.contains_key(id) {
+                diff.added_roads.push(*id);
+            }
+        }
+
+        for (id, old_pt) in &old.intersections {
+            if let Some(new_pt) = new.intersections.get(id) {
+                let dist = old_pt.gps_dist_meters(*new_pt);
+                if dist > MOVED_THRESHOLD {
+                    diff.moved_intersections.push((*id, dist));
+                }
+            } else {
+                diff.removed_intersections.push((*id, *old_pt));
+            }
+        }
+        for id in new.intersections.keys() {
+            if !old.intersections.contains_key(id) {
+                diff.added_intersections.push(*id);
+            }
+        }
+
+        diff.added_buildings = new.buildings.difference(&old.buildings).cloned().collect();
+        diff.removed_buildings = old.buildings.difference(&new.buildings).cloned().collect();
+
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added_roads.is_empty()
+            && self.removed_roads.is_empty()
+            && self.changed_roads.is_empty()
+            && self.added_intersections.is_empty()
+            && self.removed_intersections.is_empty()
+            && self.moved_intersections.is_empty()
+            && self.added_buildings.is_empty()
+            && self.removed_buildings.is_empty()
+            && self.stale_edits.is_empty()
+    }
+
+    // A one-line-per-category summary
+    pub fn summary(&self) -> Vec<String> {
+        vec![
+            format!(
+                "{} roads added, {} removed, {} changed",
+                self.added_roads.len(),
+                self.removed_roads.len(),
+                self.changed_roads.len()
+            ),
+            format!(
+                "{} intersections added, {} removed, {} moved",
+                self.added_intersections.len(),
+                self.removed_intersections.len(),
+                self.moved_intersections.len()
+            ),
+            format!(
+                "{} buildings added, {} removed",
+                self.added_buildings.len(),
+                self.removed_buildings.len()
+            ),
+            format!("{} saved edits don't apply anymore", self.stale_edits.len()),
+        ]
+    }
+
+    // The summary, then every individual difference
+    pub fn describe(&self) -> Vec<String> {
+        let mut lines = self.summary();
+        for r in &self.added_roads {
+            lines.push(format!("Added {}", r));
+        }
+        for (r, _) in &self.removed_roads {
+            lines.push(format!("Removed {}", r));
+        }
+        for (r, changes) in &self.changed_roads {
+            lines.push(format!("Changed {}: {}", r, changes.join(", ")));
+        }
+        for i in &self.added_intersections {
+            lines.push(format!("Added {}", i));
+        }
+        for (i, _) in &self.removed_intersections {
+            lines.push(format!("Removed {}", i));
+        }
+        for (i, dist) in &self.moved_intersections {
+            lines.push(format!("Moved {} by {}", i, dist));
+        }
+        for b in &self.added_buildings {
+            lines.push(format!("Added {}", b));
+        }
+        for b in &self.removed_buildings {
+            lines.push(format!("Removed {}", b));
+        }
+        for (name, err) in &self.stale_edits {
+            lines.push(format!("Edits \"{}\" don't apply: {}", name, err));
+        }
+        lines
+    }
+}
+
+// Just the things worth comparing, extracted from either a Map or a RawMap
+struct Snapshot {
+    // Geometry, then named attributes
+    roads: BTreeMap<OriginalRoad, (Vec<LonLat>, BTreeMap<&'static str, String>)>,
+    intersections: BTreeMap<OriginalIntersection, LonLat>,
+    buildings: BTreeSet<OriginalBuilding>,
+}
+
+impl Snapshot {
+    fn from_map(map: &Map) -> Snapshot {
+        let bounds = map.get_gps_bounds();
+        let mut roads = BTreeMap::new();
+        for r in map.all_roads() {
+            let (fwd, back) = r.get_lane_types();
+            let mut attribs = BTreeMap::new();
+            attribs.insert("name", r.get_name());
+            attribs.insert("highway", tag(&r.osm_tags, osm::HIGHWAY));
+            attribs.insert("lanes", RoadSpec { fwd, back }.to_string());
+            attribs.insert("speed limit", r.speed_limit.to_string());
+            roads.insert(
+                r.orig_id,
+                (bounds.must_convert_back(r.center_pts.points()), attribs),
+            );
+        }
+        Snapshot {
+            roads,
+            intersections: map
+                .all_intersections()
+                .iter()
+                .map(|i| {
+                    (
+                        i.orig_id,
+                        bounds.must_convert_back(&vec![i.polygon.center()])[0],
+                    )
+                })
+                .collect(),
+            buildings: map
+                .all_buildings()
+                .iter()
+                .map(|b| OriginalBuilding {
+                    osm_way_id: b.osm_way_id,
+                })
+                .collect(),
+        }
+    }
+
+    fn from_raw(map: &RawMap) -> Snapshot {
+        let mut roads = BTreeMap::new();
+        for (id, r) in &map.roads {
+            let mut attribs = BTreeMap::new();
+            attribs.insert("name", tag(&r.osm_tags, osm::NAME));
+            attribs.insert("highway", tag(&r.osm_tags, osm::HIGHWAY));
+            attribs.insert("lanes", r.get_spec().to_string());
+            attribs.insert("speed limit", tag(&r.osm_tags, osm::MAXSPEED));
+            roads.insert(
+                *id,
+                (map.gps_bounds.must_convert_back(&r.center_points), attribs),
+            );
+        }
+        Snapshot {
+            roads,
+            intersections: map
+                .intersections
+                .iter()
+                .map(|(id, i)| (*id, map.gps_bounds.must_convert_back(&vec![i.point])[0]))
+                .collect(),
+            buildings: map.buildings.keys().cloned().collect(),
+        }
+    }
+}
+
+fn tag(tags: &BTreeMap<String, String>, key: &str) -> String {
+    tags.get(key).cloned().unwrap_or_else(|| "none".to_string())
+}
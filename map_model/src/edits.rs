@@ -1,9 +1,10 @@
 use crate::raw::{OriginalIntersection, OriginalRoad};
 use crate::{
-    ControlStopSign, ControlTrafficSignal, IntersectionID, LaneID, LaneType, Map, RoadID, TurnID,
+    ControlStopSign, ControlTrafficSignal, Cordon, IntersectionID, LaneID, LaneType, Map,
+    PriceSchedule, RoadID, SignType, TurnID, DEFAULT_VALUE_OF_TIME,
 };
 use abstutil::{deserialize_btreemap, retain_btreemap, retain_btreeset, serialize_btreemap, Timer};
-use geom::Speed;
+use geom::{Distance, LonLat, Speed};
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, BTreeSet};
 
@@ -17,6 +18,13 @@ pub struct MapEdits {
     pub reversed_lanes: BTreeSet<LaneID>,
     pub original_intersections: BTreeMap<IntersectionID, EditIntersection>,
     pub changed_speed_limits: BTreeSet<RoadID>,
+    pub original_pockets: BTreeMap<LaneID, Option<Distance>>,
+    // The basemap never has any tolls or cordons
+    pub tolled_roads: BTreeSet<RoadID>,
+    pub cordons: BTreeSet<String>,
+
+    // In dollars per hour. How pathfinding trades off time against tolls and cordon charges.
+    pub value_of_time: f64,
 
     // Edits without these are player generated.
     pub proposal_description: Vec<String>,
@@ -53,6 +61,22 @@ pub enum EditCmd {
         new: EditIntersection,
         old: EditIntersection,
     },
+    ChangeTurnPocket {
+        id: LaneID,
+        new: Option<Distance>,
+        old: Option<Distance>,
+    },
+    ChangeToll {
+        id: RoadID,
+        new: Option<PriceSchedule>,
+        old: Option<PriceSchedule>,
+    },
+    // Cordons are identified by name
+    ChangeCordon {
+        name: String,
+        new: Option<Cordon>,
+        old: Option<Cordon>,
+    },
 }
 
 impl EditCmd {
@@ -62,10 +86,25 @@ impl EditCmd {
             EditCmd::ReverseLane { l, .. } => format!("reverse {}", l),
             EditCmd::ChangeSpeedLimit { id, new, .. } => format!("limit {} for {}", new, id),
             EditCmd::ChangeIntersection { i, new, .. } => match new {
+                EditIntersection::StopSign(ref ss) if ss.roundabout => {
+                    format!("roundabout #{}", i.0)
+                }
                 EditIntersection::StopSign(_) => format!("stop sign #{}", i.0),
                 EditIntersection::TrafficSignal(_) => format!("traffic signal #{}", i.0),
                 EditIntersection::Closed => format!("close {}", i),
             },
+            EditCmd::ChangeTurnPocket { id, new, .. } => match new {
+                Some(len) => format!("{} turn pocket on #{}", len, id.0),
+                None => format!("full length #{}", id.0),
+            },
+            EditCmd::ChangeToll { id, new, .. } => match new {
+                Some(schedule) => format!("{} toll on #{}", schedule.describe(), id.0),
+                None => format!("no toll on #{}", id.0),
+            },
+            EditCmd::ChangeCordon { name, new, .. } => match new {
+                Some(_) => format!("cordon {}", name),
+                None => format!("remove cordon {}", name),
+            },
         }
     }
 }
@@ -90,6 +129,11 @@ impl MapEdits {
             reversed_lanes: BTreeSet::new(),
             original_intersections: BTreeMap::new(),
             changed_speed_limits: BTreeSet::new(),
+            original_pockets: BTreeMap::new(),
+            tolled_roads: BTreeSet::new(),
+            cordons: BTreeSet::new(),
+
+            value_of_time: DEFAULT_VALUE_OF_TIME,
         }
     }
 
@@ -118,6 +162,9 @@ impl MapEdits {
         let mut reversed_lanes = BTreeSet::new();
         let mut orig_intersections: BTreeMap<IntersectionID, EditIntersection> = BTreeMap::new();
         let mut changed_speed_limits = BTreeSet::new();
+        let mut orig_pockets = BTreeMap::new();
+        let mut tolled_roads = BTreeSet::new();
+        let mut cordons = BTreeSet::new();
 
         for cmd in &self.commands {
             match cmd {
@@ -141,6 +188,17 @@ impl MapEdits {
                         orig_intersections.insert(*i, old.clone());
                     }
                 }
+                EditCmd::ChangeTurnPocket { id, old, .. } => {
+                    if !orig_pockets.contains_key(id) {
+                        orig_pockets.insert(*id, *old);
+                    }
+                }
+                EditCmd::ChangeToll { id, .. } => {
+                    tolled_roads.insert(*id);
+                }
+                EditCmd::ChangeCordon { name, .. } => {
+                    cordons.insert(name.clone());
+                }
             }
         }
 
@@ -151,11 +209,23 @@ impl MapEdits {
         retain_btreeset(&mut changed_speed_limits, |r| {
             map.get_r(*r).speed_limit != map.get_r(*r).speed_limit_from_osm()
         });
+        retain_btreemap(&mut orig_pockets, |l, len| {
+            map.get_l(*l).pocket_length != *len
+        });
+        retain_btreeset(&mut tolled_roads, |r| {
+            map.get_pricing().get_toll(*r).is_some()
+        });
+        retain_btreeset(&mut cordons, |name| {
+            map.get_pricing().get_cordon(name).is_some()
+        });
 
         self.original_lts = orig_lts;
         self.reversed_lanes = reversed_lanes;
         self.original_intersections = orig_intersections;
         self.changed_speed_limits = changed_speed_limits;
+        self.original_pockets = orig_pockets;
+        self.tolled_roads = tolled_roads;
+        self.cordons = cordons;
     }
 
     // Assumes update_derived has been called.
@@ -187,6 +257,27 @@ impl MapEdits {
                 old: map.get_r(*r).speed_limit_from_osm(),
             });
         }
+        for (l, old) in &self.original_pockets {
+            self.commands.push(EditCmd::ChangeTurnPocket {
+                id: *l,
+                new: map.get_l(*l).pocket_length,
+                old: *old,
+            });
+        }
+        for r in &self.tolled_roads {
+            self.commands.push(EditCmd::ChangeToll {
+                id: *r,
+                new: map.get_pricing().get_toll(*r).cloned(),
+                old: None,
+            });
+        }
+        for name in &self.cordons {
+            self.commands.push(EditCmd::ChangeCordon {
+                name: name.clone(),
+                new: map.get_pricing().get_cordon(name).cloned(),
+                old: None,
+            });
+        }
     }
 }
 
@@ -220,8 +311,46 @@ pub struct PermanentMapEdits {
     pub proposal_description: Vec<String>,
     // The link is optional even for proposals
     pub proposal_link: Option<String>,
+
+    // Edits saved before these existed won't have them.
+    #[serde(default)]
+    hints: EditHints,
+    #[serde(default = "default_value_of_time")]
+    value_of_time: f64,
+}
+
+fn default_value_of_time() -> f64 {
+    DEFAULT_VALUE_OF_TIME
+}
+
+// Where the roads and intersections referenced by the commands were, so migrate can find them again
+// after their OSM IDs change.
+#[derive(Serialize, Deserialize, Clone, Default)]
+struct EditHints {
+    #[serde(
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_btreemap"
+    )]
+    roads: BTreeMap<OriginalRoad, RoadHint>,
+    #[serde(
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_btreemap"
+    )]
+    intersections: BTreeMap<OriginalIntersection, LonLat>,
 }
 
+#[derive(Serialize, Deserialize, Clone)]
+struct RoadHint {
+    name: String,
+    // The ends of the road's center line
+    src: LonLat,
+    dst: LonLat,
+}
+
+// How far things can move between map versions and still be matched up
+const MAX_ROAD_MIGRATION_DIST: Distance = Distance::const_meters(50.0);
+const MAX_INTERSECTION_MIGRATION_DIST: Distance = Distance::const_meters(20.0);
+
 #[derive(Serialize, Deserialize, Clone)]
 enum PermanentEditIntersection {
     StopSign {
@@ -230,6 +359,11 @@ enum PermanentEditIntersection {
             deserialize_with = "deserialize_btreemap"
         )]
         must_stop: BTreeMap<OriginalRoad, bool>,
+        // Only roads not stopping can be here
+        #[serde(default)]
+        yield_signs: BTreeSet<OriginalRoad>,
+        #[serde(default)]
+        roundabout: bool,
     },
     TrafficSignal(seattle_traffic_signals::TrafficSignal),
     Closed,
@@ -267,6 +401,21 @@ enum PermanentEditCmd {
         new: PermanentEditIntersection,
         old: PermanentEditIntersection,
     },
+    ChangeTurnPocket {
+        id: OriginalLane,
+        new: Option<Distance>,
+        old: Option<Distance>,
+    },
+    ChangeToll {
+        id: OriginalRoad,
+        new: Option<PriceSchedule>,
+        old: Option<PriceSchedule>,
+    },
+    ChangeCordon {
+        name: String,
+        new: Option<Cordon>,
+        old: Option<Cordon>,
+    },
 }
 
 impl PermanentMapEdits {
@@ -305,8 +454,27 @@ impl PermanentMapEdits {
                             old: old.to_permanent(map),
                         }
                     }
+                    EditCmd::ChangeTurnPocket { id, new, old } => {
+                        PermanentEditCmd::ChangeTurnPocket {
+                            id: OriginalLane::to_permanent(*id, map),
+                            new: *new,
+                            old: *old,
+                        }
+                    }
+                    EditCmd::ChangeToll { id, new, old } => PermanentEditCmd::ChangeToll {
+                        id: map.get_r(*id).orig_id,
+                        new: new.clone(),
+                        old: old.clone(),
+                    },
+                    EditCmd::ChangeCordon { name, new, old } => PermanentEditCmd::ChangeCordon {
+                        name: name.clone(),
+                        new: new.clone(),
+                        old: old.clone(),
+                    },
                 })
                 .collect(),
+            hints: EditHints::new(edits, map),
+            value_of_time: edits.value_of_time,
         }
     }
 
@@ -318,49 +486,375 @@ impl PermanentMapEdits {
             commands: perma
                 .commands
                 .into_iter()
-                .map(|cmd| match cmd {
-                    PermanentEditCmd::ChangeLaneType { id, lt, orig_lt } => {
-                        let l = id.clone().from_permanent(map)?;
-                        if map.get_l(l).lane_type != orig_lt {
-                            return Err(format!("basemap lanetype of {:?} has changed", id));
-                        }
-                        Ok(EditCmd::ChangeLaneType { id: l, lt, orig_lt })
-                    }
-                    PermanentEditCmd::ReverseLane { l, dst_i } => {
-                        let l = l.from_permanent(map)?;
-                        let dst_i = map.find_i_by_osm_id(dst_i.osm_node_id)?;
-                        Ok(EditCmd::ReverseLane { l, dst_i })
-                    }
-                    PermanentEditCmd::ChangeSpeedLimit { id, new, old } => {
-                        let id = map.find_r_by_osm_id(
-                            id.osm_way_id,
-                            (id.i1.osm_node_id, id.i2.osm_node_id),
-                        )?;
-                        Ok(EditCmd::ChangeSpeedLimit { id, new, old })
-                    }
-                    PermanentEditCmd::ChangeIntersection { i, new, old } => {
-                        let id = map.find_i_by_osm_id(i.osm_node_id)?;
-                        Ok(EditCmd::ChangeIntersection {
-                            i: id,
-                            new: new
-                                .from_permanent(id, map)
-                                .ok_or(format!("new ChangeIntersection of {} invalid", i))?,
-                            old: old
-                                .from_permanent(id, map)
-                                .ok_or(format!("old ChangeIntersection of {} invalid", i))?,
-                        })
-                    }
-                })
+                .map(|cmd| cmd.from_permanent(map))
                 .collect::<Result<Vec<EditCmd>, String>>()?,
 
             original_lts: BTreeMap::new(),
             reversed_lanes: BTreeSet::new(),
             original_intersections: BTreeMap::new(),
             changed_speed_limits: BTreeSet::new(),
+            original_pockets: BTreeMap::new(),
+            tolled_roads: BTreeSet::new(),
+            cordons: BTreeSet::new(),
+
+            value_of_time: perma.value_of_time,
         };
         edits.update_derived(map);
         Ok(edits)
     }
+
+    // Like from_permanent, but when a command doesn't match this map anymore, tries to find the
+    // closest equivalent road or intersection using where things used to be and what they're
+    // called. Commands that still don't match are dropped. Also describes everything remapped or
+    // dropped. Like from_permanent, the map must not have any edits applied yet.
+    pub fn migrate(perma: PermanentMapEdits, map: &Map) -> (MapEdits, Vec<String>) {
+        let mut edits = MapEdits::new();
+        edits.edits_name = perma.edits_name;
+        edits.proposal_description = perma.proposal_description;
+        edits.proposal_link = perma.proposal_link;
+        edits.value_of_time = perma.value_of_time;
+
+        let mut report = Vec::new();
+        for cmd in perma.commands {
+            let describe = cmd.describe();
+            match cmd.clone().from_permanent(map) {
+                Ok(cmd) => {
+                    edits.commands.push(cmd);
+                }
+                Err(err) => match cmd.migrate(&perma.hints, map) {
+                    Ok(cmd) => {
+                        report.push(format!("Remapped {} ({})", describe, err));
+                        edits.commands.push(cmd);
+                    }
+                    Err(why) => {
+                        report.push(format!("Dropped {}: {}", describe, why));
+                    }
+                },
+            }
+        }
+        edits.update_derived(map);
+        (edits, report)
+    }
+}
+
+impl PermanentEditCmd {
+    fn from_permanent(self, map: &Map) -> Result<EditCmd, String> {
+        match self {
+            PermanentEditCmd::ChangeLaneType { id, lt, orig_lt } => {
+                let l = id.clone().from_permanent(map)?;
+                if map.get_l(l).lane_type != orig_lt {
+                    return Err(format!("basemap lanetype of {:?} has changed", id));
+                }
+                Ok(EditCmd::ChangeLaneType { id: l, lt, orig_lt })
+            }
+            PermanentEditCmd::ReverseLane { l, dst_i } => {
+                let l = l.from_permanent(map)?;
+                let dst_i = map.find_i_by_osm_id(dst_i.osm_node_id)?;
+                Ok(EditCmd::ReverseLane { l, dst_i })
+            }
+            PermanentEditCmd::ChangeSpeedLimit { id, new, old } => {
+                let id =
+                    map.find_r_by_osm_id(id.osm_way_id, (id.i1.osm_node_id, id.i2.osm_node_id))?;
+                Ok(EditCmd::ChangeSpeedLimit { id, new, old })
+            }
+            PermanentEditCmd::ChangeIntersection { i, new, old } => {
+                let id = map.find_i_by_osm_id(i.osm_node_id)?;
+                Ok(EditCmd::ChangeIntersection {
+                    i: id,
+                    new: new
+                        .from_permanent(id, map)
+                        .ok_or(format!("new ChangeIntersection of {} invalid", i))?,
+                    old: old
+                        .from_permanent(id, map)
+                        .ok_or(format!("old ChangeIntersection of {} invalid", i))?,
+                })
+            }
+            PermanentEditCmd::ChangeTurnPocket { id, new, old } => {
+                let l = id.from_permanent(map)?;
+                Ok(EditCmd::ChangeTurnPocket { id: l, new, old })
+            }
+            PermanentEditCmd::ChangeToll { id, new, old } => {
+                let id =
+                    map.find_r_by_osm_id(id.osm_way_id, (id.i1.osm_node_id, id.i2.osm_node_id))?;
+                Ok(EditCmd::ChangeToll { id, new, old })
+            }
+            PermanentEditCmd::ChangeCordon { name, new, old } => {
+                Ok(EditCmd::ChangeCordon { name, new, old })
+            }
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            PermanentEditCmd::ChangeLaneType { id, lt, .. } => {
+                format!("changing a lane of {} to {:?}", id.parent, lt)
+            }
+            PermanentEditCmd::ReverseLane { l, .. } => format!("reversing a lane of {}", l.parent),
+            PermanentEditCmd::ChangeSpeedLimit { id, new, .. } => {
+                format!("changing the speed limit of {} to {}", id, new)
+            }
+            PermanentEditCmd::ChangeIntersection { i, .. } => format!("changing {}", i),
+            PermanentEditCmd::ChangeTurnPocket { id, .. } => {
+                format!("changing a turn pocket of {}", id.parent)
+            }
+            PermanentEditCmd::ChangeToll { id, .. } => format!("changing the toll on {}", id),
+            PermanentEditCmd::ChangeCordon { name, .. } => format!("changing cordon {}", name),
+        }
+    }
+
+    // Called after from_permanent fails. The original values from the basemap are taken from the
+    // current map, since the old ones might not be true anymore.
+    fn migrate(self, hints: &EditHints, map: &Map) -> Result<EditCmd, String> {
+        match self {
+            PermanentEditCmd::ChangeLaneType { id, lt, orig_lt } => {
+                let l = hints.match_lane(&id, Some(orig_lt), map)?;
+                Ok(EditCmd::ChangeLaneType { id: l, lt, orig_lt })
+            }
+            PermanentEditCmd::ReverseLane { l, .. } => {
+                let l = hints.match_lane(&l, None, map)?;
+                // Reversing a lane from the basemap always makes it end where it used to start
+                Ok(EditCmd::ReverseLane {
+                    l,
+                    dst_i: map.get_l(l).src_i,
+                })
+            }
+            PermanentEditCmd::ChangeSpeedLimit { id, new, .. } => {
+                let (r, _) = hints.match_road(id, map)?;
+                Ok(EditCmd::ChangeSpeedLimit {
+                    id: r,
+                    new,
+                    old: map.get_r(r).speed_limit,
+                })
+            }
+            PermanentEditCmd::ChangeIntersection { i, new, .. } => {
+                let i = hints.match_intersection(i, map)?;
+                Ok(EditCmd::ChangeIntersection {
+                    i,
+                    new: hints.migrate_intersection(new, i, map)?,
+                    old: map.get_i_edit(i),
+                })
+            }
+            PermanentEditCmd::ChangeTurnPocket { id, new, .. } => {
+                let l = hints.match_lane(&id, None, map)?;
+                Ok(EditCmd::ChangeTurnPocket {
+                    id: l,
+                    new,
+                    old: map.get_l(l).pocket_length,
+                })
+            }
+            PermanentEditCmd::ChangeToll { id, new, .. } => {
+                let (r, _) = hints.match_road(id, map)?;
+                Ok(EditCmd::ChangeToll {
+                    id: r,
+                    new,
+                    old: map.get_pricing().get_toll(r).cloned(),
+                })
+            }
+            // Cordons are drawn with coordinates, so they always match
+            PermanentEditCmd::ChangeCordon { name, new, old } => {
+                Ok(EditCmd::ChangeCordon { name, new, old })
+            }
+        }
+    }
+}
+
+impl EditHints {
+    fn new(edits: &MapEdits, map: &Map) -> EditHints {
+        let mut hints = EditHints::default();
+        for cmd in &edits.commands {
+            match cmd {
+                EditCmd::ChangeLaneType { id, .. }
+                | EditCmd::ReverseLane { l: id, .. }
+                | EditCmd::ChangeTurnPocket { id, .. } => {
+                    hints.add_road(map.get_l(*id).parent, map);
+                }
+                EditCmd::ChangeSpeedLimit { id, .. } | EditCmd::ChangeToll { id, .. } => {
+                    hints.add_road(*id, map);
+                }
+                EditCmd::ChangeIntersection { i, .. } => {
+                    let i = map.get_i(*i);
+                    hints.intersections.insert(
+                        i.orig_id,
+                        i.polygon.center().to_gps(map.get_gps_bounds()).unwrap(),
+                    );
+                    // Stop signs refer to the roads too
+                    for r in &i.roads {
+                        hints.add_road(*r, map);
+                    }
+                }
+                EditCmd::ChangeCordon { .. } => {}
+            }
+        }
+        hints
+    }
+
+    fn add_road(&mut self, r: RoadID, map: &Map) {
+        let r = map.get_r(r);
+        let pts = map
+            .get_gps_bounds()
+            .must_convert_back(&vec![r.center_pts.first_pt(), r.center_pts.last_pt()]);
+        self.roads.insert(
+            r.orig_id,
+            RoadHint {
+                name: r.get_name(),
+                src: pts[0],
+                dst: pts[1],
+            },
+        );
+    }
+
+    // Also returns true if the matching road points the opposite way
+    fn match_road(&self, id: OriginalRoad, map: &Map) -> Result<(RoadID, bool), String> {
+        if let Ok(r) = map.find_r_by_osm_id(id.osm_way_id, (id.i1.osm_node_id, id.i2.osm_node_id)) {
+            return Ok((r, false));
+        }
+
+        // Maybe the OSM way was split, or redrawn between the same two intersections
+        for r in map.all_roads() {
+            let orig = r.orig_id;
+            let same_way = orig.osm_way_id == id.osm_way_id;
+            if (same_way && (orig.i1 == id.i1 || orig.i2 == id.i2))
+                || (orig.i1 == id.i1 && orig.i2 == id.i2)
+            {
+                return Ok((r.id, false));
+            }
+            if (same_way && (orig.i1 == id.i2 || orig.i2 == id.i1))
+                || (orig.i1 == id.i2 && orig.i2 == id.i1)
+            {
+                return Ok((r.id, true));
+            }
+        }
+
+        // Otherwise look nearby for a road with the same name
+        let hint = self
+            .roads
+            .get(&id)
+            .ok_or(format!("{} is gone, and there's no hint where it was", id))?;
+        let bounds = map.get_gps_bounds();
+        map.all_roads()
+            .iter()
+            .filter(|r| r.get_name() == hint.name)
+            .filter_map(|r| {
+                let pts = bounds
+                    .must_convert_back(&vec![r.center_pts.first_pt(), r.center_pts.last_pt()]);
+                let same = pts[0].gps_dist_meters(hint.src) + pts[1].gps_dist_meters(hint.dst);
+                let flipped = pts[0].gps_dist_meters(hint.dst) + pts[1].gps_dist_meters(hint.src);
+                let (dist, flipped) = if same <= flipped {
+                    (same, false)
+                } else {
+                    (flipped, true)
+                };
+                if dist <= MAX_ROAD_MIGRATION_DIST {
+                    Some((r.id, flipped, dist))
+                } else {
+                    None
+                }
+            })
+            .min_by(|a, b| a.2.partial_cmp(&b.2).unwrap())
+            .map(|(r, flipped, _)| (r, flipped))
+            .ok_or(format!(
+                "{} is gone, and nothing named {} is nearby",
+                id, hint.name
+            ))
+    }
+
+    fn match_intersection(
+        &self,
+        id: OriginalIntersection,
+        map: &Map,
+    ) -> Result<IntersectionID, String> {
+        if let Ok(i) = map.find_i_by_osm_id(id.osm_node_id) {
+            return Ok(i);
+        }
+        let pt = self
+            .intersections
+            .get(&id)
+            .ok_or(format!("{} is gone, and there's no hint where it was", id))?;
+        let bounds = map.get_gps_bounds();
+        map.all_intersections()
+            .iter()
+            .filter_map(|i| {
+                let dist = i.polygon.center().to_gps(bounds)?.gps_dist_meters(*pt);
+                if dist <= MAX_INTERSECTION_MIGRATION_DIST {
+                    Some((i.id, dist))
+                } else {
+                    None
+                }
+            })
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+            .map(|(i, _)| i)
+            .ok_or(format!("{} is gone, and no intersection is nearby", id))
+    }
+
+    // If the number of lanes changed, only lanes with a known type can be matched.
+    fn match_lane(
+        &self,
+        lane: &OriginalLane,
+        orig_lt: Option<LaneType>,
+        map: &Map,
+    ) -> Result<LaneID, String> {
+        let (r, flipped) = self.match_road(lane.parent, map)?;
+        let r = map.get_r(r);
+        let (children, orig_len) = match (lane.fwd != flipped, lane.fwd) {
+            (true, true) => (&r.children_forwards, lane.num_fwd),
+            (true, false) => (&r.children_forwards, lane.num_back),
+            (false, true) => (&r.children_backwards, lane.num_fwd),
+            (false, false) => (&r.children_backwards, lane.num_back),
+        };
+        if children.len() == orig_len
+            && orig_lt.map(|lt| children[lane.idx].1 == lt).unwrap_or(true)
+        {
+            return Ok(children[lane.idx].0);
+        }
+        let lt = orig_lt.ok_or(format!("number of lanes has changed on {}", r.orig_id))?;
+        children
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, child_lt))| *child_lt == lt)
+            .min_by_key(|(idx, _)| (*idx as isize - lane.idx as isize).abs())
+            .map(|(_, (l, _))| *l)
+            .ok_or(format!("no {:?} lane on {} anymore", lt, r.orig_id))
+    }
+
+    // Stop signs are matched road by road, leaving the default for anything that doesn't match.
+    fn migrate_intersection(
+        &self,
+        perma: PermanentEditIntersection,
+        i: IntersectionID,
+        map: &Map,
+    ) -> Result<EditIntersection, String> {
+        match perma {
+            PermanentEditIntersection::StopSign {
+                must_stop,
+                yield_signs,
+                roundabout,
+            } => {
+                let mut ss = ControlStopSign::new(map, i);
+                for (r, stop) in must_stop {
+                    let sign_type = if stop {
+                        SignType::Stop
+                    } else if yield_signs.contains(&r) {
+                        SignType::Yield
+                    } else {
+                        SignType::Uncontrolled
+                    };
+                    if let Ok((r, _)) = self.match_road(r, map) {
+                        if let Some(road) = ss.roads.get_mut(&r) {
+                            road.sign_type = sign_type;
+                        }
+                    }
+                }
+                ss.roundabout = roundabout;
+                Ok(EditIntersection::StopSign(ss))
+            }
+            PermanentEditIntersection::TrafficSignal(ts) => {
+                ControlTrafficSignal::import(ts, i, map)
+                    .map(EditIntersection::TrafficSignal)
+                    .ok_or("the traffic signal doesn't match the roads anymore".to_string())
+            }
+            PermanentEditIntersection::Closed => Ok(EditIntersection::Closed),
+        }
+    }
 }
 
 impl EditIntersection {
@@ -370,8 +864,15 @@ impl EditIntersection {
                 must_stop: ss
                     .roads
                     .iter()
-                    .map(|(r, val)| (map.get_r(*r).orig_id, val.must_stop))
+                    .map(|(r, val)| (map.get_r(*r).orig_id, val.sign_type == SignType::Stop))
                     .collect(),
+                yield_signs: ss
+                    .roads
+                    .iter()
+                    .filter(|(_, val)| val.sign_type == SignType::Yield)
+                    .map(|(r, _)| map.get_r(*r).orig_id)
+                    .collect(),
+                roundabout: ss.roundabout,
             },
             EditIntersection::TrafficSignal(ref ts) => {
                 PermanentEditIntersection::TrafficSignal(ts.export(map))
@@ -384,13 +885,24 @@ impl EditIntersection {
 impl PermanentEditIntersection {
     fn from_permanent(self, i: IntersectionID, map: &Map) -> Option<EditIntersection> {
         match self {
-            PermanentEditIntersection::StopSign { must_stop } => {
+            PermanentEditIntersection::StopSign {
+                must_stop,
+                yield_signs,
+                roundabout,
+            } => {
                 let mut translated_must_stop = BTreeMap::new();
                 for (r, stop) in must_stop {
+                    let sign_type = if stop {
+                        SignType::Stop
+                    } else if yield_signs.contains(&r) {
+                        SignType::Yield
+                    } else {
+                        SignType::Uncontrolled
+                    };
                     translated_must_stop.insert(
                         map.find_r_by_osm_id(r.osm_way_id, (r.i1.osm_node_id, r.i2.osm_node_id))
                             .ok()?,
-                        stop,
+                        sign_type,
                     );
                 }
 
@@ -399,9 +911,10 @@ impl PermanentEditIntersection {
                 if translated_must_stop.len() != ss.roads.len() {
                     return None;
                 }
-                for (r, stop) in translated_must_stop {
-                    ss.roads.get_mut(&r)?.must_stop = stop;
+                for (r, sign_type) in translated_must_stop {
+                    ss.roads.get_mut(&r)?.sign_type = sign_type;
                 }
+                ss.roundabout = roundabout;
 
                 Some(EditIntersection::StopSign(ss))
             }
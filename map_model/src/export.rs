@@ -0,0 +1,235 @@
+use crate::{IntersectionType, LaneType, Map, SignType};
+use geom::Pt2D;
+use serde_json::{json, Value};
+use std::fs::File;
+use std::io::{Error, Write};
+
+impl Map {
+    // Writes one GeoJSON file per layer (lanes, roads, intersections, buildings, bus stops,
+    // parking lots) into the directory, in WGS84. Whatever edits are currently applied are
+    // reflected. Returns the files written.
+    pub fn export_geojson(&self, dir: &str) -> Result<Vec<String>, Error> {
+        std::fs::create_dir_all(dir)?;
+        let mut paths = Vec::new();
+        for (name, features) in vec![
+            ("lanes", self.lane_features()),
+            ("roads", self.road_features()),
+            ("intersections", self.intersection_features()),
+            ("buildings", self.building_features()),
+            ("bus_stops", self.bus_stop_features()),
+            ("parking_lots", self.parking_lot_features()),
+        ] {
+            let path = format!("{}/{}.geojson", dir, name);
+            let mut f = File::create(&path)?;
+            writeln!(
+                f,
+                "{}",
+                json!({
+                    "type": "FeatureCollection",
+                    "features": features,
+                })
+            )?;
+            paths.push(path);
+        }
+        Ok(paths)
+    }
+
+    fn lane_features(&self) -> Vec<Value> {
+        self.all_lanes()
+            .iter()
+            .map(|l| {
+                let r = self.get_r(l.parent);
+                feature(
+                    line_string(self.gps(l.lane_center_pts.points())),
+                    json!({
+                        "id": l.id.0,
+                        "road": l.parent.0,
+                        "osm_way_id": r.orig_id.osm_way_id,
+                        "lane_type": format!("{:?}", l.lane_type),
+                        "forwards": r.is_forwards(l.id),
+                        "width_meters": l.width.inner_meters(),
+                        "length_meters": l.length().inner_meters(),
+                        "edits": self.edits_name(),
+                    }),
+                )
+            })
+            .collect()
+    }
+
+    fn road_features(&self) -> Vec<Value> {
+        self.all_roads()
+            .iter()
+            .map(|r| {
+                let (fwd, back) = r.get_lane_types();
+                let describe = |lts: Vec<LaneType>| -> Vec<String> {
+                    lts.into_iter().map(|lt| format!("{:?}", lt)).collect()
+                };
+                feature(
+                    line_string(self.gps(r.center_pts.points())),
+                    json!({
+                        "id": r.id.0,
+                        "osm_way_id": r.orig_id.osm_way_id,
+                        "name": r.get_name(),
+                        "highway": r.osm_tags.get(crate::osm::HIGHWAY),
+                        "speed_limit_meters_per_second": r.speed_limit.inner_meters_per_second(),
+                        "lanes_forwards": describe(fwd),
+                        "lanes_backwards": describe(back),
+                        "zorder": r.zorder,
+                        "roundabout": r.is_roundabout(),
+                        "edits": self.edits_name(),
+                    }),
+                )
+            })
+            .collect()
+    }
+
+    fn intersection_features(&self) -> Vec<Value> {
+        self.all_intersections()
+            .iter()
+            .map(|i| {
+                let mut props = json!({
+                    "id": i.id.0,
+                    "osm_node_id": i.orig_id.osm_node_id,
+                    "control": format!("{:?}", i.intersection_type),
+                    "roads": i.roads.iter().map(|r| r.0).collect::<Vec<_>>(),
+                    "edits": self.edits_name(),
+                });
+                match i.intersection_type {
+                    IntersectionType::StopSign => {
+                        let ss = self.get_stop_sign(i.id);
+                        let count = |sign_type| {
+                            ss.roads
+                                .values()
+                                .filter(|r| r.sign_type == sign_type)
+                                .count()
+                        };
+                        props["stop_signs"] = json!(count(SignType::Stop));
+                        props["yield_signs"] = json!(count(SignType::Yield));
+                        props["roundabout"] = json!(ss.roundabout);
+                    }
+                    IntersectionType::TrafficSignal => {
+                        let ts = self.get_traffic_signal(i.id);
+                        props["phases"] = json!(ts.phases.len());
+                        props["offset_seconds"] = json!(ts.offset.inner_seconds());
+                    }
+                    IntersectionType::Border | IntersectionType::Construction => {}
+                }
+                feature(polygon(self.gps(i.polygon.points())), props)
+            })
+            .collect()
+    }
+
+    fn building_features(&self) -> Vec<Value> {
+        self.all_buildings()
+            .iter()
+            .map(|b| {
+                let amenities: Vec<&String> = b.amenities.iter().map(|(_, a)| a).collect();
+                let garage = b
+                    .parking
+                    .as_ref()
+                    .and_then(|p| p.public_garage_name.clone());
+                feature(
+                    polygon(self.gps(b.polygon.points())),
+                    json!({
+                        "id": b.id.0,
+                        "osm_way_id": b.osm_way_id,
+                        "address": b.address,
+                        "name": b.name,
+                        "type": format!("{:?}", b.bldg_type),
+                        "levels": b.levels,
+                        "amenities": amenities,
+                        "parking_spots": b.parking.as_ref().map(|p| p.num_spots).unwrap_or(0),
+                        "public_garage": garage,
+                        "edits": self.edits_name(),
+                    }),
+                )
+            })
+            .collect()
+    }
+
+    fn bus_stop_features(&self) -> Vec<Value> {
+        self.all_bus_stops()
+            .values()
+            .map(|bs| {
+                let routes: Vec<&String> = self
+                    .get_all_bus_routes()
+                    .iter()
+                    .filter(|r| r.stops.contains(&bs.id))
+                    .map(|r| &r.name)
+                    .collect();
+                feature(
+                    point(self.gps(&vec![bs.sidewalk_pos.pt(self)])[0]),
+                    json!({
+                        "id": format!("{}", bs.id),
+                        "road": self.get_l(bs.sidewalk_pos.lane()).parent.0,
+                        "routes": routes,
+                        "edits": self.edits_name(),
+                    }),
+                )
+            })
+            .collect()
+    }
+
+    fn parking_lot_features(&self) -> Vec<Value> {
+        self.all_parking_lots()
+            .iter()
+            .map(|pl| {
+                feature(
+                    polygon(self.gps(pl.polygon.points())),
+                    json!({
+                        "id": pl.id.0,
+                        "osm_id": pl.osm_id,
+                        "capacity": pl.spots.len(),
+                        "edits": self.edits_name(),
+                    }),
+                )
+            })
+            .collect()
+    }
+
+    fn gps(&self, pts: &Vec<Pt2D>) -> Vec<[f64; 2]> {
+        self.get_gps_bounds()
+            .must_convert_back(pts)
+            .into_iter()
+            .map(|pt| [pt.x(), pt.y()])
+            .collect()
+    }
+
+    fn edits_name(&self) -> &str {
+        &self.get_edits().edits_name
+    }
+}
+
+fn feature(geometry: Value, properties: Value) -> Value {
+    json!({
+        "type": "Feature",
+        "geometry": geometry,
+        "properties": properties,
+    })
+}
+
+fn point(pt: [f64; 2]) -> Value {
+    json!({
+        "type": "Point",
+        "coordinates": pt,
+    })
+}
+
+fn line_string(pts: Vec<[f64; 2]>) -> Value {
+    json!({
+        "type": "LineString",
+        "coordinates": pts,
+    })
+}
+
+// Assumes the polygon's points trace its outline, which is true for anything not built from
+// triangles directly
+fn polygon(mut pts: Vec<[f64; 2]>) -> Value {
+    if pts.first() != pts.last() {
+        pts.push(pts[0]);
+    }
+    json!({
+        "type": "Polygon",
+        "coordinates": vec![pts],
+    })
+}
@@ -1,7 +1,7 @@
 use crate::pathfind;
 use crate::{
     osm, BuildingID, BusStopID, DirectedRoadID, IntersectionID, Map, PathConstraints, Road, RoadID,
-    TurnType,
+    Turn, TurnType,
 };
 use geom::{Angle, Distance, Line, PolyLine, Pt2D};
 use serde::{Deserialize, Serialize};
@@ -108,6 +108,10 @@ pub struct Lane {
     // If set, cars trying to park near here should actually start their search at this other lane.
     // Only populated for driving lanes inevitably leading to borders.
     pub parking_blackhole: Option<LaneID>,
+
+    // Turn pockets only physically exist for this distance before the end of the lane. The
+    // geometry still spans the whole road, so that turns at the start keep working.
+    pub pocket_length: Option<Distance>,
 }
 
 impl Lane {
@@ -164,6 +168,30 @@ impl Lane {
         self.lane_center_pts.length()
     }
 
+    // How much of the lane can actually hold vehicles.
+    pub fn usable_length(&self) -> Distance {
+        match self.pocket_length {
+            Some(len) => len.min(self.length()),
+            None => self.length(),
+        }
+    }
+
+    // Distance along the lane where a turn pocket begins
+    pub fn pocket_start(&self) -> Option<Distance> {
+        self.pocket_length
+            .map(|len| (self.length() - len).max(Distance::ZERO))
+    }
+
+    // The part of the lane that physically exists. Before a turn pocket begins, there's nothing.
+    pub fn physical_pts(&self) -> PolyLine {
+        match self.pocket_start() {
+            Some(start) if start > Distance::ZERO && start < self.length() => {
+                self.lane_center_pts.exact_slice(start, self.length())
+            }
+            _ => self.lane_center_pts.clone(),
+        }
+    }
+
     pub fn intersections(&self) -> Vec<IntersectionID> {
         // TODO I think we're assuming there are no loop lanes
         vec![self.src_i, self.dst_i]
@@ -236,8 +264,8 @@ impl Lane {
         Some(
             part.split(';')
                 .flat_map(|s| match s {
-                    "left" | "left\\left" => vec![TurnType::Left],
-                    "right" => vec![TurnType::Right],
+                    "left" | "left\\left" | "sharp_left" => vec![TurnType::Left],
+                    "right" | "sharp_right" => vec![TurnType::Right],
                     // TODO What is blank supposed to mean? From few observed cases, same as through
                     "through" | "" => vec![
                         TurnType::Straight,
@@ -245,14 +273,17 @@ impl Lane {
                         TurnType::LaneChangeRight,
                     ],
                     // TODO Check this more carefully
-                    "slight_right" | "slight right" | "merge_to_right" => vec![
+                    "slight_right" | "slight right" => vec![
                         TurnType::Straight,
                         TurnType::LaneChangeRight,
                         TurnType::Right,
                     ],
-                    "slight_left" | "slight left" | "merge_to_left" | "sharp_left" => {
+                    "slight_left" | "slight left" => {
                         vec![TurnType::Straight, TurnType::LaneChangeLeft, TurnType::Left]
                     }
+                    // The lane ends, so it has to move over, but doesn't turn
+                    "merge_to_right" => vec![TurnType::Straight, TurnType::LaneChangeRight],
+                    "merge_to_left" => vec![TurnType::Straight, TurnType::LaneChangeLeft],
                     "reverse" => {
                         // TODO We need TurnType::UTurn. Until then, u-turns usually show up as
                         // left turns.
@@ -264,6 +295,33 @@ impl Lane {
         )
     }
 
+    // Turns that the lane's turn:lanes markings don't allow only exist when honoring the markings
+    // would leave the lane with no way out.
+    pub fn turn_violates_markings(&self, turn: &Turn, map: &Map) -> bool {
+        self.get_turn_restrictions(map.get_r(self.parent))
+            .map(|types| !types.contains(&turn.turn_type))
+            .unwrap_or(false)
+    }
+
+    // A turn pocket branches off the nearest lane running the whole road. When the pocket is full,
+    // cars waiting to get in back up into that lane.
+    pub fn pocket_spills_into(&self, map: &Map) -> Option<LaneID> {
+        self.pocket_length?;
+        let road = map.get_r(self.parent);
+        let (fwds, idx) = road.dir_and_offset(self.id);
+        let siblings = if fwds {
+            &road.children_forwards
+        } else {
+            &road.children_backwards
+        };
+        siblings
+            .iter()
+            .enumerate()
+            .filter(|(_, (l, lt))| *lt == self.lane_type && map.get_l(*l).pocket_length.is_none())
+            .min_by_key(|(i, _)| (*i as isize - idx as isize).abs())
+            .map(|(_, (l, _))| *l)
+    }
+
     pub fn get_max_cost(&self, constraints: PathConstraints, map: &Map) -> usize {
         map.get_turns_to_lane(self.id)
             .into_iter()
@@ -276,3 +334,46 @@ impl Lane {
             })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lane(pocket_length: Option<Distance>) -> Lane {
+        Lane {
+            id: LaneID(0),
+            parent: RoadID(0),
+            lane_type: LaneType::Driving,
+            lane_center_pts: PolyLine::new(vec![Pt2D::new(0.0, 0.0), Pt2D::new(100.0, 0.0)]),
+            width: Distance::meters(3.0),
+            src_i: IntersectionID(0),
+            dst_i: IntersectionID(1),
+            building_paths: Vec::new(),
+            bus_stops: Vec::new(),
+            parking_blackhole: None,
+            pocket_length,
+        }
+    }
+
+    #[test]
+    fn test_pocket_lengths() {
+        // A normal lane exists the whole way
+        let full = lane(None);
+        assert_eq!(full.pocket_start(), None);
+        assert_eq!(full.usable_length(), Distance::meters(100.0));
+        assert_eq!(full.physical_pts().length(), Distance::meters(100.0));
+
+        // A pocket only exists for the last stretch
+        let pocket = lane(Some(Distance::meters(30.0)));
+        assert_eq!(pocket.pocket_start(), Some(Distance::meters(70.0)));
+        assert_eq!(pocket.usable_length(), Distance::meters(30.0));
+        assert_eq!(pocket.physical_pts().length(), Distance::meters(30.0));
+        assert_eq!(pocket.physical_pts().first_pt(), Pt2D::new(70.0, 0.0));
+
+        // Pockets mapped longer than the lane just cover all of it
+        let long = lane(Some(Distance::meters(150.0)));
+        assert_eq!(long.pocket_start(), Some(Distance::ZERO));
+        assert_eq!(long.usable_length(), Distance::meters(100.0));
+        assert_eq!(long.physical_pts().length(), Distance::meters(100.0));
+    }
+}
@@ -3,7 +3,9 @@ mod building;
 mod bus_stop;
 mod city;
 pub mod connectivity;
+mod diff;
 mod edits;
+mod export;
 mod intersection;
 mod lane;
 mod make;
@@ -11,17 +13,20 @@ mod map;
 pub mod osm;
 mod parking_lot;
 mod pathfind;
+mod pricing;
 pub mod raw;
 mod road;
 mod stop_signs;
 mod traffic_signals;
 mod traversable;
 mod turn;
+mod validate;
 
 pub use crate::area::{Area, AreaID, AreaType};
-pub use crate::building::{Building, BuildingID, FrontPath, OffstreetParking};
+pub use crate::building::{Building, BuildingID, BuildingType, FrontPath, OffstreetParking};
 pub use crate::bus_stop::{BusRoute, BusRouteID, BusStop, BusStopID};
 pub use crate::city::City;
+pub use crate::diff::MapDiff;
 pub use crate::edits::{
     EditCmd, EditEffects, EditIntersection, MapEdits, OriginalLane, PermanentMapEdits,
 };
@@ -32,11 +37,13 @@ pub use crate::map::Map;
 pub use crate::parking_lot::{ParkingLot, ParkingLotID};
 pub use crate::pathfind::uber_turns::{IntersectionCluster, UberTurn, UberTurnGroup};
 pub use crate::pathfind::{Path, PathConstraints, PathRequest, PathStep};
+pub use crate::pricing::{Charge, Cordon, PriceSchedule, Pricing, DEFAULT_VALUE_OF_TIME};
 pub use crate::road::{DirectedRoadID, Road, RoadID};
-pub use crate::stop_signs::{ControlStopSign, RoadWithStopSign};
+pub use crate::stop_signs::{ControlStopSign, RoadWithStopSign, SignType};
 pub use crate::traffic_signals::{ControlTrafficSignal, Phase};
 pub use crate::traversable::{Position, Traversable};
 pub use crate::turn::{Turn, TurnGroup, TurnGroupID, TurnID, TurnPriority, TurnType};
+pub use crate::validate::{Check, Problem, ValidationReport};
 use abstutil::Cloneable;
 use geom::Distance;
 
@@ -1,12 +1,12 @@
 use crate::make::sidewalk_finder::find_sidewalk_points;
 use crate::raw::{OriginalBuilding, RawBuilding, RawParkingLot};
 use crate::{
-    osm, Building, BuildingID, FrontPath, LaneID, LaneType, Map, OffstreetParking, ParkingLot,
-    ParkingLotID, Position, NORMAL_LANE_THICKNESS, PARKING_LOT_SPOT_LENGTH,
+    osm, Building, BuildingID, BuildingType, FrontPath, LaneID, LaneType, Map, OffstreetParking,
+    ParkingLot, ParkingLotID, Position, NORMAL_LANE_THICKNESS, PARKING_LOT_SPOT_LENGTH,
 };
 use abstutil::Timer;
 use geom::{Angle, Distance, HashablePt2D, Line, PolyLine, Polygon, Pt2D, Ring};
-use std::collections::{BTreeMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 
 pub fn make_all_buildings(
     input: &BTreeMap<OriginalBuilding, RawBuilding>,
@@ -67,6 +67,13 @@ pub fn make_all_buildings(
                     line: sidewalk_line.clone(),
                 },
                 amenities: b.amenities.clone(),
+                bldg_type: classify_bldg(&b.osm_tags, &b.amenities),
+                levels: b
+                    .osm_tags
+                    .get("building:levels")
+                    .and_then(|x| x.parse::<f64>().ok())
+                    .filter(|x| *x >= 1.0)
+                    .unwrap_or(1.0),
                 parking: None,
                 label_center: b.polygon.polylabel(),
             };
@@ -340,3 +347,66 @@ fn line_valid(
 
     true
 }
+
+fn classify_bldg(
+    tags: &BTreeMap<String, String>,
+    amenities: &BTreeSet<(String, String)>,
+) -> BuildingType {
+    // https://wiki.openstreetmap.org/wiki/Key:building
+    match tags.get("building").map(|x| x.as_str()) {
+        Some("house")
+        | Some("detached")
+        | Some("semidetached_house")
+        | Some("terrace")
+        | Some("bungalow")
+        | Some("residential")
+        | Some("apartments")
+        | Some("dormitory")
+        | Some("houseboat")
+        | Some("static_caravan") => {
+            if amenities.is_empty() {
+                BuildingType::Residential
+            } else {
+                BuildingType::ResidentialCommercial
+            }
+        }
+        Some("commercial")
+        | Some("office")
+        | Some("retail")
+        | Some("supermarket")
+        | Some("kiosk")
+        | Some("industrial")
+        | Some("warehouse")
+        | Some("hotel")
+        | Some("school")
+        | Some("university")
+        | Some("college")
+        | Some("kindergarten")
+        | Some("hospital")
+        | Some("civic")
+        | Some("public")
+        | Some("government")
+        | Some("church")
+        | Some("train_station")
+        | Some("transportation") => BuildingType::Commercial,
+        Some("garage") | Some("garages") | Some("shed") | Some("carport") | Some("roof")
+        | Some("hut") | Some("cabin") | Some("parking") | Some("service") | Some("ruins") => {
+            BuildingType::Empty
+        }
+        // building=yes and anything unusual. Taller buildings with shops are probably mixed-use.
+        _ => {
+            if amenities.is_empty() {
+                BuildingType::Residential
+            } else if tags
+                .get("building:levels")
+                .and_then(|x| x.parse::<f64>().ok())
+                .unwrap_or(1.0)
+                > 1.0
+            {
+                BuildingType::ResidentialCommercial
+            } else {
+                BuildingType::Commercial
+            }
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use crate::{osm, LaneType};
+use geom::Distance;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 use std::{fmt, iter};
@@ -17,7 +18,7 @@ pub fn get_lane_types(osm_tags: &BTreeMap<String, String>) -> (Vec<LaneType>, Ve
     if osm_tags.get("railway") == Some(&"light_rail".to_string()) {
         return (vec![LaneType::LightRail], Vec::new());
     }
-    if osm_tags.get("junction") == Some(&"roundabout".to_string()) {
+    if osm_tags.get(osm::JUNCTION) == Some(&"roundabout".to_string()) {
         return (vec![LaneType::Driving, LaneType::Sidewalk], Vec::new());
     }
     if osm_tags.get(osm::HIGHWAY) == Some(&"footway".to_string()) {
@@ -143,7 +144,8 @@ pub fn get_lane_types(osm_tags: &BTreeMap<String, String>) -> (Vec<LaneType>, Ve
         }
     }
 
-    // TODO Need to snap separate sidewalks to ways. Until then, just do this.
+    // convert_osm can snap separate sidewalks to their roads, but that's optional. If it didn't
+    // happen, just assume both sides.
     if osm_tags.get(osm::SIDEWALK) == Some(&"both".to_string())
         || osm_tags.get(osm::SIDEWALK) == Some(&"separate".to_string())
     {
@@ -158,6 +160,23 @@ pub fn get_lane_types(osm_tags: &BTreeMap<String, String>) -> (Vec<LaneType>, Ve
     (fwd_side, back_side)
 }
 
+// Parses osm::TURN_POCKETS_FWD or osm::TURN_POCKETS_BACK into the pocket length for each lane
+// offset. Malformed entries are skipped.
+pub fn get_turn_pockets(value: Option<&String>) -> BTreeMap<usize, Distance> {
+    let mut pockets = BTreeMap::new();
+    if let Some(value) = value {
+        for part in value.split(';') {
+            let mut pair = part.split(':');
+            if let (Some(idx), Some(len)) = (pair.next(), pair.next()) {
+                if let (Ok(idx), Ok(len)) = (idx.parse::<usize>(), len.parse::<f64>()) {
+                    pockets.insert(idx, Distance::meters(len));
+                }
+            }
+        }
+    }
+    pockets
+}
+
 // This is a convenient way for map_editor to plumb instructions here.
 #[derive(Serialize, Deserialize)]
 pub struct RoadSpec {
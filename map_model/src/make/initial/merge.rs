@@ -0,0 +1,283 @@
+use crate::make::initial::InitialMap;
+use crate::pathfind::uber_turns::IntersectionCluster;
+use crate::raw::{OriginalIntersection, OriginalRoad};
+use crate::IntersectionType;
+use abstutil::Timer;
+use geom::Distance;
+use std::collections::BTreeSet;
+
+// Measured between the OSM nodes, before trimming back from the intersections
+const SHORT_ROAD_THRESHOLD: Distance = Distance::const_meters(20.0);
+
+// Dual carriageways and short connector roads produce clusters of tiny intersections, which break
+// intersection geometry and make neighboring signals fight each other. Collapse each cluster with
+// a traffic signal into one intersection, so one signal controls the whole junction.
+pub fn merge_short_roads(m: &mut InitialMap, timer: &mut Timer) {
+    let mut visited = BTreeSet::new();
+    let mut clusters = Vec::new();
+    for i in m.intersections.keys() {
+        if visited.contains(i) {
+            continue;
+        }
+        let cluster =
+            IntersectionCluster::flood_short_roads(*i, SHORT_ROAD_THRESHOLD, |i| neighbors(m, i));
+        visited.extend(cluster.iter().cloned());
+        if cluster.len() > 1
+            && cluster
+                .iter()
+                .any(|i| m.intersections[i].intersection_type == IntersectionType::TrafficSignal)
+        {
+            clusters.push(cluster);
+        }
+    }
+
+    let mut merged = 0;
+    let mut removed_roads = 0;
+    for cluster in &clusters {
+        if let Some(r) = ring_closing_road(m, cluster) {
+            // Merging would delete a real road, so leave these intersections alone.
+            timer.warn(format!(
+                "Not merging a cluster of {} intersections, because {} closes a ring",
+                cluster.len(),
+                r
+            ));
+            continue;
+        }
+        merged += 1;
+        removed_roads += merge_cluster(m, cluster, timer);
+    }
+    if merged > 0 {
+        timer.note(format!(
+            "Merged {} clusters of intersections, removing {} short roads",
+            merged, removed_roads
+        ));
+    }
+}
+
+// A long road with both ends in the cluster would become a loop after merging.
+fn ring_closing_road(
+    m: &InitialMap,
+    cluster: &BTreeSet<OriginalIntersection>,
+) -> Option<OriginalRoad> {
+    for i in cluster {
+        for r in &m.intersections[i].roads {
+            let road = &m.roads[r];
+            if cluster.contains(&road.src_i)
+                && cluster.contains(&road.dst_i)
+                && road.original_center_pts.length() > SHORT_ROAD_THRESHOLD
+            {
+                return Some(*r);
+            }
+        }
+    }
+    None
+}
+
+fn neighbors(m: &InitialMap, i: OriginalIntersection) -> Vec<(OriginalIntersection, Distance)> {
+    // Borders have to stay attached to exactly one road
+    if m.intersections[&i].intersection_type == IntersectionType::Border {
+        return Vec::new();
+    }
+    m.intersections[&i]
+        .roads
+        .iter()
+        .filter_map(|r| {
+            let r = &m.roads[r];
+            let other = if r.src_i == i { r.dst_i } else { r.src_i };
+            if m.intersections[&other].intersection_type == IntersectionType::Border {
+                None
+            } else {
+                Some((other, r.original_center_pts.length()))
+            }
+        })
+        .collect()
+}
+
+// Returns the number of roads removed
+fn merge_cluster(
+    m: &mut InitialMap,
+    cluster: &BTreeSet<OriginalIntersection>,
+    timer: &mut Timer,
+) -> usize {
+    // Keep the intersection that already has a signal, so its signal config still applies. If
+    // there are several, the choice is arbitrary, but deterministic.
+    let keep = *cluster
+        .iter()
+        .find(|i| m.intersections[i].intersection_type == IntersectionType::TrafficSignal)
+        .unwrap();
+
+    let mut all_roads = BTreeSet::new();
+    for id in cluster {
+        if *id == keep {
+            all_roads.extend(m.intersections[id].roads.iter().cloned());
+        } else {
+            all_roads.extend(m.intersections.remove(id).unwrap().roads);
+        }
+    }
+
+    let mut removed = 0;
+    let mut remaining_roads = BTreeSet::new();
+    for id in all_roads {
+        let road = m.roads.get_mut(&id).unwrap();
+        if cluster.contains(&road.src_i) && cluster.contains(&road.dst_i) {
+            // Any road inside the cluster would become a loop. These are all short roads; clusters
+            // with a longer road closing a ring aren't merged.
+            m.roads.remove(&id);
+            removed += 1;
+            continue;
+        }
+        // The road's points still end at the old intersection; geometry handles merged
+        // intersections with different endpoints.
+        if cluster.contains(&road.src_i) {
+            road.src_i = keep;
+        } else {
+            road.dst_i = keep;
+        }
+        remaining_roads.insert(id);
+    }
+
+    if remaining_roads.is_empty() {
+        timer.warn(format!("Merging into {} left no roads; removing it", keep));
+        m.intersections.remove(&keep);
+    } else {
+        let i = m.intersections.get_mut(&keep).unwrap();
+        i.roads = remaining_roads;
+        i.intersection_type = IntersectionType::TrafficSignal;
+    }
+    removed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::make::initial::{Intersection, Road};
+    use crate::osm;
+    use crate::raw::RawRoad;
+    use geom::{Bounds, Pt2D};
+    use std::collections::BTreeMap;
+
+    fn blank() -> InitialMap {
+        InitialMap {
+            roads: BTreeMap::new(),
+            intersections: BTreeMap::new(),
+            bounds: Bounds::new(),
+        }
+    }
+
+    fn intersection(
+        m: &mut InitialMap,
+        id: i64,
+        intersection_type: IntersectionType,
+    ) -> OriginalIntersection {
+        let id = OriginalIntersection { osm_node_id: id };
+        m.intersections.insert(
+            id,
+            Intersection {
+                id,
+                polygon: Vec::new(),
+                roads: BTreeSet::new(),
+                intersection_type,
+                elevation: Distance::ZERO,
+            },
+        );
+        id
+    }
+
+    fn road(
+        m: &mut InitialMap,
+        osm_way_id: i64,
+        i1: OriginalIntersection,
+        i2: OriginalIntersection,
+        pts: Vec<Pt2D>,
+    ) -> OriginalRoad {
+        let id = OriginalRoad { osm_way_id, i1, i2 };
+        let mut osm_tags = BTreeMap::new();
+        osm_tags.insert(osm::HIGHWAY.to_string(), "residential".to_string());
+        let raw = RawRoad {
+            center_points: pts,
+            osm_tags,
+            turn_restrictions: Vec::new(),
+            complicated_turn_restrictions: Vec::new(),
+        };
+        m.roads.insert(id, Road::new(id, &raw));
+        m.intersections.get_mut(&i1).unwrap().roads.insert(id);
+        m.intersections.get_mut(&i2).unwrap().roads.insert(id);
+        id
+    }
+
+    fn pt(x: f64, y: f64) -> Pt2D {
+        Pt2D::new(x, y)
+    }
+
+    // A long road into a signal, then a short road to an intersection of far_type, then another
+    // long road.
+    fn dual_carriageway(
+        m: &mut InitialMap,
+        far_type: IntersectionType,
+    ) -> (OriginalIntersection, OriginalIntersection, OriginalRoad) {
+        let w = intersection(m, 1, IntersectionType::StopSign);
+        let a = intersection(m, 2, IntersectionType::TrafficSignal);
+        let x = intersection(m, 3, far_type);
+        let e = intersection(m, 4, IntersectionType::StopSign);
+        road(m, 10, w, a, vec![pt(-100.0, 0.0), pt(0.0, 0.0)]);
+        road(m, 11, a, x, vec![pt(0.0, 0.0), pt(10.0, 0.0)]);
+        let after = road(m, 12, x, e, vec![pt(10.0, 0.0), pt(110.0, 0.0)]);
+        (a, x, after)
+    }
+
+    #[test]
+    fn test_merge_cluster_with_signal() {
+        let mut m = blank();
+        let (a, x, after) = dual_carriageway(&mut m, IntersectionType::StopSign);
+        merge_short_roads(&mut m, &mut Timer::throwaway());
+
+        assert!(!m.intersections.contains_key(&x));
+        assert_eq!(m.roads.len(), 2);
+        assert_eq!(m.roads[&after].src_i, a);
+        assert_eq!(m.intersections[&a].roads.len(), 2);
+        assert_eq!(
+            m.intersections[&a].intersection_type,
+            IntersectionType::TrafficSignal
+        );
+    }
+
+    #[test]
+    fn test_no_signal_no_merge() {
+        let mut m = blank();
+        let w = intersection(&mut m, 1, IntersectionType::StopSign);
+        let a = intersection(&mut m, 2, IntersectionType::StopSign);
+        road(&mut m, 10, w, a, vec![pt(0.0, 0.0), pt(10.0, 0.0)]);
+        merge_short_roads(&mut m, &mut Timer::throwaway());
+
+        assert_eq!(m.intersections.len(), 2);
+        assert_eq!(m.roads.len(), 1);
+    }
+
+    #[test]
+    fn test_borders_stay_separate() {
+        let mut m = blank();
+        let (_, x, _) = dual_carriageway(&mut m, IntersectionType::Border);
+        merge_short_roads(&mut m, &mut Timer::throwaway());
+
+        assert!(m.intersections.contains_key(&x));
+        assert_eq!(m.roads.len(), 3);
+    }
+
+    #[test]
+    fn test_ring_not_merged() {
+        let mut m = blank();
+        let (a, x, _) = dual_carriageway(&mut m, IntersectionType::StopSign);
+        // A longer way around also connects the two intersections
+        road(
+            &mut m,
+            13,
+            a,
+            x,
+            vec![pt(0.0, 0.0), pt(5.0, 30.0), pt(10.0, 0.0)],
+        );
+        merge_short_roads(&mut m, &mut Timer::throwaway());
+
+        assert!(m.intersections.contains_key(&x));
+        assert_eq!(m.roads.len(), 4);
+    }
+}
@@ -1,9 +1,10 @@
 mod geometry;
 pub mod lane_specs;
+mod merge;
 
 pub use self::geometry::intersection_polygon;
 use crate::raw::{OriginalIntersection, OriginalRoad, RawMap, RawRoad};
-use crate::{IntersectionType, LaneType, NORMAL_LANE_THICKNESS, SIDEWALK_THICKNESS};
+use crate::{osm, IntersectionType, LaneType, NORMAL_LANE_THICKNESS, SIDEWALK_THICKNESS};
 use abstutil::Timer;
 use geom::{Bounds, Distance, PolyLine, Pt2D};
 use std::collections::{BTreeMap, BTreeSet};
@@ -100,6 +101,12 @@ impl InitialMap {
             m.roads.insert(*id, Road::new(*id, r));
         }
 
+        if raw.merge_short_roads {
+            timer.start("merge short roads");
+            merge::merge_short_roads(&mut m, timer);
+            timer.stop("merge short roads");
+        }
+
         timer.start_iter("find each intersection polygon", m.intersections.len());
         for i in m.intersections.values_mut() {
             timer.next();
@@ -114,22 +121,27 @@ impl InitialMap {
 pub struct LaneSpec {
     pub lane_type: LaneType,
     pub reverse_pts: bool,
+    pub pocket_length: Option<Distance>,
 }
 
 pub fn get_lane_specs(osm_tags: &BTreeMap<String, String>) -> Vec<LaneSpec> {
     let (side1_types, side2_types) = lane_specs::get_lane_types(osm_tags);
+    let pockets_fwd = lane_specs::get_turn_pockets(osm_tags.get(osm::TURN_POCKETS_FWD));
+    let pockets_back = lane_specs::get_turn_pockets(osm_tags.get(osm::TURN_POCKETS_BACK));
 
     let mut specs: Vec<LaneSpec> = Vec::new();
-    for lane_type in side1_types {
+    for (idx, lane_type) in side1_types.into_iter().enumerate() {
         specs.push(LaneSpec {
             lane_type,
             reverse_pts: false,
+            pocket_length: pockets_fwd.get(&idx).cloned(),
         });
     }
-    for lane_type in side2_types {
+    for (idx, lane_type) in side2_types.into_iter().enumerate() {
         specs.push(LaneSpec {
             lane_type,
             reverse_pts: true,
+            pocket_length: pockets_back.get(&idx).cloned(),
         });
     }
     if specs.is_empty() {
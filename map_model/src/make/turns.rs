@@ -1,11 +1,11 @@
 use crate::raw::{DrivingSide, RestrictionType};
 use crate::{
-    Intersection, IntersectionID, Lane, LaneID, LaneType, Road, RoadID, Turn, TurnID, TurnType,
+    osm, Intersection, IntersectionID, Lane, LaneID, LaneType, Road, RoadID, Turn, TurnID, TurnType,
 };
 use abstutil::{wraparound_get, Timer, Warn};
 use geom::{Distance, Line, PolyLine, Pt2D, Ring};
 use nbez::{Bez3o, BezCurve, Point2d};
-use std::collections::{BTreeSet, HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 
 // TODO Add proper warnings when the geometry is too small to handle.
 
@@ -227,9 +227,11 @@ fn make_vehicle_turns(
                     }
                     TurnType::Right => {
                         for (idx, l1) in incoming.iter().enumerate() {
+                            // Lanes explicitly marked for this movement always get the turn.
+                            let marked = is_turn_marked(*l1, tt, all_roads, lanes);
                             for l2 in &outgoing {
                                 let turn = make_vehicle_turn(lanes, i.id, *l1, *l2, tt);
-                                if idx == incoming.len() - 1 {
+                                if idx == incoming.len() - 1 || marked {
                                     result.push(turn);
                                 } else {
                                     maybe_add_turns.push(turn);
@@ -239,9 +241,10 @@ fn make_vehicle_turns(
                     }
                     TurnType::Left => {
                         for (idx, l1) in incoming.iter().enumerate() {
+                            let marked = is_turn_marked(*l1, tt, all_roads, lanes);
                             for l2 in &outgoing {
                                 let turn = make_vehicle_turn(lanes, i.id, *l1, *l2, tt);
-                                if idx == 0 {
+                                if idx == 0 || marked {
                                     result.push(turn);
                                 } else {
                                     maybe_add_turns.push(turn);
@@ -373,7 +376,9 @@ fn make_walking_turns(
         if let Some(l1) = get_sidewalk(lanes, roads[idx1].incoming_lanes(i.id)) {
             // Make the crosswalk to the other side
             if let Some(l2) = get_sidewalk(lanes, roads[idx1].outgoing_lanes(i.id)) {
-                result.extend(make_crosswalks(i.id, l1, l2));
+                if crossing_mapped(roads[idx1], i.id) {
+                    result.extend(make_crosswalks(i.id, l1, l2));
+                }
             }
 
             // Find the shared corner
@@ -628,6 +633,21 @@ fn turn_id(parent: IntersectionID, src: LaneID, dst: LaneID) -> TurnID {
     TurnID { parent, src, dst }
 }
 
+// When crossings are mapped anywhere along a road, only use the ones that exist. Roads without
+// any crossing data keep the inferred crosswalks, so their sidewalks don't get disconnected.
+fn crossing_mapped(r: &Road, i: IntersectionID) -> bool {
+    crossing_tagged(&r.osm_tags, r.dst_i == i)
+}
+
+fn crossing_tagged(osm_tags: &BTreeMap<String, String>, at_end: bool) -> bool {
+    let (here, there) = if at_end {
+        (osm::CROSSING_FWD, osm::CROSSING_BACK)
+    } else {
+        (osm::CROSSING_BACK, osm::CROSSING_FWD)
+    };
+    osm_tags.contains_key(here) || !osm_tags.contains_key(there)
+}
+
 fn get_sidewalk<'a>(lanes: &'a Vec<Lane>, children: &Vec<(LaneID, LaneType)>) -> Option<&'a Lane> {
     for (id, lt) in children {
         if *lt == LaneType::Sidewalk {
@@ -721,6 +741,15 @@ fn is_turn_allowed(turn: &Turn, roads: &Vec<Road>, lanes: &Vec<Lane>) -> bool {
     }
 }
 
+// Does the source lane have turn:lanes markings that explicitly allow this movement? Dedicated
+// turn lanes that aren't the leftmost or rightmost lane still need to get the turn.
+fn is_turn_marked(l: LaneID, turn_type: TurnType, roads: &Vec<Road>, lanes: &Vec<Lane>) -> bool {
+    let lane = &lanes[l.0];
+    lane.get_turn_restrictions(&roads[lane.parent.0])
+        .map(|types| types.contains(&turn_type))
+        .unwrap_or(false)
+}
+
 fn does_turn_pass_restrictions(
     turn: &Turn,
     intersection_roads: &BTreeSet<RoadID>,
@@ -755,3 +784,33 @@ fn does_turn_pass_restrictions(
 
     true
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tags(keys: Vec<&str>) -> BTreeMap<String, String> {
+        keys.into_iter()
+            .map(|k| (k.to_string(), "true".to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_crossing_tagged() {
+        // No crossing data at all means keep the inferred crosswalks on both ends
+        assert!(crossing_tagged(&tags(vec![]), true));
+        assert!(crossing_tagged(&tags(vec![]), false));
+
+        // A crossing mapped at one end removes the crosswalk from the other
+        let fwd = tags(vec![osm::CROSSING_FWD]);
+        assert!(crossing_tagged(&fwd, true));
+        assert!(!crossing_tagged(&fwd, false));
+        let back = tags(vec![osm::CROSSING_BACK]);
+        assert!(!crossing_tagged(&back, true));
+        assert!(crossing_tagged(&back, false));
+
+        let both = tags(vec![osm::CROSSING_FWD, osm::CROSSING_BACK]);
+        assert!(crossing_tagged(&both, true));
+        assert!(crossing_tagged(&both, false));
+    }
+}
@@ -4,11 +4,11 @@ use crate::{
     connectivity, make, osm, Area, AreaID, Building, BuildingID, BusRoute, BusRouteID, BusStop,
     BusStopID, ControlStopSign, ControlTrafficSignal, EditCmd, EditEffects, EditIntersection,
     Intersection, IntersectionID, IntersectionType, Lane, LaneID, LaneType, MapEdits, ParkingLot,
-    ParkingLotID, Path, PathConstraints, PathRequest, Position, Road, RoadID, Turn, TurnGroupID,
-    TurnID, TurnType, NORMAL_LANE_THICKNESS, SIDEWALK_THICKNESS,
+    ParkingLotID, Path, PathConstraints, PathRequest, Position, Pricing, Road, RoadID, Turn,
+    TurnGroupID, TurnID, TurnType, NORMAL_LANE_THICKNESS, SIDEWALK_THICKNESS,
 };
 use abstutil::{deserialize_btreemap, serialize_btreemap, Error, Timer, Warn};
-use geom::{Angle, Bounds, Distance, GPSBounds, Line, PolyLine, Polygon, Pt2D, Speed};
+use geom::{Angle, Bounds, Distance, GPSBounds, Line, PolyLine, Polygon, Pt2D, Speed, Time};
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
 
@@ -49,6 +49,9 @@ pub struct Map {
     name: String,
     #[serde(skip_serializing, skip_deserializing)]
     edits: MapEdits,
+    // Only comes from edits
+    #[serde(skip_serializing, skip_deserializing)]
+    pricing: Pricing,
 }
 
 impl Map {
@@ -140,6 +143,7 @@ impl Map {
             city_name: "blank city".to_string(),
             name: "blank".to_string(),
             edits: MapEdits::new(),
+            pricing: Pricing::default(),
         }
     }
 
@@ -669,6 +673,15 @@ impl Map {
         self.pathfinder.as_ref().unwrap().pathfind(req, self)
     }
 
+    // Like pathfind, but tolls and cordons cost what they charge at this time
+    pub fn pathfind_at(&self, req: PathRequest, time: Time) -> Option<Path> {
+        assert!(!self.pathfinder_dirty);
+        self.pathfinder
+            .as_ref()
+            .unwrap()
+            .pathfind_at(req, time, self)
+    }
+
     pub fn should_use_transit(
         &self,
         start: Position,
@@ -778,6 +791,10 @@ impl Map {
         &self.edits
     }
 
+    pub fn get_pricing(&self) -> &Pricing {
+        &self.pricing
+    }
+
     // Panics on borders
     pub fn get_i_edit(&self, i: IntersectionID) -> EditIntersection {
         match self.get_i(i).intersection_type {
@@ -922,6 +939,7 @@ fn make_half_map(
         city_name: raw.city_name.clone(),
         name: raw.name.clone(),
         edits: MapEdits::new(),
+        pricing: Pricing::default(),
     };
 
     let road_id_mapping: BTreeMap<OriginalRoad, RoadID> = initial_map
@@ -1047,6 +1065,7 @@ fn make_half_map(
                 building_paths: Vec::new(),
                 bus_stops: Vec::new(),
                 parking_blackhole: None,
+                pocket_length: lane.pocket_length,
             });
         }
         if road.get_name() == "???" {
@@ -1276,6 +1295,20 @@ impl EditCmd {
                 }
                 true
             }
+            EditCmd::ChangeTurnPocket { id, new, .. } => {
+                let lane = &mut map.lanes[id.0];
+                if lane.pocket_length == *new {
+                    return false;
+                }
+                lane.pocket_length = *new;
+                effects.changed_roads.insert(lane.parent);
+                true
+            }
+            EditCmd::ChangeToll { id, new, .. } => map.pricing.set_toll(*id, new.clone()),
+            EditCmd::ChangeCordon { name, new, .. } => {
+                let cordon = new.as_ref().map(|c| (c.clone(), c.roads_inside(map)));
+                map.pricing.set_cordon(name, cordon)
+            }
         }
     }
 
@@ -1320,6 +1353,19 @@ impl EditCmd {
                 new: old.clone(),
             }
             .apply(effects, map, timer),
+            EditCmd::ChangeTurnPocket { id, new, old } => EditCmd::ChangeTurnPocket {
+                id: *id,
+                new: *old,
+                old: *new,
+            }
+            .apply(effects, map, timer),
+            EditCmd::ChangeToll { id, old, .. } => map.pricing.set_toll(*id, old.clone()),
+            EditCmd::ChangeCordon { name, new, old } => EditCmd::ChangeCordon {
+                name: name.clone(),
+                new: old.clone(),
+                old: new.clone(),
+            }
+            .apply(effects, map, timer),
         }
     }
 }
@@ -9,6 +9,7 @@ pub const PARKING_RIGHT: &str = "parking:lane:right";
 pub const PARKING_LEFT: &str = "parking:lane:left";
 pub const PARKING_BOTH: &str = "parking:lane:both";
 pub const SIDEWALK: &str = "sidewalk";
+pub const JUNCTION: &str = "junction";
 
 // The rest of these are all inserted by A/B Street to plumb data between different stages of map
 // construction. They could be plumbed another way, but this is the most convenient.
@@ -30,3 +31,19 @@ pub const SYNTHETIC_LANES: &str = "abst:synthetic_lanes";
 // Any roads might have these.
 pub const INFERRED_PARKING: &str = "abst:parking_inferred";
 pub const INFERRED_SIDEWALKS: &str = "abst:sidewalks_inferred";
+
+// Turn pockets and other lanes that only exist for some distance before the end of the road. The
+// value is a ';'-separated list of "idx:meters", where idx is the lane's offset from the center
+// line in that direction.
+pub const TURN_POCKETS_FWD: &str = "abst:turn_pockets_fwd";
+pub const TURN_POCKETS_BACK: &str = "abst:turn_pockets_back";
+
+// Stop and yield signs mapped as separate nodes, resolved to the end of the road they apply to. The
+// value is the original highway tag, "stop" or "give_way".
+pub const STOP_SIGN_FWD: &str = "abst:stop_sign_fwd";
+pub const STOP_SIGN_BACK: &str = "abst:stop_sign_back";
+
+// Crossings mapped along a road near one end. When a road has either of these, crosswalks only go
+// where they're mapped.
+pub const CROSSING_FWD: &str = "abst:crossing_fwd";
+pub const CROSSING_BACK: &str = "abst:crossing_back";
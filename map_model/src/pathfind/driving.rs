@@ -3,6 +3,7 @@ use crate::pathfind::uber_turns::{IntersectionCluster, UberTurn};
 use crate::{Lane, LaneID, Map, Path, PathConstraints, PathRequest, PathStep, Turn, TurnID};
 use abstutil::MultiMap;
 use fast_paths::{deserialize_32, serialize_32, FastGraph, InputGraph, PathCalculator};
+use geom::{Duration, Time};
 use serde::{Deserialize, Serialize};
 use std::cell::RefCell;
 use thread_local::ThreadLocal;
@@ -15,6 +16,9 @@ pub struct VehiclePathfinder {
     nodes: NodeMap<Node>,
     uber_turns: Vec<UberTurn>,
     constraints: PathConstraints,
+    // Price tolls and cordons as of this time, instead of the highest price all day
+    #[serde(skip_serializing, skip_deserializing)]
+    price_time: Option<Time>,
 
     #[serde(skip_serializing, skip_deserializing)]
     path_calc: ThreadLocal<RefCell<PathCalculator>>,
@@ -49,7 +53,7 @@ impl VehiclePathfinder {
             }
         }
 
-        let input_graph = make_input_graph(map, &nodes, &uber_turns, constraints);
+        let input_graph = make_input_graph(map, &nodes, &uber_turns, constraints, None);
 
         // All VehiclePathfinders have the same nodes (lanes), so if we're not the first being
         // built, seed from the node ordering.
@@ -65,6 +69,27 @@ impl VehiclePathfinder {
             nodes,
             uber_turns,
             constraints,
+            price_time: None,
+            path_calc: ThreadLocal::new(),
+        }
+    }
+
+    // A copy of this graph, but with tolls and cordons priced as of some time
+    pub fn priced_at(&self, map: &Map, time: Time) -> VehiclePathfinder {
+        let input_graph = make_input_graph(
+            map,
+            &self.nodes,
+            &self.uber_turns,
+            self.constraints,
+            Some(time),
+        );
+        let node_ordering = self.graph.get_node_ordering();
+        VehiclePathfinder {
+            graph: fast_paths::prepare_with_order(&input_graph, &node_ordering).unwrap(),
+            nodes: self.nodes.clone(),
+            uber_turns: self.uber_turns.clone(),
+            constraints: self.constraints,
+            price_time: Some(time),
             path_calc: ThreadLocal::new(),
         }
     }
@@ -118,7 +143,13 @@ impl VehiclePathfinder {
         // the node ordering.
         // TODO Make sure the result of this is deterministic and equivalent to computing from
         // scratch.
-        let input_graph = make_input_graph(map, &self.nodes, &self.uber_turns, self.constraints);
+        let input_graph = make_input_graph(
+            map,
+            &self.nodes,
+            &self.uber_turns,
+            self.constraints,
+            self.price_time,
+        );
         let node_ordering = self.graph.get_node_ordering();
         self.graph = fast_paths::prepare_with_order(&input_graph, &node_ordering).unwrap();
     }
@@ -129,6 +160,7 @@ fn make_input_graph(
     nodes: &NodeMap<Node>,
     uber_turns: &Vec<UberTurn>,
     constraints: PathConstraints,
+    price_time: Option<Time>,
 ) -> InputGraph {
     let mut input_graph = InputGraph::new();
 
@@ -170,7 +202,7 @@ fn make_input_graph(
                         from,
                         nodes.get(Node::Lane(turn.id.dst)),
                         // Round up! 0 cost edges are ignored
-                        cost(l, turn, constraints, map).max(1),
+                        cost_at(l, turn, constraints, price_time, map).max(1),
                     );
                 }
             } else {
@@ -180,7 +212,13 @@ fn make_input_graph(
 
                     let mut sum_cost = 0;
                     for t in &ut.path {
-                        sum_cost += cost(map.get_l(t.src), map.get_t(*t), constraints, map);
+                        sum_cost += cost_at(
+                            map.get_l(t.src),
+                            map.get_t(*t),
+                            constraints,
+                            price_time,
+                            map,
+                        );
                     }
                     input_graph.add_edge(from, nodes.get(Node::UberTurn(*idx)), sum_cost.max(1));
                     input_graph.add_edge(
@@ -205,15 +243,52 @@ fn make_input_graph(
     input_graph
 }
 
+// Strongly avoid turns from lanes whose markings don't allow them, so paths change into the marked
+// lane before reaching the intersection.
+const UNMARKED_TURN_PENALTY: Duration = Duration::const_seconds(300.0);
+
 pub fn cost(lane: &Lane, turn: &Turn, constraints: PathConstraints, map: &Map) -> usize {
+    cost_at(lane, turn, constraints, None, map)
+}
+
+// Without a time, tolls and cordons are assumed to charge their highest price.
+fn cost_at(
+    lane: &Lane,
+    turn: &Turn,
+    constraints: PathConstraints,
+    price_time: Option<Time>,
+    map: &Map,
+) -> usize {
     // TODO Could cost turns differently.
+    let unmarked = if lane.turn_violates_markings(turn, map) {
+        UNMARKED_TURN_PENALTY
+    } else {
+        Duration::ZERO
+    };
 
     match constraints {
         PathConstraints::Car => {
             // Prefer slightly longer route on faster roads
             let t1 = lane.length() / map.get_r(lane.parent).speed_limit;
             let t2 = turn.geom.length() / map.get_parent(turn.id.dst).speed_limit;
-            (t1 + t2).inner_seconds().round() as usize
+            // Tolls and cordon charges count as time. Edge weights can't change over the day, so
+            // each period of pricing gets its own graph.
+            let price: f64 = map
+                .get_pricing()
+                .charges(lane.parent, map.get_l(turn.id.dst).parent)
+                .into_iter()
+                .map(|(_, schedule)| match price_time {
+                    Some(t) => schedule.price_at(t),
+                    None => schedule.max_price(),
+                })
+                .sum();
+            let value_of_time = map.get_edits().value_of_time;
+            let t3 = if price > 0.0 && value_of_time > 0.0 {
+                Duration::seconds(3600.0 * price / value_of_time)
+            } else {
+                Duration::ZERO
+            };
+            (t1 + t2 + t3 + unmarked).inner_seconds().round() as usize
         }
         PathConstraints::Bike => {
             // Speed limits don't matter, bikes are usually constrained by their own speed limit.
@@ -246,7 +321,7 @@ pub fn cost(lane: &Lane, turn: &Turn, constraints: PathConstraints, map: &Map) -
                 assert!(lane.is_driving());
                 1.1
             };
-            (lt_penalty * (t1 + t2)).inner_seconds().round() as usize
+            (lt_penalty * (t1 + t2) + unmarked).inner_seconds().round() as usize
         }
         PathConstraints::Pedestrian => unreachable!(),
     }
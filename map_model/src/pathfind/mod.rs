@@ -11,7 +11,7 @@ use crate::{
     osm, BusRouteID, BusStopID, Lane, LaneID, LaneType, Map, Position, Traversable, TurnID,
 };
 use abstutil::Timer;
-use geom::{Distance, PolyLine, EPSILON_DIST};
+use geom::{Distance, PolyLine, Time, EPSILON_DIST};
 use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 use std::fmt;
@@ -476,6 +476,10 @@ pub struct Pathfinder {
     walking_graph: SidewalkPathfinder,
     // TODO Option just during initialization! Ewww.
     walking_with_transit_graph: Option<SidewalkPathfinder>,
+    // When tolls or cordons change price over the day, a car graph for each period of pricing,
+    // with the time it starts. Only ever comes from edits, so it isn't saved.
+    #[serde(skip_serializing, skip_deserializing)]
+    priced_car_graphs: Vec<(Time, VehiclePathfinder)>,
 }
 
 impl Pathfinder {
@@ -504,6 +508,7 @@ impl Pathfinder {
             bus_graph,
             walking_graph,
             walking_with_transit_graph: None,
+            priced_car_graphs: Vec::new(),
         }
     }
 
@@ -520,6 +525,22 @@ impl Pathfinder {
         }
     }
 
+    // Drivers avoid tolls and cordons based on what they charge when the trip starts, instead of
+    // their highest price all day.
+    pub fn pathfind_at(&self, req: PathRequest, time: Time, map: &Map) -> Option<Path> {
+        if req.constraints == PathConstraints::Car {
+            if let Some((_, graph)) = self
+                .priced_car_graphs
+                .iter()
+                .rev()
+                .find(|(start, _)| *start <= time)
+            {
+                return graph.pathfind(&req, map).map(|(p, _)| p);
+            }
+        }
+        self.pathfind(req, map)
+    }
+
     pub fn should_use_transit(
         &self,
         map: &Map,
@@ -537,6 +558,18 @@ impl Pathfinder {
         self.car_graph.apply_edits(map);
         timer.stop("apply edits to car pathfinding");
 
+        timer.start("prepare car pathfinding for each period of pricing");
+        let periods = map.get_pricing().price_periods();
+        self.priced_car_graphs = if periods.len() > 1 {
+            periods
+                .into_iter()
+                .map(|t| (t, self.car_graph.priced_at(map, t)))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        timer.stop("prepare car pathfinding for each period of pricing");
+
         timer.start("apply edits to bike pathfinding");
         self.bike_graph.apply_edits(map);
         timer.stop("apply edits to bike pathfinding");
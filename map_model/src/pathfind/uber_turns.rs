@@ -40,6 +40,27 @@ impl IntersectionCluster {
         clusters
     }
 
+    // Each ring of roads tagged as a roundabout becomes one cluster. Vehicles entering the ring
+    // yield to anybody already circulating, so the entrances are uncontrolled otherwise.
+    pub fn find_roundabouts(map: &Map) -> Vec<IntersectionCluster> {
+        let mut graph: UnGraphMap<IntersectionID, ()> = UnGraphMap::new();
+        for r in map.all_roads() {
+            if r.is_roundabout() {
+                graph.add_edge(r.src_i, r.dst_i, ());
+            }
+        }
+        let mut clusters = Vec::new();
+        for intersections in petgraph::algo::kosaraju_scc(&graph) {
+            // A lone piece of a roundabout (maybe clipped by the map boundary) isn't a ring
+            if intersections.len() < 2 {
+                continue;
+            }
+            let members: BTreeSet<IntersectionID> = intersections.into_iter().collect();
+            clusters.push(IntersectionCluster::new(members, map).0);
+        }
+        clusters
+    }
+
     // (legal, illegal)
     pub fn new(
         members: BTreeSet<IntersectionID>,
@@ -113,32 +134,48 @@ impl IntersectionCluster {
     // Find all other traffic signals "close" to one
     // TODO I haven't found a case with interior nodes yet
     pub fn autodetect(from: IntersectionID, map: &Map) -> Option<BTreeSet<IntersectionID>> {
-        let threshold = Distance::meters(25.0);
+        let mut found = IntersectionCluster::flood_short_roads(from, Distance::meters(25.0), |i| {
+            map.get_i(i)
+                .roads
+                .iter()
+                .map(|r| {
+                    let r = map.get_r(*r);
+                    let other = if r.src_i == i { r.dst_i } else { r.src_i };
+                    (other, r.center_pts.length())
+                })
+                .collect()
+        });
+        retain_btreeset(&mut found, |i| map.get_i(*i).is_traffic_signal());
+        if found.len() > 1 {
+            Some(found)
+        } else {
+            None
+        }
+    }
 
+    // Find everything reachable from one intersection using only roads no longer than the
+    // threshold. Generic over the intersection ID, so the importer can use this before the Map
+    // exists. neighbors returns the other end of each road and the road's length.
+    pub fn flood_short_roads<I: Copy + Ord, F: Fn(I) -> Vec<(I, Distance)>>(
+        from: I,
+        threshold: Distance,
+        neighbors: F,
+    ) -> BTreeSet<I> {
         let mut found = BTreeSet::new();
         let mut queue = vec![from];
 
-        while !queue.is_empty() {
-            let i = map.get_i(queue.pop().unwrap());
-            if found.contains(&i.id) {
+        while let Some(i) = queue.pop() {
+            if found.contains(&i) {
                 continue;
             }
-            found.insert(i.id);
-            for r in &i.roads {
-                let r = map.get_r(*r);
-                if r.center_pts.length() > threshold {
-                    continue;
+            found.insert(i);
+            for (other, length) in neighbors(i) {
+                if length <= threshold {
+                    queue.push(other);
                 }
-                let other = if r.src_i == i.id { r.dst_i } else { r.src_i };
-                queue.push(other);
             }
         }
-        retain_btreeset(&mut found, |i| map.get_i(*i).is_traffic_signal());
-        if found.len() > 1 {
-            Some(found)
-        } else {
-            None
-        }
+        found
     }
 }
 
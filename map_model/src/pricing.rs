@@ -0,0 +1,236 @@
+use crate::{Map, Path, PathStep, RoadID};
+use geom::{LonLat, Polygon, Time};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+// In dollars per hour. Converts what drivers pay into time when pathfinding, unless edits say
+// otherwise.
+pub const DEFAULT_VALUE_OF_TIME: f64 = 20.0;
+
+// What drivers pay in dollars over the course of a day. Each price applies from its time until
+// the next one. Before the first, driving is free.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PriceSchedule {
+    pub prices: Vec<(Time, f64)>,
+}
+
+impl PriceSchedule {
+    pub fn flat(price: f64) -> PriceSchedule {
+        PriceSchedule {
+            prices: vec![(Time::START_OF_DAY, price)],
+        }
+    }
+
+    // Only charges between start and end, like during rush hour
+    pub fn during(start: Time, end: Time, price: f64) -> PriceSchedule {
+        PriceSchedule {
+            prices: vec![(start, price), (end, 0.0)],
+        }
+    }
+
+    pub fn price_at(&self, time: Time) -> f64 {
+        self.prices
+            .iter()
+            .take_while(|(t, _)| *t <= time)
+            .last()
+            .map(|(_, price)| *price)
+            .unwrap_or(0.0)
+    }
+
+    pub fn max_price(&self) -> f64 {
+        self.prices
+            .iter()
+            .map(|(_, price)| *price)
+            .fold(0.0, f64::max)
+    }
+
+    pub fn describe(&self) -> String {
+        if self.prices.len() == 1 && self.prices[0].0 == Time::START_OF_DAY {
+            return format!("${:.2}", self.prices[0].1);
+        }
+        self.prices
+            .iter()
+            .map(|(t, price)| {
+                if *price == 0.0 {
+                    format!("free from {}", t.ampm_tostring())
+                } else {
+                    format!("${:.2} from {}", price, t.ampm_tostring())
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+// Drivers pay every time they enter this area from outside of it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Cordon {
+    pub name: String,
+    pub boundary: Vec<LonLat>,
+    pub schedule: PriceSchedule,
+}
+
+impl Cordon {
+    // Roads whose middle is inside the boundary
+    pub fn roads_inside(&self, map: &Map) -> BTreeSet<RoadID> {
+        let pts = match map.get_gps_bounds().try_convert(&self.boundary) {
+            Some(pts) if pts.len() >= 3 => pts,
+            _ => {
+                return BTreeSet::new();
+            }
+        };
+        let polygon = Polygon::new(&pts);
+        map.all_roads()
+            .iter()
+            .filter(|r| polygon.contains_pt(r.center_pts.middle()))
+            .map(|r| r.id)
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Charge {
+    Toll(RoadID),
+    Cordon(String),
+}
+
+impl Charge {
+    pub fn describe(&self, map: &Map) -> String {
+        match self {
+            Charge::Toll(r) => format!("toll on {}", map.get_r(*r).get_name()),
+            Charge::Cordon(name) => format!("entering {}", name),
+        }
+    }
+}
+
+// All tolls and cordons currently on the map. Only cars pay; buses and bikes are exempt.
+#[derive(Clone, Debug, Default)]
+pub struct Pricing {
+    tolls: BTreeMap<RoadID, PriceSchedule>,
+    cordons: BTreeMap<String, (Cordon, BTreeSet<RoadID>)>,
+}
+
+impl Pricing {
+    pub fn is_empty(&self) -> bool {
+        self.tolls.is_empty() && self.cordons.is_empty()
+    }
+
+    pub fn get_toll(&self, r: RoadID) -> Option<&PriceSchedule> {
+        self.tolls.get(&r)
+    }
+
+    pub fn all_tolls(&self) -> &BTreeMap<RoadID, PriceSchedule> {
+        &self.tolls
+    }
+
+    pub fn get_cordon(&self, name: &str) -> Option<&Cordon> {
+        self.cordons.get(name).map(|(c, _)| c)
+    }
+
+    pub fn all_cordons(&self) -> Vec<&Cordon> {
+        self.cordons.values().map(|(c, _)| c).collect()
+    }
+
+    pub fn in_any_cordon(&self, r: RoadID) -> bool {
+        self.cordons.values().any(|(_, roads)| roads.contains(&r))
+    }
+
+    // What a car pays for moving from one road onto another. Tolls are charged when entering the
+    // road, so U-turns and moving between lanes of the same road are free.
+    pub fn charges(&self, from: RoadID, to: RoadID) -> Vec<(Charge, &PriceSchedule)> {
+        let mut charges = Vec::new();
+        if from == to {
+            return charges;
+        }
+        if let Some(schedule) = self.tolls.get(&to) {
+            charges.push((Charge::Toll(to), schedule));
+        }
+        for (name, (cordon, roads)) in &self.cordons {
+            if roads.contains(&to) && !roads.contains(&from) {
+                charges.push((Charge::Cordon(name.clone()), &cordon.schedule));
+            }
+        }
+        charges
+    }
+
+    // Everything a car following this path would pay, if it started at some time. Doesn't
+    // account for time passing along the way.
+    pub fn path_cost(&self, path: &Path, time: Time, map: &Map) -> f64 {
+        let mut cost = 0.0;
+        for step in path.get_steps() {
+            if let PathStep::Turn(t) = step {
+                let from = map.get_l(t.src).parent;
+                let to = map.get_l(t.dst).parent;
+                for (_, schedule) in self.charges(from, to) {
+                    cost += schedule.price_at(time);
+                }
+            }
+        }
+        cost
+    }
+
+    // When the prices change over the day. Each time starts a period where every charge stays the
+    // same, and the first is always the start of the day.
+    pub fn price_periods(&self) -> Vec<Time> {
+        let schedules: Vec<&PriceSchedule> = self
+            .tolls
+            .values()
+            .chain(self.cordons.values().map(|(c, _)| &c.schedule))
+            .collect();
+        let mut times: Vec<Time> = schedules
+            .iter()
+            .flat_map(|s| s.prices.iter().map(|(t, _)| *t))
+            .filter(|t| *t > Time::START_OF_DAY)
+            .collect();
+        times.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        times.dedup();
+
+        let prices_at = |t: Time| -> Vec<f64> { schedules.iter().map(|s| s.price_at(t)).collect() };
+        let mut periods = vec![Time::START_OF_DAY];
+        let mut current = prices_at(Time::START_OF_DAY);
+        for t in times {
+            let prices = prices_at(t);
+            if prices != current {
+                periods.push(t);
+                current = prices;
+            }
+        }
+        periods
+    }
+
+    // True if anything changed
+    pub(crate) fn set_toll(&mut self, r: RoadID, schedule: Option<PriceSchedule>) -> bool {
+        if self.tolls.get(&r) == schedule.as_ref() {
+            return false;
+        }
+        match schedule {
+            Some(s) => {
+                self.tolls.insert(r, s);
+            }
+            None => {
+                self.tolls.remove(&r);
+            }
+        }
+        true
+    }
+
+    // The cordon has to be called name. True if anything changed.
+    pub(crate) fn set_cordon(
+        &mut self,
+        name: &str,
+        cordon: Option<(Cordon, BTreeSet<RoadID>)>,
+    ) -> bool {
+        if self.cordons.get(name).map(|(c, _)| c) == cordon.as_ref().map(|(c, _)| c) {
+            return false;
+        }
+        match cordon {
+            Some(pair) => {
+                self.cordons.insert(name.to_string(), pair);
+            }
+            None => {
+                self.cordons.remove(name);
+            }
+        }
+        true
+    }
+}
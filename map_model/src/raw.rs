@@ -36,6 +36,9 @@ pub struct RawMap {
     // If true, driving happens on the right side of the road (USA). If false, on the left
     // (Australia).
     pub driving_side: DrivingSide,
+    // When building the map, collapse clusters of intersections joined by short roads that have a
+    // traffic signal.
+    pub merge_short_roads: bool,
 }
 
 // A way to refer to roads across many maps and over time. Also trivial to relate with OSM to find
@@ -101,6 +104,7 @@ impl RawMap {
             boundary_polygon: Polygon::rectangle(1.0, 1.0),
             gps_bounds: GPSBounds::new(),
             driving_side: DrivingSide::Right,
+            merge_short_roads: false,
         }
     }
 
@@ -457,4 +457,9 @@ impl Road {
     pub fn is_private(&self) -> bool {
         self.osm_tags.get("access") == Some(&"private".to_string())
     }
+
+    // Part of the ring of a roundabout, not one of the roads entering or leaving it.
+    pub fn is_roundabout(&self) -> bool {
+        self.osm_tags.get(osm::JUNCTION) == Some(&"roundabout".to_string())
+    }
 }
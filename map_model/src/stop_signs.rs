@@ -1,4 +1,4 @@
-use crate::{IntersectionID, LaneID, Map, RoadID, TurnID, TurnPriority, TurnType};
+use crate::{osm, IntersectionID, LaneID, Map, RoadID, TurnID, TurnPriority, TurnType};
 use abstutil::{deserialize_btreemap, serialize_btreemap};
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, HashMap};
@@ -35,12 +35,34 @@ pub struct ControlStopSign {
         deserialize_with = "deserialize_btreemap"
     )]
     pub roads: BTreeMap<RoadID, RoadWithStopSign>,
+    // Entering roads have yield signs, and the roads of the ring (if any) have priority.
+    pub roundabout: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct RoadWithStopSign {
     pub rightmost_lane: LaneID,
-    pub must_stop: bool,
+    pub sign_type: SignType,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum SignType {
+    // Has priority over roads with signs
+    Uncontrolled,
+    // Go without stopping if the intersection is clear
+    Yield,
+    // Always pause first
+    Stop,
+}
+
+impl SignType {
+    pub fn from_osm(value: &str) -> Option<SignType> {
+        match value {
+            "stop" => Some(SignType::Stop),
+            "give_way" => Some(SignType::Yield),
+            _ => None,
+        }
+    }
 }
 
 impl ControlStopSign {
@@ -48,6 +70,7 @@ impl ControlStopSign {
         let mut ss = ControlStopSign {
             id,
             roads: BTreeMap::new(),
+            roundabout: false,
         };
         for r in &map.get_i(id).roads {
             let travel_lanes: Vec<LaneID> = map
@@ -67,17 +90,49 @@ impl ControlStopSign {
                     *r,
                     RoadWithStopSign {
                         rightmost_lane: *travel_lanes.last().unwrap(),
-                        must_stop: false,
+                        sign_type: SignType::Uncontrolled,
                     },
                 );
             }
         }
 
+        // Everything entering a roundabout yields to the ring. Most nodes on a ring only join the
+        // incoming ring road and one entering road, so check before the degenerate case.
+        if ss.roads.keys().any(|r| map.get_r(*r).is_roundabout()) {
+            ss.roundabout = true;
+            for (r, cfg) in ss.roads.iter_mut() {
+                if !map.get_r(*r).is_roundabout() {
+                    cfg.sign_type = SignType::Yield;
+                }
+            }
+            return ss;
+        }
+
         if ss.roads.len() <= 2 {
             // Degenerate roads and deadends don't need any stop signs.
             return ss;
         }
 
+        // Use the signs mapped in OSM, if there are any. Roads without one have priority.
+        let mut mapped = BTreeMap::new();
+        for r in ss.roads.keys() {
+            let road = map.get_r(*r);
+            let key = if road.dst_i == id {
+                osm::STOP_SIGN_FWD
+            } else {
+                osm::STOP_SIGN_BACK
+            };
+            if let Some(sign_type) = road.osm_tags.get(key).and_then(|x| SignType::from_osm(x)) {
+                mapped.insert(*r, sign_type);
+            }
+        }
+        if !mapped.is_empty() {
+            for (r, sign_type) in mapped {
+                ss.roads.get_mut(&r).unwrap().sign_type = sign_type;
+            }
+            return ss;
+        }
+
         // What's the rank of each road?
         let mut rank: HashMap<RoadID, usize> = HashMap::new();
         for r in ss.roads.keys() {
@@ -93,7 +148,7 @@ impl ControlStopSign {
         // highest-priority roads.
         for (r, cfg) in ss.roads.iter_mut() {
             if ranks.len() == 1 || rank[r] != ranks[0] {
-                cfg.must_stop = true;
+                cfg.sign_type = SignType::Stop;
             }
         }
         ss
@@ -106,17 +161,38 @@ impl ControlStopSign {
             // TODO This actually feels like a policy bit that should be flippable.
             TurnType::Crosswalk => TurnPriority::Protected,
             _ => {
-                if self.roads[&map.get_l(turn.src).parent].must_stop {
-                    TurnPriority::Yield
-                } else {
+                if self.get_sign_type(turn, map) == SignType::Uncontrolled {
                     TurnPriority::Protected
+                } else {
+                    TurnPriority::Yield
                 }
             }
         }
     }
 
-    pub fn flip_sign(&mut self, r: RoadID) {
+    // Only meaningful for turns between vehicle lanes
+    pub fn get_sign_type(&self, turn: TurnID, map: &Map) -> SignType {
+        self.roads[&map.get_l(turn.src).parent].sign_type
+    }
+
+    // Used to replace some other control with a mini-roundabout. Since there's no ring, every road
+    // yields to vehicles already in the intersection.
+    pub fn roundabout(map: &Map, id: IntersectionID) -> ControlStopSign {
+        let mut ss = ControlStopSign::new(map, id);
+        ss.roundabout = true;
+        for cfg in ss.roads.values_mut() {
+            cfg.sign_type = SignType::Yield;
+        }
+        ss
+    }
+
+    // Uncontrolled -> stop -> yield -> uncontrolled
+    pub fn cycle_sign(&mut self, r: RoadID) {
         let ss = self.roads.get_mut(&r).unwrap();
-        ss.must_stop = !ss.must_stop;
+        ss.sign_type = match ss.sign_type {
+            SignType::Uncontrolled => SignType::Stop,
+            SignType::Stop => SignType::Yield,
+            SignType::Yield => SignType::Uncontrolled,
+        };
     }
 }
@@ -0,0 +1,288 @@
+use crate::connectivity::find_scc;
+use crate::{IntersectionType, Map, PathConstraints};
+use abstutil::{prettyprint_usize, Counter, Timer};
+use geom::{Distance, LonLat, Pt2D};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashSet};
+
+// Buses are 12.5m long
+const SHORT_ROAD_THRESHOLD: Distance = Distance::const_meters(13.0);
+const TINY_LANE_THRESHOLD: Distance = Distance::const_meters(1.0);
+
+// Every problem found by running all of the checks on a built map. Problems are located in WGS84,
+// so the JSON report can be opened alongside the map in other tools.
+#[derive(Serialize, Deserialize)]
+pub struct ValidationReport {
+    pub map_name: String,
+    pub problems: Vec<Problem>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Problem {
+    pub check: Check,
+    // Like "Lane #123"
+    pub id: String,
+    pub location: LonLat,
+    pub details: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Check {
+    // A driving lane outside the largest connected component. Cars looking for parking get sent
+    // somewhere else.
+    ParkingBlackhole,
+    // A sidewalk or bike lane outside the largest connected component
+    DisconnectedLane,
+    OverlappingIntersections,
+    ShortRoad,
+    // The sidewalk in front of the building isn't connected to the rest of the map
+    UnreachableBuilding,
+    // A bus can't drive between this stop and the rest of its route
+    BusStopOffRoute,
+    InvalidTrafficSignal,
+    // Invalid points or a line that doubles back on itself
+    DegenerateLaneGeometry,
+    // Shorter than a meter. Real maps have a few of these, and vehicles can still cross them.
+    TinyLane,
+}
+
+impl Check {
+    // Errors will break the simulation; everything else just looks weird or is unrealistic.
+    pub fn is_error(self) -> bool {
+        match self {
+            Check::BusStopOffRoute
+            | Check::InvalidTrafficSignal
+            | Check::DegenerateLaneGeometry => true,
+            Check::ParkingBlackhole
+            | Check::DisconnectedLane
+            | Check::OverlappingIntersections
+            | Check::ShortRoad
+            | Check::UnreachableBuilding
+            | Check::TinyLane => false,
+        }
+    }
+}
+
+impl ValidationReport {
+    pub fn new(map: &Map, timer: &mut Timer) -> ValidationReport {
+        let mut report = ValidationReport {
+            map_name: map.get_name().to_string(),
+            problems: Vec::new(),
+        };
+        timer.start("validate map");
+        report.connectivity(map, timer);
+        report.overlapping_intersections(map, timer);
+        report.short_roads(map);
+        report.traffic_signals(map);
+        report.lane_geometry(map);
+        timer.stop("validate map");
+        report
+    }
+
+    pub fn num_errors(&self) -> usize {
+        self.problems.iter().filter(|p| p.check.is_error()).count()
+    }
+
+    // How many problems of each type
+    pub fn summary(&self) -> Vec<String> {
+        let mut cnt = Counter::new();
+        for p in &self.problems {
+            cnt.inc(p.check);
+        }
+        cnt.consume()
+            .into_iter()
+            .map(|(check, n)| {
+                format!(
+                    "{} {:?}{}",
+                    prettyprint_usize(n),
+                    check,
+                    if check.is_error() { " (error)" } else { "" }
+                )
+            })
+            .collect()
+    }
+
+    fn add(&mut self, map: &Map, check: Check, id: String, pt: Pt2D, details: String) {
+        self.problems.push(Problem {
+            check,
+            id,
+            location: pt.forcibly_to_gps(map.get_gps_bounds()),
+            details,
+        });
+    }
+
+    fn connectivity(&mut self, map: &Map, timer: &mut Timer) {
+        timer.start("check connectivity");
+        let (_, disconnected) = find_scc(map, PathConstraints::Car);
+        for l in sorted(disconnected) {
+            let lane = map.get_l(l);
+            let details = if let Some(redirect) = lane.parking_blackhole {
+                format!("parking redirects to {}", redirect)
+            } else {
+                "no nearby lane to redirect parking to".to_string()
+            };
+            self.add(
+                map,
+                Check::ParkingBlackhole,
+                l.to_string(),
+                lane.lane_center_pts.middle(),
+                details,
+            );
+        }
+
+        for constraints in &[PathConstraints::Pedestrian, PathConstraints::Bike] {
+            let (_, disconnected) = find_scc(map, *constraints);
+            for l in sorted(disconnected) {
+                // Only count bike lanes here; other lanes bikes can use are covered above
+                if *constraints == PathConstraints::Bike && !map.get_l(l).is_biking() {
+                    continue;
+                }
+                self.add(
+                    map,
+                    Check::DisconnectedLane,
+                    l.to_string(),
+                    map.get_l(l).lane_center_pts.middle(),
+                    format!("not connected to most of the map for {:?}", constraints),
+                );
+            }
+        }
+
+        let (connected_sidewalks, _) = find_scc(map, PathConstraints::Pedestrian);
+        for b in map.all_buildings() {
+            if !connected_sidewalks.contains(&b.sidewalk()) {
+                self.add(
+                    map,
+                    Check::UnreachableBuilding,
+                    b.id.to_string(),
+                    b.polygon.center(),
+                    format!("its sidewalk {} is disconnected", b.sidewalk()),
+                );
+            }
+        }
+
+        let (connected_bus_lanes, _) = find_scc(map, PathConstraints::Bus);
+        for route in map.get_all_bus_routes() {
+            for bs in &route.stops {
+                let stop = map.get_bs(*bs);
+                if !connected_bus_lanes.contains(&stop.driving_pos.lane()) {
+                    self.add(
+                        map,
+                        Check::BusStopOffRoute,
+                        bs.to_string(),
+                        stop.sidewalk_pos.pt(map),
+                        format!(
+                            "route {} can't reach {}",
+                            route.name,
+                            stop.driving_pos.lane()
+                        ),
+                    );
+                }
+            }
+        }
+        timer.stop("check connectivity");
+    }
+
+    fn overlapping_intersections(&mut self, map: &Map, timer: &mut Timer) {
+        // Sweep from west to east, only comparing intersections whose bounding boxes overlap
+        let mut intersections: Vec<_> = map
+            .all_intersections()
+            .iter()
+            .map(|i| (i, i.polygon.get_bounds()))
+            .collect();
+        intersections.sort_by(|a, b| a.1.min_x.partial_cmp(&b.1.min_x).unwrap());
+
+        timer.start_iter("check overlapping intersections", intersections.len());
+        for (idx, (i1, b1)) in intersections.iter().enumerate() {
+            timer.next();
+            for (i2, b2) in &intersections[idx + 1..] {
+                if b2.min_x > b1.max_x {
+                    break;
+                }
+                if b2.min_y > b1.max_y || b2.max_y < b1.min_y {
+                    continue;
+                }
+                if let Some(hit) = i1.polygon.intersection(&i2.polygon).into_iter().next() {
+                    self.add(
+                        map,
+                        Check::OverlappingIntersections,
+                        i1.id.to_string(),
+                        hit.center(),
+                        format!("overlaps {}", i2.id),
+                    );
+                }
+            }
+        }
+    }
+
+    fn short_roads(&mut self, map: &Map) {
+        let mut roads = BTreeSet::new();
+        for l in map.all_lanes() {
+            if l.length() < SHORT_ROAD_THRESHOLD {
+                roads.insert(l.parent);
+            }
+        }
+        for r in roads {
+            let road = map.get_r(r);
+            self.add(
+                map,
+                Check::ShortRoad,
+                r.to_string(),
+                road.center_pts.middle(),
+                format!("{} is only {} long", road.orig_id, road.center_pts.length()),
+            );
+        }
+    }
+
+    fn traffic_signals(&mut self, map: &Map) {
+        for i in map.all_intersections() {
+            if i.intersection_type != IntersectionType::TrafficSignal {
+                continue;
+            }
+            if let Err(err) = map.get_traffic_signal(i.id).clone().validate() {
+                self.add(
+                    map,
+                    Check::InvalidTrafficSignal,
+                    i.id.to_string(),
+                    i.polygon.center(),
+                    err,
+                );
+            }
+        }
+    }
+
+    fn lane_geometry(&mut self, map: &Map) {
+        for l in map.all_lanes() {
+            let pts = l.lane_center_pts.points();
+            let (check, problem) = if pts
+                .iter()
+                .any(|pt| !pt.x().is_finite() || !pt.y().is_finite())
+            {
+                (
+                    Check::DegenerateLaneGeometry,
+                    "has invalid points".to_string(),
+                )
+            } else if l.length() < TINY_LANE_THRESHOLD {
+                (Check::TinyLane, format!("is only {} long", l.length()))
+            } else if l
+                .lane_center_pts
+                .lines()
+                .windows(2)
+                .any(|pair| pair[0].angle().approx_eq(pair[1].angle().opposite(), 1.0))
+            {
+                (
+                    Check::DegenerateLaneGeometry,
+                    "doubles back on itself".to_string(),
+                )
+            } else {
+                continue;
+            };
+            self.add(map, check, l.id.to_string(), pts[0], problem);
+        }
+    }
+}
+
+fn sorted<T: Ord>(set: HashSet<T>) -> Vec<T> {
+    let mut list: Vec<T> = set.into_iter().collect();
+    list.sort();
+    list
+}
@@ -1,9 +1,11 @@
-use crate::{AlertLocation, CarID, Event, ParkingSpot, TripID, TripMode, TripPhaseType};
-use abstutil::Counter;
+use crate::{
+    AgentID, AlertLocation, CarID, Event, ParkingSpot, TripID, TripMode, TripPhaseType, VehicleType,
+};
+use abstutil::{prettyprint_usize, Counter};
 use geom::{Distance, Duration, Histogram, Time};
 use map_model::{
-    BusRouteID, BusStopID, IntersectionID, LaneID, Map, ParkingLotID, Path, PathRequest, RoadID,
-    Traversable, TurnGroupID,
+    BusRouteID, BusStopID, Charge, IntersectionID, LaneID, Map, ParkingLotID, Path, PathRequest,
+    RoadID, Traversable, TurnGroupID,
 };
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, VecDeque};
@@ -27,6 +29,10 @@ pub struct Analytics {
     // Per parking lane or lot, when does a spot become filled (true) or free (false)
     pub parking_lane_changes: BTreeMap<LaneID, Vec<(Time, bool)>>,
     pub parking_lot_changes: BTreeMap<ParkingLotID, Vec<(Time, bool)>>,
+    // Every toll or cordon charge paid, in dollars. Only kept for the live run, so prebaked results
+    // saved before pricing existed still load. ResultSets save a Revenue summary instead.
+    #[serde(skip_serializing, skip_deserializing)]
+    pub charges: Vec<(Time, CarID, Charge, f64)>,
     pub(crate) alerts: Vec<(Time, AlertLocation, String)>,
 
     // After we restore from a savestate, don't record anything. This is only going to make sense
@@ -49,6 +55,7 @@ impl Analytics {
             intersection_delays: BTreeMap::new(),
             parking_lane_changes: BTreeMap::new(),
             parking_lot_changes: BTreeMap::new(),
+            charges: Vec::new(),
             alerts: Vec::new(),
             record_anything: true,
         }
@@ -72,6 +79,19 @@ impl Analytics {
                     if let Some(id) = map.get_turn_group(t) {
                         *self.demand.entry(id).or_insert(0) -= 1;
                     }
+
+                    if let AgentID::Car(car) = a {
+                        if car.1 == VehicleType::Car {
+                            let from = map.get_l(t.src).parent;
+                            let to = map.get_l(t.dst).parent;
+                            for (charge, schedule) in map.get_pricing().charges(from, to) {
+                                let price = schedule.price_at(time);
+                                if price > 0.0 {
+                                    self.charges.push((time, car, charge, price));
+                                }
+                            }
+                        }
+                    }
                 }
             };
         }
@@ -259,6 +279,85 @@ impl Analytics {
         results
     }
 
+    // Revenue from tolls and cordon charges so far. If there are cordons, also how much driving
+    // happened inside and outside of them, compared to a baseline run if there is one.
+    pub fn pricing_report(&self, now: Time, before: Option<&Analytics>, map: &Map) -> Vec<String> {
+        let mut total = 0.0;
+        let mut per_charge: BTreeMap<&Charge, (f64, usize)> = BTreeMap::new();
+        for (t, _, charge, price) in &self.charges {
+            if *t > now {
+                break;
+            }
+            total += price;
+            let entry = per_charge.entry(charge).or_insert((0.0, 0));
+            entry.0 += price;
+            entry.1 += 1;
+        }
+
+        let mut lines = vec![format!(
+            "${:.2} collected from {} charges",
+            total,
+            prettyprint_usize(per_charge.values().map(|(_, cnt)| cnt).sum())
+        )];
+        for (charge, (amount, cnt)) in per_charge {
+            lines.push(format!(
+                "${:.2} from {} cars {}",
+                amount,
+                prettyprint_usize(cnt),
+                charge.describe(map)
+            ));
+        }
+
+        if !map.get_pricing().all_cordons().is_empty() {
+            lines.push(format!(
+                "Only counting the {} complete hours so far:",
+                now.get_hours()
+            ));
+            let (inside, outside) = self.driving_by_cordon(now, map);
+            if let Some(before) = before {
+                let (inside_before, outside_before) = before.driving_by_cordon(now, map);
+                lines.push(format!(
+                    "Cars entered roads inside cordons {} times, compared to {} before",
+                    prettyprint_usize(inside),
+                    prettyprint_usize(inside_before)
+                ));
+                lines.push(format!(
+                    "Cars entered roads outside cordons {} times, compared to {} before",
+                    prettyprint_usize(outside),
+                    prettyprint_usize(outside_before)
+                ));
+            } else {
+                lines.push(format!(
+                    "Cars entered roads inside cordons {} times",
+                    prettyprint_usize(inside)
+                ));
+                lines.push(format!(
+                    "Cars entered roads outside cordons {} times",
+                    prettyprint_usize(outside)
+                ));
+            }
+        }
+        lines
+    }
+
+    // Road throughput by car, split by whether the road is inside the current cordons. Only
+    // counts complete hours, since the throughput is bucketed by hour.
+    fn driving_by_cordon(&self, now: Time, map: &Map) -> (usize, usize) {
+        let mut inside = 0;
+        let mut outside = 0;
+        for ((r, mode, hour), cnt) in &self.road_thruput.counts {
+            if *mode != TripMode::Drive || *hour >= now.get_hours() {
+                continue;
+            }
+            if map.get_pricing().in_any_cordon(*r) {
+                inside += cnt;
+            } else {
+                outside += cnt;
+            }
+        }
+        (inside, outside)
+    }
+
     pub fn bus_arrivals(
         &self,
         now: Time,
@@ -523,6 +622,54 @@ impl Analytics {
     }
 }
 
+// The parts of Analytics that can't be recovered just by dropping events after some time.
+#[derive(Clone)]
+pub(crate) struct AnalyticsCheckpoint {
+    time: Time,
+    road_thruput: TimeSeriesCount<RoadID>,
+    intersection_thruput: TimeSeriesCount<IntersectionID>,
+    demand: BTreeMap<TurnGroupID, usize>,
+}
+
+impl Analytics {
+    pub(crate) fn checkpoint(&self, time: Time) -> AnalyticsCheckpoint {
+        AnalyticsCheckpoint {
+            time,
+            road_thruput: self.road_thruput.clone(),
+            intersection_thruput: self.intersection_thruput.clone(),
+            demand: self.demand.clone(),
+        }
+    }
+
+    // Forget everything that happened after the checkpoint, so recording can continue as if the
+    // sim had never gone past it.
+    pub(crate) fn rewind(&mut self, checkpoint: &AnalyticsCheckpoint) {
+        let time = checkpoint.time;
+        self.road_thruput = checkpoint.road_thruput.clone();
+        self.intersection_thruput = checkpoint.intersection_thruput.clone();
+        self.demand = checkpoint.demand.clone();
+        self.bus_arrivals.retain(|(t, _, _, _)| *t <= time);
+        self.bus_passengers_waiting.retain(|(t, _, _)| *t <= time);
+        self.started_trips = std::mem::replace(&mut self.started_trips, BTreeMap::new())
+            .into_iter()
+            .filter(|(_, t)| *t <= time)
+            .collect();
+        self.finished_trips.retain(|(t, _, _, _)| *t <= time);
+        self.trip_log.retain(|(t, _, _, _)| *t <= time);
+        for list in self.intersection_delays.values_mut() {
+            list.retain(|(t, _, _)| *t <= time);
+        }
+        for list in self.parking_lane_changes.values_mut() {
+            list.retain(|(t, _)| *t <= time);
+        }
+        for list in self.parking_lot_changes.values_mut() {
+            list.retain(|(t, _)| *t <= time);
+        }
+        self.charges.retain(|(t, _, _, _)| *t <= time);
+        self.alerts.retain(|(t, _, _)| *t <= time);
+    }
+}
+
 impl Default for Analytics {
     fn default() -> Analytics {
         let mut a = Analytics::new();
@@ -0,0 +1,246 @@
+use crate::analytics::TimeSeriesCount;
+use crate::{Analytics, Sim, TripID, TripMode};
+use abstutil::{Counter, Timer};
+use geom::{Duration, Time};
+use map_model::{Charge, IntersectionID, LaneID, Map, ParkingLotID, RoadID};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+// The Analytics from some run, saved under a name so it can be compared against any other run on
+// the same map later.
+#[derive(Serialize, Deserialize)]
+pub struct ResultSet {
+    pub name: String,
+    pub map_name: String,
+    pub edits_name: String,
+    // How far the run got before being saved
+    pub time: Time,
+    pub analytics: Analytics,
+    // Analytics doesn't save individual toll and cordon charges, so keep a summary
+    pub revenue: Revenue,
+}
+
+// What tolls and cordons collected over a run, in dollars
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Revenue {
+    pub per_charge: BTreeMap<Charge, f64>,
+    // Indexed by hour since midnight
+    pub per_hour: Vec<f64>,
+}
+
+impl Revenue {
+    pub fn new(analytics: &Analytics, time: Time) -> Revenue {
+        let mut revenue = Revenue::default();
+        for (t, _, charge, price) in &analytics.charges {
+            if *t > time {
+                break;
+            }
+            *revenue.per_charge.entry(charge.clone()).or_insert(0.0) += price;
+            let hour = t.get_parts().0;
+            if revenue.per_hour.len() <= hour {
+                revenue.per_hour.resize(hour + 1, 0.0);
+            }
+            revenue.per_hour[hour] += price;
+        }
+        revenue
+    }
+
+    // Revenue is only stored per hour, so this includes all of the current hour
+    pub fn total_until(&self, time: Time) -> f64 {
+        self.per_hour.iter().take(time.get_parts().0 + 1).sum()
+    }
+}
+
+impl ResultSet {
+    pub fn new(name: String, map: &Map, sim: &Sim) -> ResultSet {
+        ResultSet {
+            name,
+            map_name: map.get_name().to_string(),
+            edits_name: map.get_edits().edits_name.clone(),
+            time: sim.time(),
+            analytics: sim.get_analytics().clone(),
+            revenue: Revenue::new(sim.get_analytics(), sim.time()),
+        }
+    }
+
+    pub fn save(&self) -> String {
+        let path = abstutil::path_result_set(&self.map_name, &self.name);
+        abstutil::write_binary(path.clone(), self);
+        path
+    }
+
+    pub fn load(map_name: &str, name: &str, timer: &mut Timer) -> Result<ResultSet, String> {
+        abstutil::maybe_read_binary(abstutil::path_result_set(map_name, name), timer)
+            .map_err(|err| err.to_string())
+    }
+
+    pub fn list_all(map_name: &str) -> Vec<String> {
+        abstutil::list_all_objects(abstutil::path_all_result_sets(map_name))
+    }
+}
+
+// Differences between two runs, only counting what happened before both runs reached the same
+// time. Everything is (baseline, experiment).
+pub struct RunComparison {
+    pub baseline: String,
+    pub experiment: String,
+    pub time: Time,
+    // Only roads and intersections with some change are included
+    pub road_thruput: Vec<(RoadID, usize, usize)>,
+    pub intersection_delay: Vec<(IntersectionID, Duration, Duration)>,
+    // Counts trips by how they finished in each run. None means the trip was aborted or didn't
+    // finish in time.
+    pub mode_shift: BTreeMap<(Option<TripMode>, Option<TripMode>), usize>,
+    // The net change in occupied spots since midnight
+    pub parking_lanes: Vec<(LaneID, isize, isize)>,
+    pub parking_lots: Vec<(ParkingLotID, isize, isize)>,
+    // Total toll and cordon revenue
+    pub revenue: (f64, f64),
+}
+
+impl RunComparison {
+    pub fn new(baseline: &ResultSet, experiment: &ResultSet) -> RunComparison {
+        let time = if baseline.time < experiment.time {
+            baseline.time
+        } else {
+            experiment.time
+        };
+        let (before, after) = (&baseline.analytics, &experiment.analytics);
+
+        let mut road_thruput = Vec::new();
+        for (r, cnt1, cnt2) in total_counts(&before.road_thruput, time)
+            .compare(total_counts(&after.road_thruput, time))
+        {
+            if cnt1 != cnt2 {
+                road_thruput.push((r, cnt1, cnt2));
+            }
+        }
+
+        let mut intersection_delay = Vec::new();
+        let delay1 = total_delays(before, time);
+        let mut delay2 = total_delays(after, time);
+        for (i, dt1) in delay1 {
+            let dt2 = delay2.remove(&i).unwrap_or(Duration::ZERO);
+            if dt1 != dt2 {
+                intersection_delay.push((i, dt1, dt2));
+            }
+        }
+        for (i, dt2) in delay2 {
+            intersection_delay.push((i, Duration::ZERO, dt2));
+        }
+
+        let mut mode_shift = BTreeMap::new();
+        let modes1 = finished_modes(before, time);
+        let mut modes2 = finished_modes(after, time);
+        for (trip, mode1) in modes1 {
+            let mode2 = modes2.remove(&trip).unwrap_or(None);
+            *mode_shift.entry((mode1, mode2)).or_insert(0) += 1;
+        }
+        for (_, mode2) in modes2 {
+            *mode_shift.entry((None, mode2)).or_insert(0) += 1;
+        }
+
+        RunComparison {
+            baseline: baseline.name.clone(),
+            experiment: experiment.name.clone(),
+            time,
+            road_thruput,
+            intersection_delay,
+            mode_shift,
+            parking_lanes: compare_parking(
+                &before.parking_lane_changes,
+                &after.parking_lane_changes,
+                time,
+            ),
+            parking_lots: compare_parking(
+                &before.parking_lot_changes,
+                &after.parking_lot_changes,
+                time,
+            ),
+            revenue: (
+                baseline.revenue.total_until(time),
+                experiment.revenue.total_until(time),
+            ),
+        }
+    }
+
+    // Trips that finished in both runs, but using a different mode
+    pub fn num_mode_changes(&self) -> usize {
+        self.mode_shift
+            .iter()
+            .filter(|((m1, m2), _)| m1.is_some() && m2.is_some() && m1 != m2)
+            .map(|(_, cnt)| *cnt)
+            .sum()
+    }
+}
+
+// Throughput is only stored per hour, so this includes all of the current hour
+fn total_counts<X: Ord + Clone>(thruput: &TimeSeriesCount<X>, time: Time) -> Counter<X> {
+    let hour = time.get_parts().0;
+    let mut cnt = Counter::new();
+    for ((id, _, hr), value) in &thruput.counts {
+        if *hr <= hour {
+            cnt.add(id.clone(), *value);
+        }
+    }
+    cnt
+}
+
+fn total_delays(analytics: &Analytics, time: Time) -> BTreeMap<IntersectionID, Duration> {
+    let mut delays = BTreeMap::new();
+    for (i, list) in &analytics.intersection_delays {
+        let mut sum = Duration::ZERO;
+        for (t, dt, _) in list {
+            if *t > time {
+                break;
+            }
+            sum += *dt;
+        }
+        delays.insert(*i, sum);
+    }
+    delays
+}
+
+fn finished_modes(analytics: &Analytics, time: Time) -> BTreeMap<TripID, Option<TripMode>> {
+    let mut modes = BTreeMap::new();
+    // Trips that started but didn't finish still count, so the shift includes them
+    for (trip, t) in &analytics.started_trips {
+        if *t <= time {
+            modes.insert(*trip, None);
+        }
+    }
+    for (t, trip, mode, _) in &analytics.finished_trips {
+        if *t > time {
+            break;
+        }
+        modes.insert(*trip, *mode);
+    }
+    modes
+}
+
+fn compare_parking<X: Ord + Copy>(
+    before: &BTreeMap<X, Vec<(Time, bool)>>,
+    after: &BTreeMap<X, Vec<(Time, bool)>>,
+    time: Time,
+) -> Vec<(X, isize, isize)> {
+    let net_change = |changes: Option<&Vec<(Time, bool)>>| -> isize {
+        let mut net = 0;
+        for (t, filled) in changes.into_iter().flatten() {
+            if *t > time {
+                break;
+            }
+            net += if *filled { 1 } else { -1 };
+        }
+        net
+    };
+
+    let mut results = Vec::new();
+    let ids: BTreeSet<&X> = before.keys().chain(after.keys()).collect();
+    for id in ids {
+        let (n1, n2) = (net_change(before.get(id)), net_change(after.get(id)));
+        if n1 != n2 {
+            results.push((*id, n1, n2));
+        }
+    }
+    results
+}
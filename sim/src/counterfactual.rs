@@ -0,0 +1,318 @@
+use crate::mechanics::{TIME_TO_FINISH_BIKING, TIME_TO_START_BIKING};
+use crate::{
+    Analytics, DrivingGoal, Person, SidewalkPOI, SidewalkSpot, Sim, TripEndpoint, TripID, TripMode,
+    VehicleType,
+};
+use geom::{Distance, Duration, Speed, Time};
+use map_model::{
+    BuildingID, IntersectionID, Map, Path, PathConstraints, PathRequest, PathStep, Position,
+};
+
+// Only use delays recorded this close to when the agent would've reached an intersection
+const DELAY_WINDOW: Duration = Duration::const_seconds(30.0 * 60.0);
+// For people without a bike. Roughly the middle of what scenarios use.
+const DEFAULT_BIKE_SPEED: Speed = Speed::const_meters_per_second(4.0);
+// An alternative is only "much faster" if it saves at least this fraction of the trip...
+const MUCH_FASTER_PERCENT: f64 = 0.25;
+// ... and at least this much time
+const MUCH_FASTER_MINIMUM: Duration = Duration::const_seconds(5.0 * 60.0);
+
+// How long a finished trip might've taken by other modes, in the traffic recorded that day.
+#[derive(Clone)]
+pub struct TripCounterfactuals {
+    pub trip: TripID,
+    pub departure: Time,
+    pub actual_mode: TripMode,
+    pub actual_duration: Duration,
+    // Includes the mode actually used, since the pathfinder might pick a different route than
+    // the one taken
+    pub alternatives: Vec<TripAlternative>,
+}
+
+#[derive(Clone)]
+pub struct TripAlternative {
+    pub mode: TripMode,
+    pub duration: Duration,
+    // A breakdown of the estimate and what it leaves out
+    pub notes: Vec<String>,
+    // Every leg that isn't spent on a bus
+    pub paths: Vec<Path>,
+}
+
+impl TripCounterfactuals {
+    // The fastest alternative using a different mode, if it would've saved a lot of time
+    pub fn much_faster(&self) -> Option<&TripAlternative> {
+        self.alternatives
+            .iter()
+            .filter(|alt| alt.mode != self.actual_mode && self.saves_a_lot(alt))
+            .min_by_key(|alt| alt.duration)
+    }
+
+    pub fn saves_a_lot(&self, alt: &TripAlternative) -> bool {
+        let saved = self.actual_duration - alt.duration;
+        saved >= MUCH_FASTER_MINIMUM && saved >= self.actual_duration * MUCH_FASTER_PERCENT
+    }
+}
+
+// Only handles trips between two buildings; anything crossing the map boundary could've
+// started or ended anywhere off-map.
+pub(crate) fn trip_counterfactuals(
+    sim: &Sim,
+    trip: TripID,
+    map: &Map,
+) -> Option<TripCounterfactuals> {
+    let (departure, start, end, actual_mode) = sim.trip_info(trip);
+    let (actual_duration, _) = sim.finished_trip_time(trip)?;
+    let (b1, b2) = match (start, end) {
+        (TripEndpoint::Bldg(b1), TripEndpoint::Bldg(b2)) => (b1, b2),
+        _ => {
+            return None;
+        }
+    };
+    let person = sim.get_person(sim.trip_to_person(trip));
+
+    let estimator = Estimator {
+        analytics: sim.get_analytics(),
+        map,
+        departure,
+    };
+    let alternatives = TripMode::all()
+        .into_iter()
+        .filter_map(|mode| match mode {
+            TripMode::Walk => estimator.walk(b1, b2, person),
+            TripMode::Bike => estimator.bike(b1, b2, person),
+            TripMode::Transit => estimator.transit(b1, b2, person),
+            TripMode::Drive => estimator.drive(b1, b2, person),
+        })
+        .collect();
+
+    Some(TripCounterfactuals {
+        trip,
+        departure,
+        actual_mode,
+        actual_duration,
+        alternatives,
+    })
+}
+
+struct Estimator<'a> {
+    analytics: &'a Analytics,
+    map: &'a Map,
+    departure: Time,
+}
+
+impl<'a> Estimator<'a> {
+    fn walk(&self, b1: BuildingID, b2: BuildingID, person: &Person) -> Option<TripAlternative> {
+        let path = self.map.pathfind(PathRequest {
+            start: self.map.get_b(b1).front_path.sidewalk,
+            end: self.map.get_b(b2).front_path.sidewalk,
+            constraints: PathConstraints::Pedestrian,
+        })?;
+        let (moving, delay) = self.follow(
+            &path,
+            TripMode::Walk,
+            Some(person.ped_speed),
+            self.departure,
+        );
+        Some(TripAlternative {
+            mode: TripMode::Walk,
+            duration: moving + delay,
+            notes: vec![format!(
+                "{} walking, {} waiting at intersections",
+                moving, delay
+            )],
+            paths: vec![path],
+        })
+    }
+
+    fn bike(&self, b1: BuildingID, b2: BuildingID, person: &Person) -> Option<TripAlternative> {
+        let sidewalk = self.map.get_b(b1).front_path.sidewalk.lane();
+        let start = match SidewalkSpot::bike_from_bike_rack(sidewalk, self.map)?.connection {
+            SidewalkPOI::BikeRack(pos) => pos,
+            _ => unreachable!(),
+        };
+        let path = self.map.pathfind(PathRequest {
+            start,
+            end: DrivingGoal::ParkNear(b2).goal_pos(PathConstraints::Bike, self.map),
+            constraints: PathConstraints::Bike,
+        })?;
+        let speed = person
+            .vehicles
+            .iter()
+            .find(|v| v.vehicle_type == VehicleType::Bike)
+            .and_then(|v| v.max_speed)
+            .unwrap_or(DEFAULT_BIKE_SPEED);
+        let (moving, delay) = self.follow(
+            &path,
+            TripMode::Bike,
+            Some(speed),
+            self.departure + TIME_TO_START_BIKING,
+        );
+        let setup = TIME_TO_START_BIKING + TIME_TO_FINISH_BIKING;
+
+        let mut notes = vec![
+            format!(
+                "{} biking, {} waiting at intersections, {} with the bike rack",
+                moving, delay, setup
+            ),
+            "Doesn't include the short walks to and from the bike racks".to_string(),
+        ];
+        if !person
+            .vehicles
+            .iter()
+            .any(|v| v.vehicle_type == VehicleType::Bike)
+        {
+            notes.push("This person doesn't own a bike".to_string());
+        }
+        Some(TripAlternative {
+            mode: TripMode::Bike,
+            duration: moving + delay + setup,
+            notes,
+            paths: vec![path],
+        })
+    }
+
+    fn transit(&self, b1: BuildingID, b2: BuildingID, person: &Person) -> Option<TripAlternative> {
+        let start = self.map.get_b(b1).front_path.sidewalk;
+        let end = self.map.get_b(b2).front_path.sidewalk;
+        let (stop1, stop2, route) = self.map.should_use_transit(start, end)?;
+
+        let walk1 = self.map.pathfind(PathRequest {
+            start,
+            end: self.map.get_bs(stop1).sidewalk_pos,
+            constraints: PathConstraints::Pedestrian,
+        })?;
+        let (moving1, delay1) = self.follow(
+            &walk1,
+            TripMode::Walk,
+            Some(person.ped_speed),
+            self.departure,
+        );
+        let reach_stop = self.departure + moving1 + delay1;
+
+        // Catch the first bus that actually showed up that day, and ride it until it reached the
+        // other stop
+        let (board, bus) = self
+            .analytics
+            .bus_arrivals
+            .iter()
+            .find(|(t, _, r, s)| *t >= reach_stop && *r == route && *s == stop1)
+            .map(|(t, car, _, _)| (*t, *car))?;
+        let alight = self
+            .analytics
+            .bus_arrivals
+            .iter()
+            .find(|(t, car, _, s)| *t > board && *car == bus && *s == stop2)
+            .map(|(t, _, _, _)| *t)?;
+
+        let walk2 = self.map.pathfind(PathRequest {
+            start: self.map.get_bs(stop2).sidewalk_pos,
+            end,
+            constraints: PathConstraints::Pedestrian,
+        })?;
+        let (moving2, delay2) = self.follow(&walk2, TripMode::Walk, Some(person.ped_speed), alight);
+
+        Some(TripAlternative {
+            mode: TripMode::Transit,
+            duration: (alight - self.departure) + moving2 + delay2,
+            notes: vec![
+                format!(
+                    "{} walking, {} waiting at intersections",
+                    moving1 + moving2,
+                    delay1 + delay2
+                ),
+                format!(
+                    "{} waiting for {}, {} riding it",
+                    board - reach_stop,
+                    self.map.get_br(route).name,
+                    alight - board
+                ),
+            ],
+            paths: vec![walk1, walk2],
+        })
+    }
+
+    fn drive(&self, b1: BuildingID, b2: BuildingID, person: &Person) -> Option<TripAlternative> {
+        let path = self.map.pathfind_at(
+            PathRequest {
+                start: Position::new(self.map.find_driving_lane_near_building(b1), Distance::ZERO),
+                end: DrivingGoal::ParkNear(b2).goal_pos(PathConstraints::Car, self.map),
+                constraints: PathConstraints::Car,
+            },
+            self.departure,
+        )?;
+        let car = person
+            .vehicles
+            .iter()
+            .find(|v| v.vehicle_type == VehicleType::Car);
+        let speed = car.and_then(|v| v.max_speed);
+        let (moving, delay) = self.follow(&path, TripMode::Drive, speed, self.departure);
+
+        let mut notes = vec![
+            format!("{} driving, {} waiting at intersections", moving, delay),
+            "Doesn't include walking to the car or searching for parking".to_string(),
+        ];
+        let charges = self
+            .map
+            .get_pricing()
+            .path_cost(&path, self.departure, self.map);
+        if charges > 0.0 {
+            notes.push(format!("Would pay ${:.2} in tolls", charges));
+        }
+        if car.is_none() {
+            notes.push("This person doesn't own a car".to_string());
+        }
+        Some(TripAlternative {
+            mode: TripMode::Drive,
+            duration: moving + delay,
+            notes,
+            paths: vec![path],
+        })
+    }
+
+    // Returns (time spent moving, time spent waiting at intersections). Every step is crossed
+    // at full speed; congestion only shows up as delay at intersections.
+    fn follow(
+        &self,
+        path: &Path,
+        mode: TripMode,
+        max_speed: Option<Speed>,
+        start_time: Time,
+    ) -> (Duration, Duration) {
+        let mut moving = Duration::ZERO;
+        let mut delay = Duration::ZERO;
+        for step in path.get_steps() {
+            let on = step.as_traversable();
+            // Pedestrians ignore speed limits
+            let speed = match max_speed {
+                Some(s) if mode == TripMode::Walk => s,
+                Some(s) => on.speed_limit(self.map).min(s),
+                None => on.speed_limit(self.map),
+            };
+            moving += on.length(self.map) / speed;
+            if let PathStep::Turn(t) = step {
+                delay += self.typical_delay(t.parent, mode, start_time + moving + delay);
+            }
+        }
+        (moving, delay)
+    }
+
+    // The average delay agents using some mode experienced at an intersection, around some time
+    fn typical_delay(&self, i: IntersectionID, mode: TripMode, time: Time) -> Duration {
+        let mut total = Duration::ZERO;
+        let mut count = 0;
+        if let Some(delays) = self.analytics.intersection_delays.get(&i) {
+            for (t, dt, m) in delays {
+                if *m == mode && (*t - time).inner_seconds().abs() <= DELAY_WINDOW.inner_seconds() {
+                    total += *dt;
+                    count += 1;
+                }
+            }
+        }
+        if count == 0 {
+            Duration::ZERO
+        } else {
+            total / (count as f64)
+        }
+    }
+}
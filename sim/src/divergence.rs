@@ -0,0 +1,150 @@
+use crate::{AgentID, Sim};
+use abstutil::Timer;
+use geom::{Duration, Time};
+use map_model::Map;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+// Where two runs that should be identical first stop matching.
+pub struct Divergence {
+    pub time: Time,
+    // Which part of the sim differs, like "driving" or "scheduler"
+    pub system: &'static str,
+    pub agent: Option<AgentID>,
+    // The differing lines of the state as JSON. Lines from the first run start with "-", from
+    // the second with "+".
+    pub diff: Vec<String>,
+}
+
+impl Divergence {
+    pub fn describe(&self) -> Vec<String> {
+        let mut lines = vec![match self.agent {
+            Some(a) => format!("At {}, {} ({}) diverged", self.time, a, self.system),
+            None => format!("At {}, {} diverged", self.time, self.system),
+        }];
+        lines.extend(self.diff.clone());
+        lines
+    }
+}
+
+// Steps both sims forwards together, comparing every dt until end_time. When they stop matching,
+// both are replayed from the last identical state in tiny steps to find exactly when.
+pub fn find_first_divergence(
+    sim1: &mut Sim,
+    sim2: &mut Sim,
+    map: &Map,
+    dt: Duration,
+    end_time: Time,
+    timer: &mut Timer,
+) -> Option<Divergence> {
+    if let Some(d) = sim1.find_divergence(sim2) {
+        return Some(d);
+    }
+
+    while sim1.time() < end_time && !(sim1.is_done() && sim2.is_done()) {
+        let step = if sim1.time() + dt < end_time {
+            dt
+        } else {
+            end_time - sim1.time()
+        };
+        timer.note(format!(
+            "Comparing runs from {} to {}",
+            sim1.time(),
+            sim1.time() + step
+        ));
+        let (before1, before2) = (sim1.clone(), sim2.clone());
+        sim1.timed_step(map, step, &mut None, &mut Timer::throwaway());
+        sim2.timed_step(map, step, &mut None, &mut Timer::throwaway());
+        let coarse = match sim1.find_divergence(sim2) {
+            Some(d) => d,
+            None => {
+                continue;
+            }
+        };
+
+        let target = sim1.time();
+        *sim1 = before1;
+        *sim2 = before2;
+        while sim1.time() < target {
+            sim1.tiny_step(map, &mut None);
+            sim2.tiny_step(map, &mut None);
+            if let Some(d) = sim1.find_divergence(sim2) {
+                return Some(d);
+            }
+        }
+        // Stepping differently somehow avoided the problem
+        return Some(coarse);
+    }
+    None
+}
+
+// Compares a run against the savestates written by an earlier run, probably of different code.
+// Only as precise as how often the savestates were written.
+pub fn compare_to_savestates(
+    sim: &mut Sim,
+    map: &Map,
+    savestates: Vec<String>,
+    timer: &mut Timer,
+) -> Result<Option<Divergence>, String> {
+    for path in savestates {
+        let expected = Sim::load_savestate(path.clone(), map, timer)
+            .map_err(|err| format!("Couldn't load {}: {}", path, err))?;
+        if expected.time() < sim.time() {
+            continue;
+        }
+        timer.note(format!("Comparing against {}", path));
+        sim.timed_step(
+            map,
+            expected.time() - sim.time(),
+            &mut None,
+            &mut Timer::throwaway(),
+        );
+        if let Some(d) = expected.find_divergence(sim) {
+            return Ok(Some(d));
+        }
+    }
+    Ok(None)
+}
+
+// The first entry that differs between two maps of state, diffed as JSON
+pub(crate) fn first_diff<K: Ord + Copy, V: PartialEq + Serialize>(
+    map1: &BTreeMap<K, V>,
+    map2: &BTreeMap<K, V>,
+) -> Option<(K, Vec<String>)> {
+    let keys: BTreeSet<&K> = map1.keys().chain(map2.keys()).collect();
+    for key in keys {
+        let (x1, x2) = (map1.get(key), map2.get(key));
+        if x1 != x2 {
+            return Some((*key, diff_json(&x1, &x2)));
+        }
+    }
+    None
+}
+
+// Not a minimal diff; just trims the lines in common at the start and end.
+pub(crate) fn diff_json<T: Serialize>(x1: &T, x2: &T) -> Vec<String> {
+    let (json1, json2) = (abstutil::to_json(x1), abstutil::to_json(x2));
+    let lines1: Vec<&str> = json1.lines().collect();
+    let lines2: Vec<&str> = json2.lines().collect();
+
+    let mut start = 0;
+    while start < lines1.len() && start < lines2.len() && lines1[start] == lines2[start] {
+        start += 1;
+    }
+    let mut end = 0;
+    while end < lines1.len() - start
+        && end < lines2.len() - start
+        && lines1[lines1.len() - 1 - end] == lines2[lines2.len() - 1 - end]
+    {
+        end += 1;
+    }
+
+    let mut diff = Vec::new();
+    for line in &lines1[start..lines1.len() - end] {
+        diff.push(format!("-{}", line));
+    }
+    for line in &lines2[start..lines2.len() - end] {
+        diff.push(format!("+{}", line));
+    }
+    diff
+}
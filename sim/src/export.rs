@@ -0,0 +1,285 @@
+use crate::{Analytics, Scenario, Sim, Trajectories, TripEndpoint, TripMode};
+use geom::{LonLat, Pt2D};
+use map_model::{Map, RoadID};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{Error, Write};
+
+// Writes simulation results and scenarios in formats that GIS and stats tools understand.
+
+// One row per trip, with its endpoints, how long it took, and a summary of each phase.
+pub fn export_trips_csv(sim: &Sim, map: &Map, path: &str) -> Result<(), Error> {
+    let phases_per_trip = sim.get_analytics().get_all_trip_phases();
+    let mut f = create(path)?;
+    writeln!(
+        f,
+        "trip,person,depart,origin_lon,origin_lat,destination_lon,destination_lat,mode,\
+         duration_seconds,waiting_seconds,phases"
+    )?;
+    for person in sim.get_all_people() {
+        for trip in &person.trips {
+            let (depart, from, to, mode) = sim.trip_info(*trip);
+            let from = endpoint_gps(&from, map);
+            let to = endpoint_gps(&to, map);
+            // Unfinished or aborted trips don't have a duration
+            let (duration, waiting) = match sim.finished_trip_time(*trip) {
+                Some((total, blocked)) => (
+                    total.inner_seconds().to_string(),
+                    blocked.inner_seconds().to_string(),
+                ),
+                None => (String::new(), String::new()),
+            };
+            let phases = phases_per_trip
+                .get(trip)
+                .map(|phases| {
+                    phases
+                        .iter()
+                        .map(|p| {
+                            format!(
+                                "{} from {} to {}",
+                                p.phase_type.describe(map),
+                                p.start_time,
+                                p.end_time
+                                    .map(|t| t.to_string())
+                                    .unwrap_or_else(|| "now".to_string())
+                            )
+                        })
+                        .collect::<Vec<_>>()
+                        .join("; ")
+                })
+                .unwrap_or_else(String::new);
+            writeln!(
+                f,
+                "{},{},{},{},{},{},{},{},{},{},\"{}\"",
+                trip.0,
+                person.id.0,
+                depart.inner_seconds(),
+                from.x(),
+                from.y(),
+                to.x(),
+                to.y(),
+                mode.verb(),
+                duration,
+                waiting,
+                phases.replace('"', "\"\"")
+            )?;
+        }
+    }
+    Ok(())
+}
+
+// A straight line from origin to destination for every trip
+pub fn export_trips_geojson(sim: &Sim, map: &Map, path: &str) -> Result<(), Error> {
+    let mut features = Vec::new();
+    for person in sim.get_all_people() {
+        for trip in &person.trips {
+            let (depart, from, to, mode) = sim.trip_info(*trip);
+            let duration = sim
+                .finished_trip_time(*trip)
+                .map(|(total, _)| total.inner_seconds());
+            features.push(feature(
+                vec![endpoint_gps(&from, map), endpoint_gps(&to, map)],
+                json!({
+                    "trip": trip.0,
+                    "person": person.id.0,
+                    "depart_seconds": depart.inner_seconds(),
+                    "mode": mode.verb(),
+                    "duration_seconds": duration,
+                }),
+            ));
+        }
+    }
+    write_features(path, features)
+}
+
+// Every road with its throughput, in total and per mode
+pub fn export_road_throughput_geojson(
+    analytics: &Analytics,
+    map: &Map,
+    path: &str,
+) -> Result<(), Error> {
+    // Sum over every hour, including later days when the scenario repeats
+    let mut per_mode: BTreeMap<(RoadID, TripMode), usize> = BTreeMap::new();
+    for ((r, mode, _), cnt) in &analytics.road_thruput.counts {
+        *per_mode.entry((*r, *mode)).or_insert(0) += *cnt;
+    }
+
+    let gps_bounds = map.get_gps_bounds();
+    let mut features = Vec::new();
+    for r in map.all_roads() {
+        let mut props = json!({
+            "road": r.id.0,
+            "name": r.get_name(),
+        });
+        let mut total = 0;
+        for mode in TripMode::all() {
+            let cnt = per_mode.get(&(r.id, mode)).cloned().unwrap_or(0);
+            props[mode.ongoing_verb()] = json!(cnt);
+            total += cnt;
+        }
+        props["total"] = json!(total);
+        features.push(feature(
+            gps_bounds.must_convert_back(r.center_pts.points()),
+            props,
+        ));
+    }
+    write_features(path, features)
+}
+
+// One row per moving agent per recorded frame, for animating outside of the game. Parked cars only
+// get a row in the frame where they park.
+pub fn export_trajectories_csv(
+    trajectories: &Trajectories,
+    map: &Map,
+    path: &str,
+) -> Result<(), Error> {
+    let mut f = create(path)?;
+    writeln!(f, "time_seconds,agent,person,lon,lat,parked")?;
+    for frame in &trajectories.frames {
+        let cars = frame
+            .cars
+            .iter()
+            .map(|c| (c.id.to_string(), c.person, c.front, false))
+            .chain(
+                frame
+                    .newly_parked
+                    .iter()
+                    .map(|p| (p.id.to_string(), None, p.body.last_pt(), true)),
+            );
+        let peds = frame
+            .peds
+            .iter()
+            .map(|p| (p.id.to_string(), p.person, p.pos, false));
+        for (agent, person, pt, parked) in cars.chain(peds) {
+            let gps = to_gps(pt, map);
+            writeln!(
+                f,
+                "{},{},{},{},{},{}",
+                frame.time.inner_seconds(),
+                agent,
+                person.map(|p| p.0.to_string()).unwrap_or_else(String::new),
+                gps.x(),
+                gps.y(),
+                parked
+            )?;
+        }
+    }
+    Ok(())
+}
+
+// A MATSim population file (http://www.matsim.org/files/dtd/population_v6.dtd). Every trip
+// endpoint becomes an activity; coordinates are WGS84 longitude and latitude.
+pub fn export_matsim_plans(scenario: &Scenario, map: &Map, path: &str) -> Result<(), Error> {
+    let mut f = create(path)?;
+    writeln!(f, "<?xml version=\"1.0\" encoding=\"utf-8\"?>")?;
+    writeln!(
+        f,
+        "<!DOCTYPE population SYSTEM \"http://www.matsim.org/files/dtd/population_v6.dtd\">"
+    )?;
+    writeln!(f, "<population>")?;
+    writeln!(f, "  <attributes>")?;
+    writeln!(
+        f,
+        "    <attribute name=\"coordinateReferenceSystem\" class=\"java.lang.String\">\
+         EPSG:4326</attribute>"
+    )?;
+    writeln!(f, "  </attributes>")?;
+    for person in &scenario.people {
+        if person.trips.is_empty() {
+            continue;
+        }
+        writeln!(f, "  <person id=\"{}\">", person.id.0)?;
+        writeln!(f, "    <plan selected=\"yes\">")?;
+        for (idx, trip) in person.trips.iter().enumerate() {
+            let from = endpoint_gps(&trip.trip.start(map), map);
+            writeln!(
+                f,
+                "      <activity type=\"{}\" x=\"{}\" y=\"{}\" end_time=\"{}\" />",
+                if idx == 0 { "home" } else { "other" },
+                from.x(),
+                from.y(),
+                matsim_time(trip.depart.inner_seconds())
+            )?;
+            writeln!(
+                f,
+                "      <leg mode=\"{}\" />",
+                match trip.trip.mode() {
+                    TripMode::Walk => "walk",
+                    TripMode::Bike => "bike",
+                    TripMode::Transit => "pt",
+                    TripMode::Drive => "car",
+                }
+            )?;
+        }
+        let to = endpoint_gps(&person.trips.last().unwrap().trip.end(map), map);
+        writeln!(
+            f,
+            "      <activity type=\"other\" x=\"{}\" y=\"{}\" />",
+            to.x(),
+            to.y()
+        )?;
+        writeln!(f, "    </plan>")?;
+        writeln!(f, "  </person>")?;
+    }
+    writeln!(f, "</population>")?;
+    Ok(())
+}
+
+fn endpoint_gps(endpt: &TripEndpoint, map: &Map) -> LonLat {
+    let pt = match endpt {
+        TripEndpoint::Bldg(b) => map.get_b(*b).polygon.center(),
+        // Use the real location off-map, if we know it
+        TripEndpoint::Border(_, Some(loc)) => {
+            return loc.gps;
+        }
+        TripEndpoint::Border(i, None) => map.get_i(*i).polygon.center(),
+    };
+    to_gps(pt, map)
+}
+
+fn to_gps(pt: Pt2D, map: &Map) -> LonLat {
+    map.get_gps_bounds().must_convert_back(&vec![pt])[0]
+}
+
+// HH:MM:SS, with hours going past 24 for multi-day scenarios
+fn matsim_time(seconds: f64) -> String {
+    let seconds = seconds as usize;
+    format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 3600,
+        (seconds % 3600) / 60,
+        seconds % 60
+    )
+}
+
+fn feature(pts: Vec<LonLat>, properties: Value) -> Value {
+    json!({
+        "type": "Feature",
+        "geometry": {
+            "type": "LineString",
+            "coordinates": pts.into_iter().map(|pt| vec![pt.x(), pt.y()]).collect::<Vec<_>>(),
+        },
+        "properties": properties,
+    })
+}
+
+fn write_features(path: &str, features: Vec<Value>) -> Result<(), Error> {
+    let mut f = create(path)?;
+    writeln!(
+        f,
+        "{}",
+        json!({
+            "type": "FeatureCollection",
+            "features": features,
+        })
+    )?;
+    Ok(())
+}
+
+fn create(path: &str) -> Result<File, Error> {
+    if let Some(dir) = std::path::Path::new(path).parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    File::create(path)
+}
@@ -0,0 +1,153 @@
+use crate::{AlertLocation, CarID};
+use geom::{Duration, Time};
+use map_model::{IntersectionID, Traversable, TurnID};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+// How often to look for cycles of stuck cars
+pub(crate) const GRIDLOCK_CHECK_FREQUENCY: Duration = Duration::const_seconds(60.0);
+
+// A cycle of cars all waiting on each other, so none of them can ever move.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Gridlock {
+    pub time: Time,
+    // Each car is blocked by the next one, and the last by the first
+    pub links: Vec<GridlockLink>,
+    // Different ways to break the cycle. None of them fix the underlying problem, but they point
+    // modelers at what to fix in the map.
+    pub resolutions: Vec<GridlockResolution>,
+    // If the sim was configured to resolve gridlock, what it did
+    pub applied: Option<GridlockResolution>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GridlockLink {
+    pub car: CarID,
+    pub on: Traversable,
+    // How many cars are queued on the same lane or turn
+    pub queue_len: usize,
+    // The turn the car wants to do next, and when it started waiting for it
+    pub turn: Option<TurnID>,
+    pub waiting_since: Option<Time>,
+    pub blocked_by: CarID,
+    pub blockage: Blockage,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Blockage {
+    // Stuck behind another car in the same queue
+    Queued,
+    // Waiting for a car doing a conflicting turn to finish
+    ConflictingTurn(IntersectionID),
+    // Won't start a turn, because there's no room in the target lane
+    NoRoom(IntersectionID),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum GridlockResolution {
+    // Delete the car and abort its trip
+    RemoveCar(CarID),
+    // Let cars enter the intersection, even if there's no room on the other side
+    AllowBlockTheBox {
+        intersection: IntersectionID,
+        osm_node_id: i64,
+    },
+    // Let cars start turns that conflict with ones already happening
+    AllowConflictingTurns {
+        intersection: IntersectionID,
+        osm_node_id: i64,
+    },
+}
+
+impl Gridlock {
+    pub fn cars(&self) -> Vec<CarID> {
+        self.links.iter().map(|l| l.car).collect()
+    }
+
+    // Where to point the alert
+    pub fn location(&self) -> AlertLocation {
+        for link in &self.links {
+            match link.blockage {
+                Blockage::ConflictingTurn(i) | Blockage::NoRoom(i) => {
+                    return AlertLocation::Intersection(i);
+                }
+                Blockage::Queued => {}
+            }
+        }
+        AlertLocation::Nil
+    }
+
+    pub fn describe(&self) -> String {
+        let cycle: Vec<String> = self
+            .links
+            .iter()
+            .map(|l| match l.blockage {
+                Blockage::Queued => format!("{} (queued)", l.car),
+                Blockage::ConflictingTurn(i) => format!("{} (conflicting turn at {})", l.car, i),
+                Blockage::NoRoom(i) => format!("{} (no room past {})", l.car, i),
+            })
+            .collect();
+        let mut msg = format!(
+            "Gridlock of {} cars: {}",
+            self.links.len(),
+            cycle.join(" -> ")
+        );
+        if !self.resolutions.is_empty() {
+            let fixes: Vec<String> = self.resolutions.iter().map(|r| r.describe()).collect();
+            msg = format!("{}. Could be broken by: {}", msg, fixes.join("; "));
+        }
+        if let Some(ref r) = self.applied {
+            msg = format!("{}. Resolved by: {}", msg, r.describe());
+        }
+        msg
+    }
+}
+
+impl GridlockResolution {
+    pub fn describe(&self) -> String {
+        match self {
+            GridlockResolution::RemoveCar(c) => format!("removing {}", c),
+            GridlockResolution::AllowBlockTheBox {
+                intersection,
+                osm_node_id,
+            } => format!(
+                "allowing block-the-box at {} (OSM node {})",
+                intersection, osm_node_id
+            ),
+            GridlockResolution::AllowConflictingTurns {
+                intersection,
+                osm_node_id,
+            } => format!(
+                "allowing conflicting turns at {} (OSM node {})",
+                intersection, osm_node_id
+            ),
+        }
+    }
+}
+
+// Remembers every gridlock found so far, so a cycle that sticks around is only reported once.
+#[derive(Clone)]
+pub(crate) struct GridlockDetector {
+    pub threshold: Duration,
+    pub resolve: bool,
+    reported: BTreeSet<Vec<CarID>>,
+    pub reports: Vec<Gridlock>,
+}
+
+impl GridlockDetector {
+    pub fn new(threshold: Duration, resolve: bool) -> GridlockDetector {
+        GridlockDetector {
+            threshold,
+            resolve,
+            reported: BTreeSet::new(),
+            reports: Vec::new(),
+        }
+    }
+
+    // True if this cycle hasn't been seen before
+    pub fn is_new(&mut self, gridlock: &Gridlock) -> bool {
+        let mut cars = gridlock.cars();
+        cars.sort();
+        self.reported.insert(cars)
+    }
+}
@@ -1,29 +1,50 @@
 mod analytics;
+mod comparison;
+mod counterfactual;
+mod divergence;
 mod events;
+mod export;
+mod gridlock;
 mod make;
 mod mechanics;
 mod pandemic;
+mod regional;
 mod render;
 mod router;
 mod scheduler;
 mod sim;
+mod trajectories;
 mod transit;
 mod trips;
 
 pub use self::analytics::{Analytics, TripPhase};
+pub use self::comparison::{ResultSet, Revenue, RunComparison};
+pub use self::counterfactual::{TripAlternative, TripCounterfactuals};
+pub use self::divergence::{compare_to_savestates, find_first_divergence, Divergence};
 pub(crate) use self::events::Event;
 pub use self::events::{AlertLocation, TripPhaseType};
+pub use self::export::{
+    export_matsim_plans, export_road_throughput_geojson, export_trajectories_csv, export_trips_csv,
+    export_trips_geojson,
+};
+pub use self::gridlock::{Blockage, Gridlock, GridlockLink, GridlockResolution};
+pub(crate) use self::gridlock::{GridlockDetector, GRIDLOCK_CHECK_FREQUENCY};
 pub use self::make::{
-    BorderSpawnOverTime, IndividTrip, OffMapLocation, OriginDestination, PersonSpec, Scenario,
-    ScenarioGenerator, ScenarioModifier, SimFlags, SpawnOverTime, SpawnTrip, TripSpawner, TripSpec,
+    synthetic_weekday, BorderSpawnOverTime, DepartureChoice, IndividTrip, ModeChoice, ModeShift,
+    OffMapLocation, OriginDestination, PersonSpec, Scenario, ScenarioGenerator, ScenarioModifier,
+    SimFlags, SpawnOverTime, SpawnTrip, TripSpawner, TripSpec,
 };
 pub(crate) use self::mechanics::{
     DrivingSimState, IntersectionSimState, ParkingSimState, WalkingSimState,
 };
 pub(crate) use self::pandemic::PandemicModel;
+pub use self::regional::{Handoff, Journey, Region, RegionalResults, RegionalSim};
 pub(crate) use self::router::{ActionAtEnd, Router};
 pub(crate) use self::scheduler::{Command, Scheduler};
 pub use self::sim::{AgentProperties, AlertHandler, Sim, SimCallback, SimOptions};
+pub use self::trajectories::{
+    CarSample, ParkedSample, PedSample, Trajectories, TrajectoryFrame, TrajectoryPlayback,
+};
 pub(crate) use self::transit::TransitSimState;
 pub use self::trips::{Person, PersonState, TripResult};
 pub use self::trips::{TripEndpoint, TripMode};
@@ -0,0 +1,238 @@
+use crate::{IndividTrip, PersonID, PersonSpec, Scenario, SpawnTrip, TripEndpoint, TripMode};
+use abstutil::Timer;
+use geom::{Distance, Duration, Time};
+use map_model::{BuildingID, BuildingType, LaneType, Map};
+use rand::distributions::WeightedIndex;
+use rand::Rng;
+use rand_distr::{Distribution, Normal};
+use rand_xorshift::XorShiftRng;
+use std::collections::BTreeMap;
+
+// Roughly how much floor space each resident or job takes up
+const SQ_METERS_PER_RESIDENT: f64 = 50.0;
+const SQ_METERS_PER_JOB: f64 = 30.0;
+// Every shop, restaurant, etc employs at least a few people
+const JOBS_PER_AMENITY: usize = 3;
+const PERCENT_EMPLOYED: f64 = 0.6;
+// Chance of stopping somewhere on the way home from work
+const PERCENT_ERRAND_AFTER_WORK: f64 = 0.3;
+// Chance of somebody without a job going out once during the day
+const PERCENT_ERRAND_WITHOUT_WORK: f64 = 0.5;
+
+// Builds a plausible population from the map alone, for places without a travel demand model like
+// Soundcast. Residents and jobs are estimated from the size and use of each building, then
+// everybody gets a home -> work -> (maybe errand) -> home tour, or a shorter errand tour if they
+// don't work.
+pub fn synthetic_weekday(map: &Map, rng: &mut XorShiftRng, timer: &mut Timer) -> Scenario {
+    let mut scenario = Scenario::empty(map, "synthetic");
+    scenario.only_seed_buses = None;
+
+    timer.start("estimate residents and jobs");
+    let mut homes: Vec<(BuildingID, usize)> = Vec::new();
+    let mut jobs: Vec<(BuildingID, usize)> = Vec::new();
+    let mut errands: Vec<(BuildingID, usize)> = Vec::new();
+    for b in map.all_buildings() {
+        let floor_area = b.polygon.area() * b.levels;
+        let (residential_area, commercial_area) = match b.bldg_type {
+            BuildingType::Residential => (floor_area, 0.0),
+            // Assume just the ground floor is commercial
+            BuildingType::ResidentialCommercial => (
+                floor_area * (b.levels - 1.0).max(0.5) / b.levels,
+                floor_area / b.levels,
+            ),
+            BuildingType::Commercial => (0.0, floor_area),
+            BuildingType::Empty => (0.0, 0.0),
+        };
+        // Every house has somebody living there
+        let residents = if residential_area > 0.0 {
+            ((residential_area / SQ_METERS_PER_RESIDENT).round() as usize).max(1)
+        } else {
+            0
+        };
+        let num_jobs = (commercial_area / SQ_METERS_PER_JOB).round() as usize
+            + JOBS_PER_AMENITY * b.amenities.len();
+        if residents > 0 {
+            homes.push((b.id, residents));
+        }
+        if num_jobs > 0 {
+            jobs.push((b.id, num_jobs));
+        }
+        if !b.amenities.is_empty() {
+            errands.push((b.id, b.amenities.len()));
+        }
+    }
+    timer.note(format!(
+        "{} residents and {} jobs in {} buildings",
+        abstutil::prettyprint_usize(homes.iter().map(|(_, n)| *n).sum()),
+        abstutil::prettyprint_usize(jobs.iter().map(|(_, n)| *n).sum()),
+        abstutil::prettyprint_usize(map.all_buildings().len())
+    ));
+    timer.stop("estimate residents and jobs");
+
+    let job_choice = WeightedIndex::new(jobs.iter().map(|(_, n)| *n)).ok();
+    let errand_choice = WeightedIndex::new(errands.iter().map(|(_, n)| *n)).ok();
+    let mut modes: BTreeMap<TripMode, usize> = BTreeMap::new();
+
+    let total_residents = homes.iter().map(|(_, n)| *n).sum();
+    timer.start_iter("generate tours", total_residents);
+    for (home, residents) in homes {
+        for _ in 0..residents {
+            timer.next();
+            let mut stops = Vec::new();
+            if rng.gen_bool(PERCENT_EMPLOYED) && job_choice.is_some() {
+                let work = pick_nearby(&jobs, job_choice.as_ref().unwrap(), home, 2, map, rng);
+                if work == home {
+                    // Working from home
+                    continue;
+                }
+                let leave_home = normal_time(rng, 8.0, 1.0);
+                let leave_work = leave_home + normal_duration(rng, 8.5, 1.0, 1.0);
+                stops.push((leave_home, work));
+                if rng.gen_bool(PERCENT_ERRAND_AFTER_WORK) && errand_choice.is_some() {
+                    let errand =
+                        pick_nearby(&errands, errand_choice.as_ref().unwrap(), work, 5, map, rng);
+                    if errand != work && errand != home {
+                        stops.push((leave_work, errand));
+                        stops.push((leave_work + normal_duration(rng, 1.0, 0.5, 0.25), home));
+                    } else {
+                        stops.push((leave_work, home));
+                    }
+                } else {
+                    stops.push((leave_work, home));
+                }
+            } else if rng.gen_bool(PERCENT_ERRAND_WITHOUT_WORK) && errand_choice.is_some() {
+                let errand =
+                    pick_nearby(&errands, errand_choice.as_ref().unwrap(), home, 5, map, rng);
+                if errand == home {
+                    continue;
+                }
+                let leave_home = rand_hour(rng, 9.0, 17.0);
+                stops.push((leave_home, errand));
+                stops.push((leave_home + normal_duration(rng, 1.0, 0.5, 0.25), home));
+            } else {
+                continue;
+            }
+
+            // The whole tour uses the same mode, so cars and bikes wind up back home
+            let longest_leg = {
+                let mut from = home;
+                let mut longest = Distance::ZERO;
+                for (_, to) in &stops {
+                    longest = longest.max(dist_between(from, *to, map));
+                    from = *to;
+                }
+                longest
+            };
+            let mode = pick_mode(home, stops[0].1, longest_leg, map, rng);
+            *modes.entry(mode).or_insert(0) += 1;
+
+            let mut trips = Vec::new();
+            let mut from = home;
+            for (depart, to) in stops {
+                trips.push(IndividTrip {
+                    depart,
+                    trip: SpawnTrip::new(
+                        TripEndpoint::Bldg(from),
+                        TripEndpoint::Bldg(to),
+                        mode,
+                        map,
+                    ),
+                    cancelled: false,
+                });
+                from = to;
+            }
+            scenario.people.push(PersonSpec {
+                id: PersonID(scenario.people.len()),
+                orig_id: None,
+                trips,
+            });
+        }
+    }
+    for (mode, cnt) in modes {
+        timer.note(format!(
+            "{} tours by {:?}",
+            abstutil::prettyprint_usize(cnt),
+            mode
+        ));
+    }
+    scenario
+}
+
+// Samples a few candidates (weighted by size) and takes the closest, so trips favor nearby
+// destinations without ignoring big attractors farther away.
+fn pick_nearby(
+    candidates: &[(BuildingID, usize)],
+    choice: &WeightedIndex<usize>,
+    from: BuildingID,
+    num_samples: usize,
+    map: &Map,
+    rng: &mut XorShiftRng,
+) -> BuildingID {
+    (0..num_samples)
+        .map(|_| candidates[choice.sample(rng)].0)
+        .min_by_key(|b| dist_between(from, *b, map))
+        .unwrap()
+}
+
+fn pick_mode(
+    home: BuildingID,
+    first_stop: BuildingID,
+    dist: Distance,
+    map: &Map,
+    rng: &mut XorShiftRng,
+) -> TripMode {
+    // Most people walk short distances
+    let walk_limit = Distance::miles(1.0);
+    if dist < walk_limit && rng.gen_bool(1.0 - dist / walk_limit) {
+        return TripMode::Walk;
+    }
+
+    // More people bike when there's a bike lane near home or the first destination
+    if dist < Distance::miles(5.0) {
+        let has_bike_lane = [home, first_stop].iter().any(|b| {
+            let (fwd, back) = map.get_parent(map.get_b(*b).sidewalk()).get_lane_types();
+            fwd.contains(&LaneType::Biking) || back.contains(&LaneType::Biking)
+        });
+        if rng.gen_bool(if has_bike_lane { 0.3 } else { 0.05 }) {
+            return TripMode::Bike;
+        }
+    }
+
+    // Transit is only an option with a bus stop nearby. SpawnTrip::new falls back to walking if
+    // there's no useful route.
+    let near_bus_stop = !map
+        .get_parent(map.get_b(home).sidewalk())
+        .all_bus_stops(map)
+        .is_empty();
+    if near_bus_stop && dist > Distance::miles(0.5) && rng.gen_bool(0.3) {
+        return TripMode::Transit;
+    }
+
+    TripMode::Drive
+}
+
+fn dist_between(b1: BuildingID, b2: BuildingID, map: &Map) -> Distance {
+    map.get_b(b1)
+        .polygon
+        .center()
+        .dist_to(map.get_b(b2).polygon.center())
+}
+
+fn normal_time(rng: &mut XorShiftRng, mean_hours: f64, std_hours: f64) -> Time {
+    Time::START_OF_DAY + normal_duration(rng, mean_hours, std_hours, 0.0)
+}
+
+// Never less than min_hours
+fn normal_duration(
+    rng: &mut XorShiftRng,
+    mean_hours: f64,
+    std_hours: f64,
+    min_hours: f64,
+) -> Duration {
+    let hours = Normal::new(mean_hours, std_hours).unwrap().sample(rng);
+    Duration::seconds(3600.0 * hours.max(min_hours))
+}
+
+fn rand_hour(rng: &mut XorShiftRng, low: f64, high: f64) -> Time {
+    Time::START_OF_DAY + Duration::seconds(3600.0 * rng.gen_range(low, high))
+}
@@ -0,0 +1,294 @@
+use crate::make::mode_choice::{BIKING_SPEED, WALKING_SPEED};
+use crate::{Analytics, DrivingGoal, Scenario, SidewalkPOI, SidewalkSpot, SpawnTrip, TripMode};
+use abstutil::{prettyprint_usize, Timer};
+use geom::{Distance, Duration, Speed, Time};
+use map_model::{IntersectionID, Map, Path, PathConstraints, PathRequest, PathStep, Position};
+use rand::Rng;
+use rand_xorshift::XorShiftRng;
+use std::collections::BTreeMap;
+
+// Delays from the prior run are averaged over bins of time this long...
+const BIN_SIZE: Duration = Duration::const_seconds(15.0 * 60.0);
+// ... and reaching an intersection uses this many bins on either side
+const NEARBY_BINS: usize = 2;
+
+// Shifts when people leave, to dodge the congestion experienced in a prior run. Like in bottleneck
+// models, people want to arrive when they would with no traffic at all, and trade off arriving
+// early or late against time spent stuck in traffic.
+//
+// This is one iteration. Run the new scenario, save the results, and apply this again to move
+// towards an equilibrium.
+#[derive(Clone)]
+pub struct DepartureChoice {
+    // Each person is willing to shift all of their trips by up to some random fraction of this
+    pub max_flexibility: Duration,
+    // How finely to try other departure times
+    pub step: Duration,
+    // How much each minute of arriving early or late matters, compared to a minute of travel
+    pub early_weight: f64,
+    pub late_weight: f64,
+    // Only shift a trip when it's better than the original by this much
+    pub min_improvement: Duration,
+}
+
+impl DepartureChoice {
+    pub fn new() -> DepartureChoice {
+        DepartureChoice {
+            max_flexibility: Duration::minutes(60),
+            step: Duration::minutes(5),
+            early_weight: 0.5,
+            late_weight: 1.5,
+            min_improvement: Duration::minutes(1),
+        }
+    }
+
+    pub fn apply(
+        &self,
+        mut scenario: Scenario,
+        prior: &Analytics,
+        map: &Map,
+        rng: &mut XorShiftRng,
+        timer: &mut Timer,
+    ) -> Scenario {
+        let delays = DelayProfile::new(prior);
+        let mut num_shifted = 0;
+        let mut total_shift = Duration::ZERO;
+
+        timer.start_iter("shift departures", scenario.people.len());
+        for person in &mut scenario.people {
+            timer.next();
+            // Draw even for people with nothing to shift, to keep the RNG stable
+            let flexibility = self.max_flexibility * rng.gen_range(0.0, 1.0);
+
+            // Don't leave before the previous trip is probably done
+            let mut earliest = Time::START_OF_DAY;
+            for idx in 0..person.trips.len() {
+                let next_depart = person.trips.get(idx + 1).map(|t| t.depart);
+                let trip = &mut person.trips[idx];
+                let orig_depart = trip.depart;
+                let (req, mode, max_speed) = match trip_path(&trip.trip, map) {
+                    Some(x) if !trip.cancelled => x,
+                    _ => {
+                        earliest = orig_depart;
+                        continue;
+                    }
+                };
+                let path = match map.pathfind_at(req, orig_depart) {
+                    Some(p) => p,
+                    None => {
+                        earliest = orig_depart;
+                        continue;
+                    }
+                };
+
+                let preferred_arrival =
+                    orig_depart + travel_time(&path, mode, max_speed, map, None);
+                let estimate = |depart: Time| {
+                    travel_time(&path, mode, max_speed, map, Some((&delays, depart)))
+                };
+                let orig_travel = estimate(orig_depart);
+                let orig_cost =
+                    self.cost(orig_depart + orig_travel, orig_travel, preferred_arrival);
+
+                let mut best: Option<(f64, Time, Duration)> = None;
+                let max_steps = (flexibility / self.step).floor() as isize;
+                for i in -max_steps..=max_steps {
+                    let offset = self.step * (i as f64);
+                    if i == 0 || (orig_depart - Time::START_OF_DAY) + offset < Duration::ZERO {
+                        continue;
+                    }
+                    let depart = orig_depart + offset;
+                    if depart <= earliest {
+                        continue;
+                    }
+                    let travel = estimate(depart);
+                    // Keep the schedule in order
+                    if next_depart.map(|t| depart + travel >= t).unwrap_or(false) {
+                        continue;
+                    }
+                    let cost = self.cost(depart + travel, travel, preferred_arrival);
+                    if best.map(|(c, _, _)| cost < c).unwrap_or(true) {
+                        best = Some((cost, depart, travel));
+                    }
+                }
+
+                match best {
+                    Some((cost, depart, travel))
+                        if cost < orig_cost - self.min_improvement.inner_seconds() =>
+                    {
+                        trip.depart = depart;
+                        earliest = depart + travel;
+                        num_shifted += 1;
+                        total_shift += if depart > orig_depart {
+                            depart - orig_depart
+                        } else {
+                            orig_depart - depart
+                        };
+                    }
+                    _ => {
+                        earliest = orig_depart + orig_travel;
+                    }
+                }
+            }
+        }
+
+        if num_shifted > 0 {
+            timer.note(format!(
+                "Shifted {} trips, by {} on average",
+                prettyprint_usize(num_shifted),
+                total_shift / (num_shifted as f64)
+            ));
+        }
+        scenario
+    }
+
+    // In seconds
+    fn cost(&self, arrival: Time, travel: Duration, preferred_arrival: Time) -> f64 {
+        let schedule_delay = if arrival < preferred_arrival {
+            self.early_weight * (preferred_arrival - arrival).inner_seconds()
+        } else {
+            self.late_weight * (arrival - preferred_arrival).inner_seconds()
+        };
+        travel.inner_seconds() + schedule_delay
+    }
+}
+
+// The average delay at each intersection, per mode and bin of time
+struct DelayProfile {
+    bins: BTreeMap<(IntersectionID, TripMode, usize), (Duration, usize)>,
+}
+
+impl DelayProfile {
+    fn new(analytics: &Analytics) -> DelayProfile {
+        let mut bins = BTreeMap::new();
+        for (i, delays) in &analytics.intersection_delays {
+            for (t, dt, mode) in delays {
+                let entry = bins
+                    .entry((*i, *mode, bin(*t)))
+                    .or_insert((Duration::ZERO, 0));
+                entry.0 += *dt;
+                entry.1 += 1;
+            }
+        }
+        DelayProfile { bins }
+    }
+
+    fn typical_delay(&self, i: IntersectionID, mode: TripMode, time: Time) -> Duration {
+        let mut total = Duration::ZERO;
+        let mut count = 0;
+        let b = bin(time);
+        for b in b.saturating_sub(NEARBY_BINS)..=b + NEARBY_BINS {
+            if let Some((dt, cnt)) = self.bins.get(&(i, mode, b)) {
+                total += *dt;
+                count += cnt;
+            }
+        }
+        if count == 0 {
+            Duration::ZERO
+        } else {
+            total / (count as f64)
+        }
+    }
+}
+
+fn bin(time: Time) -> usize {
+    ((time - Time::START_OF_DAY) / BIN_SIZE).floor() as usize
+}
+
+// Crossing everything at full speed, plus the typical delay at each intersection if a prior run
+// and departure time are given
+fn travel_time(
+    path: &Path,
+    mode: TripMode,
+    max_speed: Option<Speed>,
+    map: &Map,
+    delays: Option<(&DelayProfile, Time)>,
+) -> Duration {
+    let mut time = Duration::ZERO;
+    for step in path.get_steps() {
+        let on = step.as_traversable();
+        // Pedestrians ignore speed limits
+        let speed = match max_speed {
+            Some(s) if mode == TripMode::Walk => s,
+            Some(s) => on.speed_limit(map).min(s),
+            None => on.speed_limit(map),
+        };
+        time += on.length(map) / speed;
+        if let (PathStep::Turn(t), Some((profile, depart))) = (step, delays) {
+            time += profile.typical_delay(t.parent, mode, depart + time);
+        }
+    }
+    time
+}
+
+// Transit trips are left alone, since buses don't wait for anybody, and so are trips that are
+// entirely off-map.
+fn trip_path(trip: &SpawnTrip, map: &Map) -> Option<(PathRequest, TripMode, Option<Speed>)> {
+    match trip {
+        SpawnTrip::VehicleAppearing {
+            start,
+            goal,
+            is_bike,
+        } => Some(vehicle_path(*start, goal, *is_bike, map)),
+        SpawnTrip::FromBorder {
+            dr, goal, is_bike, ..
+        } => {
+            let constraints = if *is_bike {
+                PathConstraints::Bike
+            } else {
+                PathConstraints::Car
+            };
+            let lane = *dr.lanes(constraints, map).get(0)?;
+            Some(vehicle_path(
+                Position::new(lane, Distance::ZERO),
+                goal,
+                *is_bike,
+                map,
+            ))
+        }
+        SpawnTrip::UsingParkedCar(b, goal) => Some(vehicle_path(
+            Position::new(map.find_driving_lane_near_building(*b), Distance::ZERO),
+            goal,
+            false,
+            map,
+        )),
+        SpawnTrip::UsingBike(spot, goal) => {
+            match SidewalkSpot::bike_from_bike_rack(spot.sidewalk_pos.lane(), map)?.connection {
+                SidewalkPOI::BikeRack(pos) => Some(vehicle_path(pos, goal, true, map)),
+                _ => unreachable!(),
+            }
+        }
+        SpawnTrip::JustWalking(start, end) => Some((
+            PathRequest {
+                start: start.sidewalk_pos,
+                end: end.sidewalk_pos,
+                constraints: PathConstraints::Pedestrian,
+            },
+            TripMode::Walk,
+            Some(WALKING_SPEED),
+        )),
+        SpawnTrip::UsingTransit(_, _, _, _, _) | SpawnTrip::Remote { .. } => None,
+    }
+}
+
+fn vehicle_path(
+    start: Position,
+    goal: &DrivingGoal,
+    is_bike: bool,
+    map: &Map,
+) -> (PathRequest, TripMode, Option<Speed>) {
+    let (constraints, mode, max_speed) = if is_bike {
+        (PathConstraints::Bike, TripMode::Bike, Some(BIKING_SPEED))
+    } else {
+        (PathConstraints::Car, TripMode::Drive, None)
+    };
+    (
+        PathRequest {
+            start,
+            end: goal.goal_pos(constraints, map),
+            constraints,
+        },
+        mode,
+        max_speed,
+    )
+}
@@ -1,5 +1,6 @@
 use crate::{AlertHandler, Scenario, Sim, SimOptions};
 use abstutil::CmdArgs;
+use geom::Duration;
 use map_model::{Map, MapEdits};
 use rand::SeedableRng;
 use rand_xorshift::XorShiftRng;
@@ -47,6 +48,26 @@ impl SimFlags {
                     })
                     .unwrap_or(AlertHandler::Print),
                 pathfinding_upfront: args.enabled("--pathfinding_upfront"),
+                record_trajectories: args
+                    .optional_parse("--record_trajectories", |s| s.parse::<f64>())
+                    .map(|secs| {
+                        if secs <= 0.0 || !secs.is_finite() {
+                            panic!("Bad --record_trajectories={}. Must be over 0 seconds", secs);
+                        }
+                        Duration::seconds(secs)
+                    }),
+                checkpoint_interval: args
+                    .optional_parse("--checkpoint_minutes", |s| s.parse::<usize>())
+                    .map(|mins| {
+                        if mins == 0 {
+                            panic!("Bad --checkpoint_minutes=0. Must be at least 1 minute");
+                        }
+                        Duration::minutes(mins)
+                    }),
+                detect_gridlock: args
+                    .optional_parse("--detect_gridlock", |s| s.parse::<usize>())
+                    .map(Duration::minutes),
+                resolve_gridlock: args.enabled("--resolve_gridlock"),
             },
         }
     }
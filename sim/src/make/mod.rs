@@ -1,13 +1,19 @@
+mod activity_model;
+mod departure_choice;
 mod generator;
 mod load;
+mod mode_choice;
 mod modifier;
 mod scenario;
 mod spawner;
 
+pub use self::activity_model::synthetic_weekday;
+pub use self::departure_choice::DepartureChoice;
 pub use self::generator::{
     BorderSpawnOverTime, OriginDestination, ScenarioGenerator, SpawnOverTime,
 };
 pub use self::load::SimFlags;
+pub use self::mode_choice::{ModeChoice, ModeShift};
 pub use self::modifier::ScenarioModifier;
 pub use self::scenario::{IndividTrip, OffMapLocation, PersonSpec, Scenario, SpawnTrip};
 pub use self::spawner::{TripSpawner, TripSpec};
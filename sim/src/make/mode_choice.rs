@@ -0,0 +1,382 @@
+use crate::mechanics::{TIME_TO_FINISH_BIKING, TIME_TO_START_BIKING};
+use crate::{
+    DrivingGoal, PersonID, PersonSpec, Scenario, SidewalkPOI, SidewalkSpot, SpawnTrip,
+    TripEndpoint, TripMode,
+};
+use abstutil::{prettyprint_usize, Timer};
+use geom::{Distance, Duration, Speed, Time};
+use map_model::{BuildingID, Map, Path, PathConstraints, PathRequest, Position};
+use rand::Rng;
+use rand_xorshift::XorShiftRng;
+use std::collections::{BTreeMap, BTreeSet};
+
+// Travel times are estimated without any traffic, so these are just typical speeds
+pub(crate) const WALKING_SPEED: Speed = Speed::const_meters_per_second(1.12);
+pub(crate) const BIKING_SPEED: Speed = Speed::const_meters_per_second(4.0);
+
+// Re-decides how people get around, based on how long each mode takes on the current map and what
+// it costs. Every trip of a person uses the same mode, so cars and bikes wind up back home. Only
+// people whose trips all go between buildings are eligible; the rest keep their original modes.
+//
+// Each mode's utility is a weighted sum of "generalized minutes" -- travel time, plus costs and
+// penalties converted into time -- and people pick a mode randomly with logit probabilities.
+#[derive(Clone)]
+pub struct ModeChoice {
+    // How much each extra generalized minute matters. Higher values make choices more
+    // deterministic.
+    pub minute_weight: f64,
+    // Generalized minutes added to every trip, to capture everything else about a mode
+    pub mode_penalty: BTreeMap<TripMode, f64>,
+    // In dollars
+    pub driving_cost_per_mile: f64,
+    pub transit_fare: f64,
+    // Charged when the destination has no off-street parking
+    pub parking_cost: f64,
+    // Time spent circling when the destination has no off-street parking. Doubled if there's no
+    // on-street parking on the same road either.
+    pub parking_search: Duration,
+    // Time spent waiting for a bus, since schedules aren't known ahead of time
+    pub transit_wait: Duration,
+    // Walking to and from bus stops feels this much longer than it is
+    pub transit_access_weight: f64,
+}
+
+impl ModeChoice {
+    pub fn new() -> ModeChoice {
+        let mut mode_penalty = BTreeMap::new();
+        mode_penalty.insert(TripMode::Walk, 0.0);
+        mode_penalty.insert(TripMode::Bike, 10.0);
+        mode_penalty.insert(TripMode::Transit, 5.0);
+        mode_penalty.insert(TripMode::Drive, 0.0);
+        ModeChoice {
+            minute_weight: 0.1,
+            mode_penalty,
+            driving_cost_per_mile: 0.6,
+            transit_fare: 2.75,
+            parking_cost: 5.0,
+            parking_search: Duration::minutes(5),
+            transit_wait: Duration::minutes(5),
+            transit_access_weight: 2.0,
+        }
+    }
+
+    pub fn apply(
+        &self,
+        mut scenario: Scenario,
+        map: &Map,
+        rng: &mut XorShiftRng,
+        timer: &mut Timer,
+    ) -> Scenario {
+        let buses = scenario.only_seed_buses.clone();
+        timer.start_iter("choose modes", scenario.people.len());
+        for person in &mut scenario.people {
+            timer.next();
+            let trips = match eligible_trips(person, map) {
+                Some(trips) => trips,
+                None => {
+                    continue;
+                }
+            };
+
+            let mut modes = Vec::new();
+            let mut costs = Vec::new();
+            for mode in TripMode::all() {
+                if let Some(cost) = trips
+                    .iter()
+                    .zip(person.trips.iter())
+                    .map(|((b1, b2), trip)| {
+                        self.generalized_minutes(*b1, *b2, trip.depart, mode, &buses, map)
+                    })
+                    .sum::<Option<f64>>()
+                {
+                    modes.push(mode);
+                    costs.push(cost);
+                }
+            }
+            let mode = match logit_choice(&costs, self.minute_weight, rng) {
+                Some(idx) => modes[idx],
+                None => {
+                    continue;
+                }
+            };
+
+            for (trip, (b1, b2)) in person.trips.iter_mut().zip(trips) {
+                if trip.trip.mode() != mode {
+                    trip.trip =
+                        SpawnTrip::new(TripEndpoint::Bldg(b1), TripEndpoint::Bldg(b2), mode, map);
+                }
+            }
+        }
+        scenario
+    }
+
+    // None if the mode can't be used for this trip at all
+    fn generalized_minutes(
+        &self,
+        b1: BuildingID,
+        b2: BuildingID,
+        depart: Time,
+        mode: TripMode,
+        buses: &Option<BTreeSet<String>>,
+        map: &Map,
+    ) -> Option<f64> {
+        let start = map.get_b(b1).front_path.sidewalk;
+        let end = map.get_b(b2).front_path.sidewalk;
+        let (time, cost) = match mode {
+            TripMode::Walk => {
+                let path = pathfind(start, end, PathConstraints::Pedestrian, map)?;
+                (path.total_length() / WALKING_SPEED, 0.0)
+            }
+            TripMode::Bike => {
+                let start = match SidewalkSpot::bike_from_bike_rack(start.lane(), map)?.connection {
+                    SidewalkPOI::BikeRack(pos) => pos,
+                    _ => unreachable!(),
+                };
+                let end = DrivingGoal::ParkNear(b2).goal_pos(PathConstraints::Bike, map);
+                let path = pathfind(start, end, PathConstraints::Bike, map)?;
+                (
+                    free_flow_time(&path, Some(BIKING_SPEED), map)
+                        + TIME_TO_START_BIKING
+                        + TIME_TO_FINISH_BIKING,
+                    0.0,
+                )
+            }
+            TripMode::Transit => {
+                let (stop1, stop2, route) = map.should_use_transit(start, end)?;
+                // The route has to actually run in this scenario
+                if let Some(ref routes) = buses {
+                    if !routes.contains(&map.get_br(route).name) {
+                        return None;
+                    }
+                }
+                let walk1 = pathfind(
+                    start,
+                    map.get_bs(stop1).sidewalk_pos,
+                    PathConstraints::Pedestrian,
+                    map,
+                )?;
+                let ride = pathfind(
+                    map.get_bs(stop1).driving_pos,
+                    map.get_bs(stop2).driving_pos,
+                    PathConstraints::Bus,
+                    map,
+                )?;
+                let walk2 = pathfind(
+                    map.get_bs(stop2).sidewalk_pos,
+                    end,
+                    PathConstraints::Pedestrian,
+                    map,
+                )?;
+                let walking = (walk1.total_length() + walk2.total_length()) / WALKING_SPEED;
+                (
+                    walking * self.transit_access_weight
+                        + self.transit_wait
+                        + free_flow_time(&ride, None, map),
+                    self.transit_fare,
+                )
+            }
+            TripMode::Drive => {
+                let start = Position::new(map.find_driving_lane_near_building(b1), Distance::ZERO);
+                let end = DrivingGoal::ParkNear(b2).goal_pos(PathConstraints::Car, map);
+                let path = map.pathfind_at(
+                    PathRequest {
+                        start,
+                        end,
+                        constraints: PathConstraints::Car,
+                    },
+                    depart,
+                )?;
+                let mut time = free_flow_time(&path, None, map);
+                let mut cost = self.driving_cost_per_mile
+                    * (path.total_length() / Distance::miles(1.0))
+                    + map.get_pricing().path_cost(&path, depart, map);
+                if !has_offstreet_parking(b2, map) {
+                    time += self.parking_search;
+                    cost += self.parking_cost;
+                    if !has_onstreet_parking(b2, map) {
+                        time += self.parking_search;
+                    }
+                }
+                (time, cost)
+            }
+        };
+        // Costs become time using the same value of time as pricing-aware pathfinding
+        let value_of_time = map.get_edits().value_of_time;
+        let cost_minutes = if value_of_time > 0.0 {
+            60.0 * cost / value_of_time
+        } else {
+            0.0
+        };
+        Some(
+            time.inner_seconds() / 60.0
+                + cost_minutes
+                + self.mode_penalty.get(&mode).cloned().unwrap_or(0.0),
+        )
+    }
+}
+
+// How many trips changed modes between two runs of mode choice, usually on a map before and after
+// some edits. Comparing against the original scenario instead would also count everyone who just
+// picks differently than the source data, even without any edits.
+pub struct ModeShift {
+    pub shifts: BTreeMap<(TripMode, TripMode), usize>,
+    // With RepeatDays, every day's trips are counted
+    pub days: usize,
+}
+
+impl ModeShift {
+    // Trips are matched by person and their index in that person's schedule, so both scenarios
+    // have to come from the same one, repeated the same number of days.
+    pub fn new(before: &Scenario, after: &Scenario, days: usize) -> ModeShift {
+        let before_people: BTreeMap<PersonID, &PersonSpec> =
+            before.people.iter().map(|p| (p.id, p)).collect();
+        let mut shifts = BTreeMap::new();
+        for person in &after.people {
+            let orig = before_people[&person.id];
+            assert_eq!(orig.trips.len(), person.trips.len());
+            for (trip1, trip2) in orig.trips.iter().zip(person.trips.iter()) {
+                if trip1.cancelled || trip2.cancelled {
+                    continue;
+                }
+                *shifts
+                    .entry((trip1.trip.mode(), trip2.trip.mode()))
+                    .or_insert(0) += 1;
+            }
+        }
+        ModeShift { shifts, days }
+    }
+
+    pub fn num_changed(&self) -> usize {
+        self.shifts
+            .iter()
+            .filter(|((before, after), _)| before != after)
+            .map(|(_, cnt)| *cnt)
+            .sum()
+    }
+
+    pub fn describe(&self) -> Vec<String> {
+        let total: usize = self.shifts.values().sum();
+        let mut lines = vec![format!(
+            "{} of {} trips changed modes",
+            prettyprint_usize(self.num_changed()),
+            prettyprint_usize(total)
+        )];
+        if self.days > 1 {
+            lines.push(format!(
+                "That's over {} days, so {} trips per day",
+                self.days,
+                prettyprint_usize(self.num_changed() / self.days)
+            ));
+        }
+        for ((before, after), cnt) in &self.shifts {
+            if before != after {
+                lines.push(format!(
+                    "{} trips: {} -> {}",
+                    prettyprint_usize(*cnt),
+                    before.ongoing_verb(),
+                    after.ongoing_verb()
+                ));
+            }
+        }
+        lines
+    }
+}
+
+// The (start, end) of every trip, if this person can change modes
+fn eligible_trips(person: &PersonSpec, map: &Map) -> Option<Vec<(BuildingID, BuildingID)>> {
+    let mut trips = Vec::new();
+    for trip in &person.trips {
+        if trip.cancelled {
+            return None;
+        }
+        match trip.trip {
+            SpawnTrip::UsingParkedCar(_, _)
+            | SpawnTrip::UsingBike(_, _)
+            | SpawnTrip::JustWalking(_, _)
+            | SpawnTrip::UsingTransit(_, _, _, _, _) => {}
+            SpawnTrip::VehicleAppearing { .. }
+            | SpawnTrip::FromBorder { .. }
+            | SpawnTrip::Remote { .. } => {
+                return None;
+            }
+        }
+        match (trip.trip.start(map), trip.trip.end(map)) {
+            (TripEndpoint::Bldg(b1), TripEndpoint::Bldg(b2)) => {
+                trips.push((b1, b2));
+            }
+            _ => {
+                return None;
+            }
+        }
+    }
+    if trips.is_empty() {
+        None
+    } else {
+        Some(trips)
+    }
+}
+
+// Picks an index with logit probabilities; lower costs are better. Always draws exactly once from
+// the RNG, so map edits affecting one person's options don't change everybody else's choices.
+fn logit_choice(costs: &Vec<f64>, weight: f64, rng: &mut XorShiftRng) -> Option<usize> {
+    let draw = rng.gen_range(0.0, 1.0);
+    if costs.is_empty() {
+        return None;
+    }
+    // Shift everything by the best option first, so exp() doesn't underflow for long trips
+    let best = costs.iter().cloned().fold(std::f64::INFINITY, f64::min);
+    let weights: Vec<f64> = costs
+        .iter()
+        .map(|cost| (-weight * (cost - best)).exp())
+        .collect();
+    let total: f64 = weights.iter().sum();
+    let mut sum = 0.0;
+    for (idx, w) in weights.iter().enumerate() {
+        sum += w / total;
+        if draw < sum {
+            return Some(idx);
+        }
+    }
+    Some(weights.len() - 1)
+}
+
+fn pathfind(
+    start: Position,
+    end: Position,
+    constraints: PathConstraints,
+    map: &Map,
+) -> Option<Path> {
+    map.pathfind(PathRequest {
+        start,
+        end,
+        constraints,
+    })
+}
+
+fn free_flow_time(path: &Path, max_speed: Option<Speed>, map: &Map) -> Duration {
+    let mut time = Duration::ZERO;
+    for step in path.get_steps() {
+        let on = step.as_traversable();
+        let mut speed = on.speed_limit(map);
+        if let Some(s) = max_speed {
+            speed = speed.min(s);
+        }
+        time += on.length(map) / speed;
+    }
+    time
+}
+
+fn has_offstreet_parking(b: BuildingID, map: &Map) -> bool {
+    map.get_b(b)
+        .parking
+        .as_ref()
+        .map(|p| p.num_spots > 0)
+        .unwrap_or(false)
+}
+
+fn has_onstreet_parking(b: BuildingID, map: &Map) -> bool {
+    map.get_parent(map.find_driving_lane_near_building(b))
+        .all_lanes()
+        .into_iter()
+        .any(|l| map.get_l(l).is_parking())
+}
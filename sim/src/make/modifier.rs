@@ -1,5 +1,7 @@
-use crate::{IndividTrip, Scenario};
+use crate::{DepartureChoice, IndividTrip, ModeChoice, ResultSet, Scenario};
+use abstutil::Timer;
 use geom::Duration;
+use map_model::Map;
 use rand::Rng;
 use rand_xorshift::XorShiftRng;
 
@@ -7,15 +9,38 @@ use rand_xorshift::XorShiftRng;
 pub enum ScenarioModifier {
     RepeatDays(usize),
     CancelPeople(usize),
+    // Re-decide modes with the default ModeChoice model, based on the current map
+    ChooseModes,
+    // Shift departure times to avoid the congestion in some saved results
+    ShiftDepartures(String),
 }
 
 impl ScenarioModifier {
     // If this modifies scenario_name, then that means prebaked results don't match up and
     // shouldn't be used.
-    pub fn apply(&self, s: Scenario, rng: &mut XorShiftRng) -> Scenario {
+    pub fn apply(
+        &self,
+        s: Scenario,
+        map: &Map,
+        rng: &mut XorShiftRng,
+        timer: &mut Timer,
+    ) -> Scenario {
         match self {
             ScenarioModifier::RepeatDays(n) => repeat_days(s, *n),
             ScenarioModifier::CancelPeople(pct) => cancel_people(s, *pct, rng),
+            ScenarioModifier::ChooseModes => ModeChoice::new().apply(s, map, rng, timer),
+            ScenarioModifier::ShiftDepartures(ref results) => {
+                match ResultSet::load(map.get_name(), results, timer) {
+                    Ok(prior) => DepartureChoice::new().apply(s, &prior.analytics, map, rng, timer),
+                    Err(err) => {
+                        timer.warn(format!(
+                            "Not shifting departures, can't load {}: {}",
+                            results, err
+                        ));
+                        s
+                    }
+                }
+            }
         }
     }
 
@@ -25,6 +50,12 @@ impl ScenarioModifier {
             ScenarioModifier::CancelPeople(pct) => {
                 format!("cancel all trips for {}% of people", pct)
             }
+            ScenarioModifier::ChooseModes => {
+                "choose modes based on travel time and cost".to_string()
+            }
+            ScenarioModifier::ShiftDepartures(ref results) => {
+                format!("shift departures to avoid congestion in {}", results)
+            }
         }
     }
 }
@@ -340,7 +340,7 @@ fn find_spot_near_building(
 }
 
 impl SpawnTrip {
-    fn to_trip_spec(
+    pub(crate) fn to_trip_spec(
         self,
         use_vehicle: Option<CarID>,
         rng: &mut XorShiftRng,
@@ -472,6 +472,23 @@ impl SpawnTrip {
         }
     }
 
+    pub fn mode(&self) -> TripMode {
+        match self {
+            SpawnTrip::VehicleAppearing { is_bike, .. } | SpawnTrip::FromBorder { is_bike, .. } => {
+                if *is_bike {
+                    TripMode::Bike
+                } else {
+                    TripMode::Drive
+                }
+            }
+            SpawnTrip::UsingParkedCar(_, _) => TripMode::Drive,
+            SpawnTrip::UsingBike(_, _) => TripMode::Bike,
+            SpawnTrip::JustWalking(_, _) => TripMode::Walk,
+            SpawnTrip::UsingTransit(_, _, _, _, _) => TripMode::Transit,
+            SpawnTrip::Remote { mode, .. } => *mode,
+        }
+    }
+
     pub fn new(from: TripEndpoint, to: TripEndpoint, mode: TripMode, map: &Map) -> SpawnTrip {
         match mode {
             TripMode::Drive => match from {
@@ -560,7 +577,7 @@ impl PersonSpec {
         Ok(())
     }
 
-    fn get_vehicles(
+    pub(crate) fn get_vehicles(
         &self,
         rng: &mut XorShiftRng,
     ) -> (
@@ -200,7 +200,7 @@ impl TripSpawner {
                     tuple,
                     req.clone(),
                     if pathfinding_upfront {
-                        req.and_then(|r| map.pathfind(r))
+                        req.and_then(|r| map.pathfind_at(r, tuple.1))
                     } else {
                         None
                     },
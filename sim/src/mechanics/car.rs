@@ -37,6 +37,24 @@ impl Car {
         self.crossing_state_with_end_dist(dist_int, start_time, map)
     }
 
+    // Turn pockets only exist for the last stretch of a lane. Cars physically reach them in the
+    // adjacent lane, so they appear where the pocket begins, but take as long as crossing the
+    // whole lane would.
+    pub fn pocket_crossing_state(
+        &self,
+        pocket_start: Distance,
+        start_time: Time,
+        map: &Map,
+    ) -> CarState {
+        match self.crossing_state(Distance::ZERO, start_time, map) {
+            CarState::Crossing(time_int, dist_int) => CarState::Crossing(
+                time_int,
+                DistanceInterval::new_driving(pocket_start.min(dist_int.end), dist_int.end),
+            ),
+            _ => unreachable!(),
+        }
+    }
+
     pub fn crossing_state_with_end_dist(
         &self,
         dist_int: DistanceInterval,
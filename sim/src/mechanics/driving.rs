@@ -2,8 +2,8 @@ use crate::mechanics::car::{Car, CarState};
 use crate::mechanics::Queue;
 use crate::{
     ActionAtEnd, AgentID, AgentProperties, CarID, Command, CreateCar, DistanceInterval,
-    DrawCarInput, Event, IntersectionSimState, ParkedCar, ParkingSimState, PersonID, Scheduler,
-    TimeInterval, TransitSimState, TripManager, UnzoomedAgent, Vehicle, WalkingSimState,
+    DrawCarInput, Event, Gridlock, IntersectionSimState, ParkedCar, ParkingSimState, PersonID,
+    Scheduler, TimeInterval, TransitSimState, TripManager, UnzoomedAgent, Vehicle, WalkingSimState,
     FOLLOWING_DISTANCE,
 };
 use abstutil::{deserialize_btreemap, serialize_btreemap};
@@ -66,13 +66,24 @@ impl DrivingSimState {
     pub fn start_car_on_lane(
         &mut self,
         now: Time,
-        params: CreateCar,
+        mut params: CreateCar,
         map: &Map,
         intersections: &IntersectionSimState,
         parking: &ParkingSimState,
         scheduler: &mut Scheduler,
     ) -> bool {
         let first_lane = params.router.head().as_lane();
+        // Nothing can appear before a turn pocket opens up, whether it's driving in from a border,
+        // leaving a bus stop, or pulling out of a parking spot into the adjacent lane. Cars parked
+        // along a pocket and staying on that lane just stay put, so they don't pass their end.
+        if params.maybe_parked_car.is_none() || !params.router.last_step() {
+            if let Some(start) = map.get_l(first_lane).pocket_start() {
+                let min_dist = (start + params.vehicle.length).min(map.get_l(first_lane).length());
+                if params.start_dist < min_dist {
+                    params.start_dist = min_dist;
+                }
+            }
+        }
 
         if !intersections.nobody_headed_towards(first_lane, map.get_l(first_lane).src_i) {
             return false;
@@ -364,7 +375,10 @@ impl DrivingSimState {
                     &mut self.events,
                 );
                 car.total_blocked_time += now - blocked_since;
-                car.state = car.crossing_state(Distance::ZERO, now, map);
+                car.state = match goto.maybe_lane().and_then(|l| map.get_l(l).pocket_start()) {
+                    Some(start) => car.pocket_crossing_state(start, now, map),
+                    None => car.crossing_state(Distance::ZERO, now, map),
+                };
                 scheduler.push(car.state.get_end_time(), Command::UpdateCar(car.vehicle.id));
                 self.events.push(Event::AgentEntersTraversable(
                     AgentID::Car(car.vehicle.id),
@@ -956,6 +970,16 @@ impl DrivingSimState {
         car.vehicle.owner
     }
 
+    pub fn find_gridlock(
+        &self,
+        now: Time,
+        threshold: Duration,
+        map: &Map,
+        intersections: &IntersectionSimState,
+    ) -> Vec<Gridlock> {
+        intersections.find_gridlock(now, threshold, &self.cars, &self.queues, map)
+    }
+
     // TODO Clean this up
     pub fn find_blockage_front(
         &self,
@@ -1021,4 +1045,9 @@ impl DrivingSimState {
     pub fn collect_events(&mut self) -> Vec<Event> {
         std::mem::replace(&mut self.events, Vec::new())
     }
+
+    // The first car whose state differs from another run
+    pub fn find_divergence(&self, other: &DrivingSimState) -> Option<(CarID, Vec<String>)> {
+        crate::divergence::first_diff(&self.cars, &other.cars)
+    }
 }
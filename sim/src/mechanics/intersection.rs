@@ -1,14 +1,17 @@
 use crate::mechanics::car::Car;
 use crate::mechanics::Queue;
-use crate::{AgentID, AlertLocation, CarID, Command, Event, Scheduler, Speed, TripMode};
+use crate::{
+    AgentID, AlertLocation, Blockage, CarID, Command, Event, Gridlock, GridlockLink,
+    GridlockResolution, Scheduler, Speed, TripMode,
+};
 use abstutil::{deserialize_btreemap, retain_btreeset, serialize_btreemap};
 use geom::{Duration, Time};
 use map_model::{
-    ControlStopSign, ControlTrafficSignal, IntersectionID, LaneID, Map, RoadID, Traversable,
-    TurnID, TurnPriority, TurnType,
+    ControlStopSign, ControlTrafficSignal, IntersectionID, LaneID, Map, RoadID, SignType,
+    Traversable, TurnID, TurnPriority, TurnType,
 };
 use serde::{Deserialize, Serialize};
-use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
 
 const WAIT_AT_STOP_SIGN: Duration = Duration::const_seconds(0.5);
 const WAIT_BEFORE_YIELD_AT_TRAFFIC_SIGNAL: Duration = Duration::const_seconds(0.2);
@@ -252,8 +255,14 @@ impl IntersectionSimState {
         }
 
         // Don't block the box
-        if let Some((car, _, queues)) = maybe_cars_and_queues {
+        if let Some((car, cars, queues)) = maybe_cars_and_queues {
             assert_eq!(agent, AgentID::Car(car.vehicle.id));
+            if let Some(c) = self.pocket_spillback(turn, map, cars, queues) {
+                if self.break_turn_conflict_cycles {
+                    self.blocked_by.insert((car.vehicle.id, c));
+                }
+                return false;
+            }
             let queue = queues.get_mut(&Traversable::Lane(turn.dst)).unwrap();
             if !queue.try_to_reserve_entry(
                 car,
@@ -307,6 +316,32 @@ impl IntersectionSimState {
         true
     }
 
+    // Somebody waiting to get into a full turn pocket has backed up into the lane it branches off
+    // from, so nobody else can get into that lane either. Returns who's in the way.
+    fn pocket_spillback(
+        &self,
+        turn: TurnID,
+        map: &Map,
+        cars: &BTreeMap<CarID, Car>,
+        queues: &BTreeMap<Traversable, Queue>,
+    ) -> Option<CarID> {
+        for req in self.state[&turn.parent].waiting.keys() {
+            if req.turn.dst == turn.dst {
+                continue;
+            }
+            if let AgentID::Car(id) = req.agent {
+                if let Some(car) = cars.get(&id) {
+                    if map.get_l(req.turn.dst).pocket_spills_into(map) == Some(turn.dst)
+                        && !queues[&Traversable::Lane(req.turn.dst)].room_for_car(car)
+                    {
+                        return Some(id);
+                    }
+                }
+            }
+        }
+        None
+    }
+
     pub fn debug(&self, id: IntersectionID, map: &Map) {
         println!("{}", abstutil::to_json(&self.state[&id]));
         if let Some(ref sign) = map.maybe_get_stop_sign(id) {
@@ -423,14 +458,26 @@ impl IntersectionSimState {
         assert!(our_priority != TurnPriority::Banned);
         let our_time = self.state[&req.turn.parent].waiting[req];
 
-        if our_priority == TurnPriority::Yield && now < our_time + WAIT_AT_STOP_SIGN {
-            // Since we have "ownership" of scheduling for req.agent, don't need to use
-            // scheduler.update.
-            scheduler.push(
-                our_time + WAIT_AT_STOP_SIGN,
-                Command::update_agent(req.agent),
-            );
-            return false;
+        if our_priority == TurnPriority::Yield {
+            if sign.get_sign_type(req.turn, map) == SignType::Yield {
+                // No need to stop at a yield sign, but vehicles with priority that're already at
+                // the intersection go first. When they finish their turn, we'll be woken up again.
+                let our_turn = map.get_t(req.turn);
+                if self.state[&req.turn.parent].waiting.keys().any(|r| {
+                    sign.get_priority(r.turn, map) == TurnPriority::Protected
+                        && map.get_t(r.turn).conflicts_with(our_turn)
+                }) {
+                    return false;
+                }
+            } else if now < our_time + WAIT_AT_STOP_SIGN {
+                // Since we have "ownership" of scheduling for req.agent, don't need to use
+                // scheduler.update.
+                scheduler.push(
+                    our_time + WAIT_AT_STOP_SIGN,
+                    Command::update_agent(req.agent),
+                );
+                return false;
+            }
         }
 
         // Once upon a time, we'd make sure that this request doesn't conflict with another in
@@ -574,6 +621,186 @@ impl IntersectionSimState {
         ok
     }
 
+    // Cycles of cars waiting on each other, where somebody has been waiting to start a turn for
+    // longer than the threshold. Relies on blocked_by, so break_turn_conflict_cycles must be on.
+    pub fn find_gridlock(
+        &self,
+        now: Time,
+        threshold: Duration,
+        cars: &BTreeMap<CarID, Car>,
+        queues: &BTreeMap<Traversable, Queue>,
+        map: &Map,
+    ) -> Vec<Gridlock> {
+        let mut results = Vec::new();
+        let mut seen = BTreeSet::new();
+        for state in self.state.values() {
+            for (req, since) in &state.waiting {
+                if now - *since < threshold {
+                    continue;
+                }
+                let car = match req.agent {
+                    AgentID::Car(c) if cars.contains_key(&c) => c,
+                    _ => {
+                        continue;
+                    }
+                };
+                if let Some(cycle) = self.find_waiting_cycle(car, cars, queues) {
+                    let mut members: Vec<CarID> = cycle.iter().map(|(c, _, _)| *c).collect();
+                    members.sort();
+                    if seen.insert(members) {
+                        results.push(self.describe_gridlock(now, cycle, cars, queues, map));
+                    }
+                }
+            }
+        }
+        results
+    }
+
+    // Who is this car directly waiting on, and why?
+    fn waiting_for(
+        &self,
+        car: CarID,
+        cars: &BTreeMap<CarID, Car>,
+        queues: &BTreeMap<Traversable, Queue>,
+    ) -> Vec<(CarID, Blockage)> {
+        let q = &queues[&cars[&car].router.head()];
+        let head = if let Some(c) = q.laggy_head {
+            c
+        } else {
+            *q.cars.get(0).unwrap()
+        };
+        if head != car {
+            return vec![(head, Blockage::Queued)];
+        }
+
+        let turn = match cars[&car].router.maybe_next() {
+            Some(Traversable::Turn(t)) => t,
+            _ => {
+                return Vec::new();
+            }
+        };
+        let mut results = Vec::new();
+        for (c1, c2) in &self.blocked_by {
+            if *c1 != car || !cars.contains_key(c2) {
+                continue;
+            }
+            let blocker = self.state[&turn.parent]
+                .accepted
+                .iter()
+                .find(|r| r.agent == AgentID::Car(*c2));
+            results.push((
+                *c2,
+                match blocker {
+                    Some(r) if r.turn.dst != turn.dst => Blockage::ConflictingTurn(turn.parent),
+                    _ => Blockage::NoRoom(turn.parent),
+                },
+            ));
+        }
+        results
+    }
+
+    // Breadth-first search for a path back to the start. Each step is (car, blocked by, why).
+    fn find_waiting_cycle(
+        &self,
+        start: CarID,
+        cars: &BTreeMap<CarID, Car>,
+        queues: &BTreeMap<Traversable, Queue>,
+    ) -> Option<Vec<(CarID, CarID, Blockage)>> {
+        let mut backrefs: BTreeMap<CarID, (CarID, Blockage)> = BTreeMap::new();
+        let mut queue = VecDeque::new();
+        queue.push_back(start);
+        while let Some(current) = queue.pop_front() {
+            for (next, why) in self.waiting_for(current, cars, queues) {
+                if next == start {
+                    let mut cycle = vec![(current, start, why)];
+                    let mut at = current;
+                    while at != start {
+                        let (prev, prev_why) = backrefs[&at];
+                        cycle.push((prev, at, prev_why));
+                        at = prev;
+                    }
+                    cycle.reverse();
+                    return Some(cycle);
+                }
+                if !backrefs.contains_key(&next) {
+                    backrefs.insert(next, (current, why));
+                    queue.push_back(next);
+                }
+            }
+        }
+        None
+    }
+
+    fn describe_gridlock(
+        &self,
+        now: Time,
+        cycle: Vec<(CarID, CarID, Blockage)>,
+        cars: &BTreeMap<CarID, Car>,
+        queues: &BTreeMap<Traversable, Queue>,
+        map: &Map,
+    ) -> Gridlock {
+        let mut links = Vec::new();
+        let mut resolutions = Vec::new();
+        for (car, blocked_by, blockage) in cycle {
+            let on = cars[&car].router.head();
+            let turn = match cars[&car].router.maybe_next() {
+                Some(Traversable::Turn(t)) => Some(t),
+                _ => None,
+            };
+            links.push(GridlockLink {
+                car,
+                on,
+                queue_len: queues[&on].cars.len(),
+                turn,
+                waiting_since: turn.and_then(|t| {
+                    self.state[&t.parent]
+                        .waiting
+                        .get(&Request {
+                            agent: AgentID::Car(car),
+                            turn: t,
+                        })
+                        .cloned()
+                }),
+                blocked_by,
+                blockage,
+            });
+
+            let fix = match blockage {
+                Blockage::Queued => None,
+                Blockage::ConflictingTurn(i) => Some(GridlockResolution::AllowConflictingTurns {
+                    intersection: i,
+                    osm_node_id: map.get_i(i).orig_id.osm_node_id,
+                }),
+                Blockage::NoRoom(i) => Some(GridlockResolution::AllowBlockTheBox {
+                    intersection: i,
+                    osm_node_id: map.get_i(i).orig_id.osm_node_id,
+                }),
+            };
+            if let Some(fix) = fix {
+                if !resolutions.contains(&fix) {
+                    resolutions.push(fix);
+                }
+            }
+        }
+
+        // Removing whoever's been waiting the longest is the least arbitrary choice. Buses don't
+        // have trips to abort, so skip them.
+        if let Some(link) = links
+            .iter()
+            .filter(|l| cars[&l.car].trip_and_person.is_some())
+            .min_by_key(|l| l.waiting_since.unwrap_or(now))
+        {
+            resolutions.insert(0, GridlockResolution::RemoveCar(link.car));
+        }
+
+        Gridlock {
+            time: now,
+            links,
+            resolutions,
+            applied: None,
+        }
+    }
+
     fn detect_conflict_cycle(
         &self,
         car: CarID,
@@ -10,3 +10,4 @@ pub use self::intersection::IntersectionSimState;
 pub use self::parking::ParkingSimState;
 pub use self::queue::Queue;
 pub use self::walking::WalkingSimState;
+pub(crate) use self::walking::{TIME_TO_FINISH_BIKING, TIME_TO_START_BIKING};
@@ -19,16 +19,24 @@ pub struct Queue {
     // length first. This is unused for turns themselves. This value can exceed geom_len (for the
     // edge case of ONE long car on a short queue).
     pub reserved_length: Distance,
+    // How much of geom_len can be reserved. Only less for turn pockets, where vehicles can't queue
+    // before the pocket begins.
+    pub capacity: Distance,
 }
 
 impl Queue {
     pub fn new(id: Traversable, map: &Map) -> Queue {
+        let geom_len = id.length(map);
         Queue {
             id,
             cars: VecDeque::new(),
             laggy_head: None,
-            geom_len: id.length(map),
+            geom_len,
             reserved_length: Distance::ZERO,
+            capacity: match id {
+                Traversable::Lane(l) => map.get_l(l).usable_length(),
+                Traversable::Turn(_) => geom_len,
+            },
         }
     }
 
@@ -205,7 +213,7 @@ impl Queue {
         // Reserve the normal amount of space; the next car trying to enter will get rejected.
         // Also allow this don't-block-the-box prevention to be disabled.
         let dist = car.vehicle.length + FOLLOWING_DISTANCE;
-        if self.reserved_length + dist < self.geom_len
+        if self.reserved_length + dist < self.capacity
             || self.reserved_length == Distance::ZERO
             || force_entry
         {
@@ -218,7 +226,7 @@ impl Queue {
     // TODO Refactor
     pub fn room_for_car(&self, car: &Car) -> bool {
         self.reserved_length == Distance::ZERO
-            || self.reserved_length + car.vehicle.length + FOLLOWING_DISTANCE < self.geom_len
+            || self.reserved_length + car.vehicle.length + FOLLOWING_DISTANCE < self.capacity
     }
 
     pub fn free_reserved_space(&mut self, car: &Car) {
@@ -12,8 +12,8 @@ use map_model::{
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 
-const TIME_TO_START_BIKING: Duration = Duration::const_seconds(30.0);
-const TIME_TO_FINISH_BIKING: Duration = Duration::const_seconds(45.0);
+pub(crate) const TIME_TO_START_BIKING: Duration = Duration::const_seconds(30.0);
+pub(crate) const TIME_TO_FINISH_BIKING: Duration = Duration::const_seconds(45.0);
 
 #[derive(Serialize, Deserialize, PartialEq, Clone)]
 pub struct WalkingSimState {
@@ -502,6 +502,11 @@ impl WalkingSimState {
     pub fn collect_events(&mut self) -> Vec<Event> {
         std::mem::replace(&mut self.events, Vec::new())
     }
+
+    // The first pedestrian whose state differs from another run
+    pub fn find_divergence(&self, other: &WalkingSimState) -> Option<(PedestrianID, Vec<String>)> {
+        crate::divergence::first_diff(&self.peds, &other.peds)
+    }
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Clone)]
@@ -0,0 +1,743 @@
+use crate::{
+    DrivingGoal, IndividTrip, OffMapLocation, OrigPersonID, Person, PersonID, Scenario,
+    SidewalkSpot, Sim, SimOptions, SpawnTrip, TripEndpoint, TripID, TripMode, VehicleSpec,
+    VehicleType,
+};
+use abstutil::{prettyprint_usize, Timer};
+use geom::{Distance, Duration, FindClosest, LonLat, Pt2D, Speed, Time};
+use instant::Instant;
+use map_model::{BuildingID, IntersectionID, Map, PathConstraints};
+use rand_xorshift::XorShiftRng;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+// Border intersections of adjacent maps closer than this are the same place
+const BORDER_MATCH_THRESHOLD: Distance = Distance::const_meters(50.0);
+// How far from an off-map destination to look for a building in the neighboring map
+const BUILDING_SEARCH_RADIUS: Distance = Distance::const_meters(500.0);
+
+// Simulates a city as several adjacent maps, usually clipped from the same huge map. Each map gets
+// its own Sim, and all of them advance together. Somebody leaving one map through a border is
+// handed off to the neighboring map at the matching border, instead of becoming a remote trip with
+// a guessed duration.
+pub struct RegionalSim {
+    pub regions: Vec<Region>,
+    pub handoffs: Vec<Handoff>,
+    // Somebody left one map, but there was no sensible way to continue their trip in the next. Also
+    // counts people expected to arrive from a neighbor who never will.
+    pub lost_handoffs: usize,
+
+    // People leaving a map are only noticed this often, so handoffs are delayed by up to this much
+    resolution: Duration,
+    rng: XorShiftRng,
+}
+
+pub struct Region {
+    pub map: Map,
+    pub sim: Sim,
+
+    // Where each outgoing border leads: the neighboring region and its incoming border
+    links: BTreeMap<IntersectionID, (usize, IntersectionID)>,
+    buildings: FindClosest<BuildingID>,
+    // How many of the sim's finished trips have been checked for handoffs
+    num_checked_trips: usize,
+    // People in this region's scenario whose trips into it start in a neighboring region, in the
+    // order they'll arrive. Keyed by (the neighboring region, the incoming border here, person).
+    arrivals: BTreeMap<ArrivalKey, VecDeque<Arrival>>,
+}
+
+type ArrivalKey = (usize, IntersectionID, OrigPersonID);
+
+// Somebody entering this region from a neighbor. Their trip in, and the rest of their schedule
+// here, waits until they're actually handed off.
+struct Arrival {
+    person: PersonID,
+    // The trip in, then everything after it until the next trip in. Each has the index of the
+    // person's vehicle to use.
+    trips: Vec<(IndividTrip, Option<usize>)>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Handoff {
+    // When the person reached the border of the first map
+    pub time: Time,
+    // How long after that they appeared in the neighboring map
+    pub delay: Duration,
+    pub mode: TripMode,
+    // (region, trip)
+    pub from: (usize, TripID),
+    pub to: (usize, TripID),
+}
+
+// Everything needed to combine the regions' results at the end. Trips staying inside one map are
+// only in that map's own Analytics.
+#[derive(Serialize, Deserialize)]
+pub struct RegionalResults {
+    pub regions: Vec<String>,
+    pub handoffs: Vec<Handoff>,
+    pub lost_handoffs: usize,
+    pub journeys: Vec<Journey>,
+}
+
+// One trip stitched together across several maps
+#[derive(Serialize, Deserialize)]
+pub struct Journey {
+    pub mode: TripMode,
+    // (region, trip) for each map passed through
+    pub legs: Vec<(usize, TripID)>,
+    pub departure: Time,
+    // None if the last leg isn't finished
+    pub duration: Option<Duration>,
+}
+
+struct PendingHandoff {
+    time: Time,
+    from: (usize, TripID),
+    to: usize,
+    border: IntersectionID,
+    destination: Option<OffMapLocation>,
+    mode: TripMode,
+    orig_person: Option<OrigPersonID>,
+    ped_speed: Speed,
+    vehicle: Option<VehicleSpec>,
+    // The trip in the first map was aborted, so the person never really reached the border
+    aborted: bool,
+}
+
+impl RegionalSim {
+    pub fn new(
+        maps_and_scenarios: Vec<(Map, Scenario)>,
+        opts: SimOptions,
+        mut rng: XorShiftRng,
+        timer: &mut Timer,
+    ) -> RegionalSim {
+        assert!(
+            !maps_and_scenarios.is_empty(),
+            "RegionalSim needs at least one map"
+        );
+        let mut regions = Vec::new();
+        let mut scenarios = Vec::new();
+        for (map, scenario) in maps_and_scenarios {
+            let sim = Sim::new(&map, opts.clone(), timer);
+            let mut buildings = FindClosest::new(map.get_bounds());
+            for b in map.all_buildings() {
+                buildings.add(b.id, &vec![b.polygon.center()]);
+            }
+            regions.push(Region {
+                map,
+                sim,
+                links: BTreeMap::new(),
+                buildings,
+                num_checked_trips: 0,
+                arrivals: BTreeMap::new(),
+            });
+            scenarios.push(scenario);
+        }
+        link_borders(&mut regions, timer);
+
+        for (idx, mut scenario) in scenarios.into_iter().enumerate() {
+            let (cancelled, arrivals) = cancel_incoming_trips(idx, &mut scenario, &regions, &rng);
+            timer.note(format!(
+                "{}: {} trips entering from other regions will be handed off instead",
+                regions[idx].map.get_name(),
+                prettyprint_usize(cancelled)
+            ));
+            let region = &mut regions[idx];
+            region.arrivals = arrivals;
+            scenario.instantiate(&mut region.sim, &region.map, &mut rng, timer);
+        }
+
+        RegionalSim {
+            regions,
+            handoffs: Vec::new(),
+            lost_handoffs: 0,
+            resolution: Duration::seconds(1.0),
+            rng,
+        }
+    }
+
+    pub fn time(&self) -> Time {
+        self.regions[0].sim.time()
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.regions.iter().all(|r| r.sim.is_done())
+    }
+
+    // Advance every region to the same time, handing people between maps along the way
+    pub fn timed_step(&mut self, dt: Duration) {
+        let end_time = self.time() + dt;
+        while self.time() < end_time {
+            let next = (self.time() + self.resolution).min(end_time);
+            for r in &mut self.regions {
+                let dt = next - r.sim.time();
+                r.sim
+                    .timed_step(&r.map, dt, &mut None, &mut Timer::throwaway());
+            }
+            self.handoff();
+        }
+    }
+
+    pub fn run_until_done(&mut self) {
+        let mut last_print = Instant::now();
+        while !self.is_done() {
+            self.timed_step(Duration::seconds(30.0));
+
+            if Duration::realtime_elapsed(last_print) >= Duration::seconds(1.0) {
+                println!(
+                    "{}: {} handoffs between {} maps",
+                    self.time(),
+                    prettyprint_usize(self.handoffs.len()),
+                    self.regions.len()
+                );
+                last_print = Instant::now();
+            }
+        }
+    }
+
+    fn handoff(&mut self) {
+        let mut pending = Vec::new();
+        for (idx, r) in self.regions.iter_mut().enumerate() {
+            let finished = &r.sim.get_analytics().finished_trips;
+            for (time, trip, mode, _) in &finished[r.num_checked_trips..] {
+                // Aborted trips warp to their destination, but there's nothing to continue in the
+                // next map. Still notice them, so the rest of the person's schedule there happens.
+                let aborted = mode.is_none();
+                if let (_, _, TripEndpoint::Border(i, destination), mode) = r.sim.trip_info(*trip) {
+                    if let Some((to, border)) = r.links.get(&i) {
+                        let person = r.sim.get_person(r.sim.trip_to_person(*trip));
+                        pending.push(PendingHandoff {
+                            time: *time,
+                            from: (idx, *trip),
+                            to: *to,
+                            border: *border,
+                            destination,
+                            mode,
+                            orig_person: person.orig_id,
+                            ped_speed: person.ped_speed,
+                            vehicle: vehicle_for_mode(person, mode),
+                            aborted,
+                        });
+                    }
+                }
+            }
+            r.num_checked_trips = finished.len();
+        }
+
+        for p in pending {
+            if !self.spawn(p) {
+                self.lost_handoffs += 1;
+            }
+        }
+
+        // Once every map is done, nobody still expected from a neighbor will ever show up
+        if self.is_done() {
+            for r in &mut self.regions {
+                self.lost_handoffs += r.arrivals.values().map(|queue| queue.len()).sum::<usize>();
+                r.arrivals.clear();
+            }
+        }
+    }
+
+    fn spawn(&mut self, p: PendingHandoff) -> bool {
+        let region = &mut self.regions[p.to];
+        if let Some(arrival) = p
+            .orig_person
+            .and_then(|id| region.arrivals.get_mut(&(p.from.0, p.border, id)))
+            .and_then(|queue| queue.pop_front())
+        {
+            return self.arrive(p, arrival);
+        }
+        if p.aborted {
+            return false;
+        }
+
+        // Nobody in this region's scenario matches, so make up somebody new just for this trip
+        let trip = match region
+            .destination(p.destination)
+            .and_then(|goal| handoff_trip(p.border, goal, p.mode, &region.map))
+        {
+            Some(trip) => trip,
+            None => {
+                return false;
+            }
+        };
+        let map = &region.map;
+        let start = trip.start(map);
+        let vehicle_specs = match (p.vehicle, p.mode) {
+            (Some(v), _) => vec![v],
+            (None, TripMode::Drive) => vec![Scenario::rand_car(&mut self.rng)],
+            (None, TripMode::Bike) => vec![Scenario::rand_bike(&mut self.rng)],
+            (None, TripMode::Walk) | (None, TripMode::Transit) => Vec::new(),
+        };
+
+        let now = region.sim.time();
+        let mut spawner = region.sim.make_spawner();
+        let person = region.sim.random_person(p.ped_speed, vehicle_specs);
+        let use_vehicle = person.vehicles.get(0).map(|v| v.id);
+        let person_id = person.id;
+        spawner.schedule_trip(
+            person,
+            now,
+            trip.to_trip_spec(use_vehicle, &mut self.rng, map),
+            start,
+            false,
+            map,
+        );
+        region
+            .sim
+            .flush_spawner(spawner, map, &mut Timer::throwaway());
+
+        self.handoffs.push(Handoff {
+            time: p.time,
+            delay: now - p.time,
+            mode: p.mode,
+            from: p.from,
+            to: (
+                p.to,
+                *region.sim.get_person(person_id).trips.last().unwrap(),
+            ),
+        });
+        true
+    }
+
+    // The same person continues their trip in the next region, then follows the rest of their
+    // schedule there.
+    fn arrive(&mut self, p: PendingHandoff, arrival: Arrival) -> bool {
+        let region = &mut self.regions[p.to];
+        let map = &region.map;
+        let now = region.sim.time();
+        let mut trips = arrival.trips.into_iter();
+        let (trip_in, vehicle_idx) = trips.next().unwrap();
+
+        let mut spawner = region.sim.make_spawner();
+        let person = region.sim.get_person(arrival.person);
+        let num_trips = person.trips.len();
+        let handed_off = match handoff_trip(p.border, trip_in.trip.end(map), p.mode, map) {
+            Some(_) if p.aborted => false,
+            Some(trip) => {
+                let use_vehicle = vehicle_idx
+                    .filter(|idx| Some(person.vehicles[*idx].vehicle_type) == p.vehicle_type())
+                    .or_else(|| {
+                        person
+                            .vehicles
+                            .iter()
+                            .position(|v| Some(v.vehicle_type) == p.vehicle_type())
+                    })
+                    .map(|idx| person.vehicles[idx].id);
+                if use_vehicle.is_some() == p.vehicle_type().is_some() {
+                    let start = trip.start(map);
+                    spawner.schedule_trip(
+                        person,
+                        now,
+                        trip.to_trip_spec(use_vehicle, &mut self.rng, map),
+                        start,
+                        false,
+                        map,
+                    );
+                    true
+                } else {
+                    false
+                }
+            }
+            None => false,
+        };
+        // Even if the trip in couldn't continue, the rest of their schedule here still happens.
+        // When the trip in continues, trips that should've already started wait for it to finish.
+        for (t, idx) in trips {
+            let depart = if t.depart > now {
+                t.depart
+            } else {
+                now + Duration::seconds(1.0)
+            };
+            spawner.schedule_trip(
+                person,
+                depart,
+                t.trip.clone().to_trip_spec(
+                    idx.map(|idx| person.vehicles[idx].id),
+                    &mut self.rng,
+                    map,
+                ),
+                t.trip.start(map),
+                false,
+                map,
+            );
+        }
+        region
+            .sim
+            .flush_spawner(spawner, map, &mut Timer::throwaway());
+
+        if handed_off {
+            self.handoffs.push(Handoff {
+                time: p.time,
+                delay: now - p.time,
+                mode: p.mode,
+                from: p.from,
+                to: (p.to, region.sim.get_person(arrival.person).trips[num_trips]),
+            });
+        }
+        handed_off
+    }
+
+    pub fn results(&self) -> RegionalResults {
+        let mut next_leg: BTreeMap<(usize, TripID), (usize, TripID)> = BTreeMap::new();
+        let mut continued: BTreeSet<(usize, TripID)> = BTreeSet::new();
+        for h in &self.handoffs {
+            next_leg.insert(h.from, h.to);
+            continued.insert(h.to);
+        }
+
+        let mut journeys = Vec::new();
+        for h in &self.handoffs {
+            // Only start from the first leg of each journey
+            if continued.contains(&h.from) {
+                continue;
+            }
+            let mut legs = vec![h.from];
+            while let Some(leg) = next_leg.get(legs.last().unwrap()) {
+                legs.push(*leg);
+            }
+
+            let (first_region, first_trip) = legs[0];
+            let (last_region, last_trip) = *legs.last().unwrap();
+            let departure = self.regions[first_region].sim.trip_info(first_trip).0;
+            let last_sim = &self.regions[last_region].sim;
+            let duration = last_sim
+                .finished_trip_time(last_trip)
+                .map(|(dt, _)| last_sim.trip_info(last_trip).0 + dt - departure);
+            journeys.push(Journey {
+                mode: h.mode,
+                legs,
+                departure,
+                duration,
+            });
+        }
+
+        RegionalResults {
+            regions: self
+                .regions
+                .iter()
+                .map(|r| r.map.get_name().to_string())
+                .collect(),
+            handoffs: self.handoffs.clone(),
+            lost_handoffs: self.lost_handoffs,
+            journeys,
+        }
+    }
+
+    // Returns the path
+    pub fn save_results(&self) -> String {
+        let mut names: Vec<&str> = self.regions.iter().map(|r| r.map.get_name()).collect();
+        names.sort();
+        let path =
+            abstutil::path_regional_results(self.regions[0].map.get_city_name(), &names.join("_"));
+        abstutil::write_json(path.clone(), &self.results());
+        path
+    }
+}
+
+impl Region {
+    fn contains(&self, gps: LonLat) -> bool {
+        let bounds = self.map.get_gps_bounds();
+        bounds.contains(gps)
+            && self
+                .map
+                .get_boundary_polygon()
+                .contains_pt(Pt2D::forcibly_from_gps(gps, bounds))
+    }
+
+    // Where should somebody heading to this off-map location go in this region?
+    fn destination(&self, destination: Option<OffMapLocation>) -> Option<TripEndpoint> {
+        let loc = destination?;
+        let bounds = self.map.get_gps_bounds();
+        if self.contains(loc.gps) {
+            let (b, _) = self.buildings.closest_pt(
+                Pt2D::forcibly_from_gps(loc.gps, bounds),
+                BUILDING_SEARCH_RADIUS,
+            )?;
+            return Some(TripEndpoint::Bldg(b));
+        }
+        // Just passing through, so leave towards the destination
+        let i = self
+            .map
+            .all_outgoing_borders()
+            .into_iter()
+            .min_by_key(|i| {
+                i.polygon
+                    .center()
+                    .forcibly_to_gps(bounds)
+                    .fast_dist(loc.gps)
+            })?;
+        Some(TripEndpoint::Border(i.id, Some(loc)))
+    }
+}
+
+// Match every outgoing border to the closest incoming border of another region
+fn link_borders(regions: &mut [Region], timer: &mut Timer) {
+    let mut incoming: Vec<(usize, IntersectionID, LonLat)> = Vec::new();
+    for (idx, r) in regions.iter().enumerate() {
+        for i in r.map.all_incoming_borders() {
+            incoming.push((
+                idx,
+                i.id,
+                i.polygon.center().forcibly_to_gps(r.map.get_gps_bounds()),
+            ));
+        }
+    }
+
+    for idx in 0..regions.len() {
+        let mut links = BTreeMap::new();
+        let map = &regions[idx].map;
+        for i in map.all_outgoing_borders() {
+            let pt = i.polygon.center().forcibly_to_gps(map.get_gps_bounds());
+            if let Some(link) = closest_incoming(idx, pt, &incoming) {
+                links.insert(i.id, link);
+            }
+        }
+        timer.note(format!(
+            "{}: {} of {} outgoing borders lead to another region",
+            map.get_name(),
+            links.len(),
+            map.all_outgoing_borders().len()
+        ));
+        regions[idx].links = links;
+    }
+}
+
+// The closest incoming border of another region, if it's close enough to be the same place
+fn closest_incoming(
+    idx: usize,
+    pt: LonLat,
+    incoming: &[(usize, IntersectionID, LonLat)],
+) -> Option<(usize, IntersectionID)> {
+    incoming
+        .iter()
+        .filter(|(to, _, _)| *to != idx)
+        .map(|(to, j, other_pt)| (*to, *j, pt.gps_dist_meters(*other_pt)))
+        .filter(|(_, _, dist)| *dist <= BORDER_MATCH_THRESHOLD)
+        .min_by(|a, b| a.2.partial_cmp(&b.2).unwrap())
+        .map(|(to, j, _)| (to, j))
+}
+
+// Trips entering this region from a neighbor are simulated there first, then handed off here.
+// Cancel the copy in this region's scenario, along with the rest of that person's schedule until
+// they arrive. People from scenarios without original IDs can't be matched up between regions, so
+// only their trip in is cancelled. Returns the number of trips in and everybody who'll arrive.
+fn cancel_incoming_trips(
+    idx: usize,
+    scenario: &mut Scenario,
+    regions: &[Region],
+    rng: &XorShiftRng,
+) -> (usize, BTreeMap<ArrivalKey, VecDeque<Arrival>>) {
+    let map = &regions[idx].map;
+    // Each incoming border here, and the neighbors leading to it
+    let mut sources: BTreeMap<IntersectionID, Vec<usize>> = BTreeMap::new();
+    for (other, r) in regions.iter().enumerate() {
+        for (to, i) in r.links.values() {
+            if *to == idx {
+                sources.entry(*i).or_insert_with(Vec::new).push(other);
+            }
+        }
+    }
+
+    let mut cancelled = 0;
+    let mut arrivals: BTreeMap<ArrivalKey, VecDeque<Arrival>> = BTreeMap::new();
+    for person in &mut scenario.people {
+        // Vehicle indices don't depend on the RNG, so don't disturb the real one
+        let (_, _, vehicle_foreach_trip) = person.get_vehicles(&mut rng.clone());
+        let mut current: Option<(ArrivalKey, Arrival)> = None;
+        for (trip, vehicle_idx) in person.trips.iter_mut().zip(vehicle_foreach_trip) {
+            let incoming = match trip.trip {
+                SpawnTrip::Remote { .. } => None,
+                _ => match trip.trip.start(map) {
+                    TripEndpoint::Border(i, Some(origin)) => {
+                        let containing: Vec<usize> = regions
+                            .iter()
+                            .enumerate()
+                            .filter(|(_, r)| r.contains(origin.gps))
+                            .map(|(other, _)| other)
+                            .collect();
+                        incoming_from(idx, i, &containing, &sources)
+                    }
+                    _ => None,
+                },
+            };
+            if let Some((from, i)) = incoming {
+                cancelled += 1;
+                if let Some(id) = person.orig_id {
+                    if let Some((key, arrival)) = current.take() {
+                        arrivals
+                            .entry(key)
+                            .or_insert_with(VecDeque::new)
+                            .push_back(arrival);
+                    }
+                    current = Some((
+                        (from, i, id),
+                        Arrival {
+                            person: person.id,
+                            trips: vec![(trip.clone(), vehicle_idx)],
+                        },
+                    ));
+                }
+                trip.cancelled = true;
+            } else if let Some((_, ref mut arrival)) = current {
+                if !trip.cancelled {
+                    arrival.trips.push((trip.clone(), vehicle_idx));
+                    trip.cancelled = true;
+                }
+            }
+        }
+        if let Some((key, arrival)) = current {
+            arrivals
+                .entry(key)
+                .or_insert_with(VecDeque::new)
+                .push_back(arrival);
+        }
+    }
+    (cancelled, arrivals)
+}
+
+// Does a trip starting at this border in region idx really come from a neighboring region? If so,
+// returns that neighbor and the border. containing lists the regions with the trip's origin in
+// them; the neighbor containing it is preferred.
+fn incoming_from(
+    idx: usize,
+    i: IntersectionID,
+    containing: &[usize],
+    sources: &BTreeMap<IntersectionID, Vec<usize>>,
+) -> Option<(usize, IntersectionID)> {
+    let neighbors = sources.get(&i)?;
+    if !containing.iter().any(|other| *other != idx) {
+        return None;
+    }
+    let from = neighbors
+        .iter()
+        .find(|other| containing.contains(other))
+        .unwrap_or(&neighbors[0]);
+    Some((*from, i))
+}
+
+impl PendingHandoff {
+    fn vehicle_type(&self) -> Option<VehicleType> {
+        match self.mode {
+            TripMode::Drive => Some(VehicleType::Car),
+            TripMode::Bike => Some(VehicleType::Bike),
+            TripMode::Walk | TripMode::Transit => None,
+        }
+    }
+}
+
+// Continue a trip starting from a border
+fn handoff_trip(
+    i: IntersectionID,
+    goal: TripEndpoint,
+    mode: TripMode,
+    map: &Map,
+) -> Option<SpawnTrip> {
+    match mode {
+        TripMode::Drive | TripMode::Bike => {
+            let constraints = if mode == TripMode::Bike {
+                PathConstraints::Bike
+            } else {
+                PathConstraints::Car
+            };
+            let goal = match goal {
+                TripEndpoint::Bldg(b) => DrivingGoal::ParkNear(b),
+                TripEndpoint::Border(i, destination) => DrivingGoal::end_at_border(
+                    map.get_i(i).some_incoming_road(map)?,
+                    constraints,
+                    destination,
+                    map,
+                )?,
+            };
+            Some(SpawnTrip::FromBorder {
+                dr: map.get_i(i).some_outgoing_road(map)?,
+                goal,
+                is_bike: mode == TripMode::Bike,
+                origin: None,
+            })
+        }
+        TripMode::Walk | TripMode::Transit => {
+            let start = SidewalkSpot::start_at_border(i, None, map)?;
+            let goal = match goal {
+                TripEndpoint::Bldg(b) => SidewalkSpot::building(b, map),
+                TripEndpoint::Border(i, destination) => {
+                    SidewalkSpot::end_at_border(i, destination, map)?
+                }
+            };
+            if mode == TripMode::Transit {
+                if let Some((stop1, stop2, route)) =
+                    map.should_use_transit(start.sidewalk_pos, goal.sidewalk_pos)
+                {
+                    return Some(SpawnTrip::UsingTransit(start, goal, route, stop1, stop2));
+                }
+            }
+            Some(SpawnTrip::JustWalking(start, goal))
+        }
+    }
+}
+
+// Keep using the same kind of vehicle in the next map
+fn vehicle_for_mode(person: &Person, mode: TripMode) -> Option<VehicleSpec> {
+    let vehicle_type = match mode {
+        TripMode::Drive => VehicleType::Car,
+        TripMode::Bike => VehicleType::Bike,
+        TripMode::Walk | TripMode::Transit => {
+            return None;
+        }
+    };
+    person
+        .vehicles
+        .iter()
+        .find(|v| v.vehicle_type == vehicle_type)
+        .map(|v| VehicleSpec {
+            vehicle_type,
+            length: v.length,
+            max_speed: v.max_speed,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_closest_incoming() {
+        let pt = LonLat::new(-122.3, 47.6);
+        // Borders of two neighbors about 22m and 11m north, and one of region 0 right here
+        let incoming = vec![
+            (0, IntersectionID(0), pt),
+            (1, IntersectionID(1), LonLat::new(-122.3, 47.6002)),
+            (2, IntersectionID(2), LonLat::new(-122.3, 47.6001)),
+        ];
+        assert_eq!(
+            closest_incoming(0, pt, &incoming),
+            Some((2, IntersectionID(2)))
+        );
+        assert_eq!(
+            closest_incoming(2, pt, &incoming),
+            Some((0, IntersectionID(0)))
+        );
+
+        // About 110m away is a different place
+        let far = vec![(1, IntersectionID(1), LonLat::new(-122.3, 47.601))];
+        assert_eq!(closest_incoming(0, pt, &far), None);
+    }
+
+    #[test]
+    fn test_incoming_from() {
+        let i = IntersectionID(5);
+        let mut sources = BTreeMap::new();
+        sources.insert(i, vec![1, 2]);
+
+        // Only trips from one of the neighbors leading here
+        assert_eq!(incoming_from(0, i, &[2], &sources), Some((2, i)));
+        // Starting somewhere else off-map, but still in another region
+        assert_eq!(incoming_from(0, i, &[3], &sources), Some((1, i)));
+        // The origin isn't in any other region
+        assert_eq!(incoming_from(0, i, &[0], &sources), None);
+        assert_eq!(incoming_from(0, i, &[], &sources), None);
+        // Nothing leads to this border
+        assert_eq!(incoming_from(0, IntersectionID(6), &[1], &sources), None);
+    }
+}
@@ -356,8 +356,21 @@ impl Router {
                         src: *l,
                         dst: next_lane,
                     };
-                    if map.maybe_get_t(turn2).is_some() {
-                        Some((queues[&Traversable::Lane(*l)].cars.len(), turn1, *l, turn2))
+                    if let Some(t) = map.maybe_get_t(turn2) {
+                        // Stay in lanes marked for the next turn, even if they're busier
+                        let unmarked = map.get_l(*l).turn_violates_markings(t, map);
+                        // Short turn pockets fill up quickly, so compare how much room is left,
+                        // not just the number of cars. Don't squeeze into a full lane.
+                        let queue = &queues[&Traversable::Lane(*l)];
+                        if *l != orig_target_lane && queue.reserved_length >= queue.capacity {
+                            return None;
+                        }
+                        let fullness = if queue.capacity == Distance::ZERO {
+                            100
+                        } else {
+                            (100.0 * (queue.reserved_length / queue.capacity)).round() as usize
+                        };
+                        Some(((unmarked, fullness), turn1, *l, turn2))
                     } else {
                         None
                     }
@@ -365,7 +378,7 @@ impl Router {
                     None
                 }
             })
-            .min_by_key(|(len, _, _, _)| *len)
+            .min_by_key(|(key, _, _, _)| *key)
             .unwrap();
         // TODO Only switch if the target queue is some amount better; don't oscillate
         // unnecessarily.
@@ -23,6 +23,7 @@ pub enum Command {
     Callback(Duration),
     Pandemic(pandemic::Cmd),
     FinishRemoteTrip(TripID),
+    CheckForGridlock,
 }
 
 impl Command {
@@ -46,6 +47,7 @@ impl Command {
             Command::Callback(_) => CommandType::Callback,
             Command::Pandemic(ref p) => CommandType::Pandemic(p.clone()),
             Command::FinishRemoteTrip(t) => CommandType::FinishRemoteTrip(*t),
+            Command::CheckForGridlock => CommandType::CheckForGridlock,
         }
     }
 }
@@ -62,6 +64,7 @@ pub enum CommandType {
     Callback,
     Pandemic(pandemic::Cmd),
     FinishRemoteTrip(TripID),
+    CheckForGridlock,
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Eq, Clone)]
@@ -224,15 +227,16 @@ impl Scheduler {
     // serialize paths inside Router for live agents. We need to defer calling make_router and just
     // store the input in CreateCar.
     // TODO Rethink all of this; probably broken by StartTrip.
-    pub fn get_requests_for_savestate(&self) -> Vec<PathRequest> {
+    // With the time each path is needed, since that affects tolls
+    pub fn get_requests_for_savestate(&self) -> Vec<(PathRequest, Time)> {
         let mut reqs = Vec::new();
-        for (cmd, _) in self.queued_commands.values() {
+        for (cmd, time) in self.queued_commands.values() {
             match cmd {
                 Command::SpawnCar(ref create_car, _) => {
-                    reqs.push(create_car.req.clone());
+                    reqs.push((create_car.req.clone(), *time));
                 }
                 Command::SpawnPed(ref create_ped) => {
-                    reqs.push(create_ped.req.clone());
+                    reqs.push((create_ped.req.clone(), *time));
                 }
                 _ => {}
             }
@@ -1,10 +1,15 @@
+use crate::analytics::AnalyticsCheckpoint;
+use crate::counterfactual::trip_counterfactuals;
+use crate::divergence::diff_json;
 use crate::{
-    AgentID, AlertLocation, Analytics, CarID, Command, CreateCar, DrawCarInput, DrawPedCrowdInput,
-    DrawPedestrianInput, DrivingSimState, Event, GetDrawAgents, IntersectionSimState, OrigPersonID,
-    PandemicModel, ParkedCar, ParkingSimState, ParkingSpot, PedestrianID, Person, PersonID,
-    PersonState, Router, Scheduler, SidewalkPOI, SidewalkSpot, TransitSimState, TripEndpoint,
-    TripID, TripManager, TripMode, TripPhaseType, TripResult, TripSpawner, UnzoomedAgent, Vehicle,
-    VehicleSpec, VehicleType, WalkingSimState, BUS_LENGTH, MIN_CAR_LENGTH,
+    AgentID, AlertLocation, Analytics, CarID, Command, CreateCar, Divergence, DrawCarInput,
+    DrawPedCrowdInput, DrawPedestrianInput, DrivingSimState, Event, GetDrawAgents, Gridlock,
+    GridlockDetector, GridlockResolution, IntersectionSimState, OrigPersonID, PandemicModel,
+    ParkedCar, ParkingSimState, ParkingSpot, PedestrianID, Person, PersonID, PersonState, Router,
+    Scheduler, SidewalkPOI, SidewalkSpot, Trajectories, TransitSimState, TripCounterfactuals,
+    TripEndpoint, TripID, TripManager, TripMode, TripPhaseType, TripResult, TripSpawner,
+    UnzoomedAgent, Vehicle, VehicleSpec, VehicleType, WalkingSimState, BUS_LENGTH,
+    GRIDLOCK_CHECK_FREQUENCY, MIN_CAR_LENGTH,
 };
 use abstutil::Timer;
 use derivative::Derivative;
@@ -16,7 +21,7 @@ use map_model::{
 };
 use rand_xorshift::XorShiftRng;
 use serde::{Deserialize, Serialize};
-use std::collections::{BTreeMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::panic;
 
 // TODO Do something else.
@@ -56,6 +61,33 @@ pub struct Sim {
     #[derivative(PartialEq = "ignore")]
     #[serde(skip_serializing, skip_deserializing)]
     alerts: AlertHandler,
+
+    // Only recorded if requested by SimOptions; very large.
+    #[derivative(PartialEq = "ignore")]
+    #[serde(skip_serializing, skip_deserializing)]
+    trajectories: Option<Trajectories>,
+
+    // Copies of the sim from earlier times, kept in memory for rewinding. Only taken if
+    // SimOptions asks for them.
+    #[derivative(PartialEq = "ignore")]
+    #[serde(skip_serializing, skip_deserializing)]
+    checkpoint_interval: Option<Duration>,
+    #[derivative(PartialEq = "ignore")]
+    #[serde(skip_serializing, skip_deserializing)]
+    checkpoints: Vec<Checkpoint>,
+
+    // Only set up by SimOptions, like the above. Savestates don't keep it, so gridlock detection
+    // stops after loading one.
+    #[derivative(PartialEq = "ignore")]
+    #[serde(skip_serializing, skip_deserializing)]
+    gridlock: Option<GridlockDetector>,
+}
+
+#[derive(Clone)]
+struct Checkpoint {
+    // Without analytics, trajectories, or other checkpoints
+    sim: Box<Sim>,
+    analytics: AnalyticsCheckpoint,
 }
 
 #[derive(Clone)]
@@ -68,6 +100,17 @@ pub struct SimOptions {
     pub enable_pandemic_model: Option<XorShiftRng>,
     pub alerts: AlertHandler,
     pub pathfinding_upfront: bool,
+    // Sample every agent's position at this interval, for replaying later
+    pub record_trajectories: Option<Duration>,
+    // Keep a copy of the sim at this interval, so it can be rewound to any earlier time
+    pub checkpoint_interval: Option<Duration>,
+    // Report cycles of cars that've been stuck waiting on each other for this long. Finding who's
+    // waiting on whom needs break_turn_conflict_cycles.
+    pub detect_gridlock: Option<Duration>,
+    // When gridlock is detected, delete one of the stuck cars. Allowing block-the-box or
+    // conflicting turns is only suggested in the report, since that changes the map's behavior
+    // for everyone afterwards.
+    pub resolve_gridlock: bool,
 }
 
 #[derive(Clone)]
@@ -97,6 +140,10 @@ impl SimOptions {
             enable_pandemic_model: None,
             alerts: AlertHandler::Print,
             pathfinding_upfront: false,
+            record_trajectories: None,
+            checkpoint_interval: None,
+            detect_gridlock: None,
+            resolve_gridlock: false,
         }
     }
 }
@@ -104,7 +151,24 @@ impl SimOptions {
 // Setup
 impl Sim {
     pub fn new(map: &Map, opts: SimOptions, timer: &mut Timer) -> Sim {
+        if let Some(dt) = opts.checkpoint_interval {
+            assert!(dt > Duration::ZERO, "checkpoint_interval must be positive");
+        }
+        assert!(
+            opts.detect_gridlock.is_none() || opts.break_turn_conflict_cycles,
+            "Detecting gridlock needs break_turn_conflict_cycles; don't combine \
+             --detect_gridlock with --disable_break_turn_conflict_cycles"
+        );
         let mut scheduler = Scheduler::new();
+        let gridlock = if let Some(threshold) = opts.detect_gridlock {
+            scheduler.push(
+                Time::START_OF_DAY + GRIDLOCK_CHECK_FREQUENCY,
+                Command::CheckForGridlock,
+            );
+            Some(GridlockDetector::new(threshold, opts.resolve_gridlock))
+        } else {
+            None
+        };
         Sim {
             driving: DrivingSimState::new(map, opts.recalc_lanechanging),
             parking: ParkingSimState::new(map, timer),
@@ -129,11 +193,17 @@ impl Sim {
             map_name: map.get_name().to_string(),
             // TODO
             edits_name: "untitled edits".to_string(),
+            trajectories: opts
+                .record_trajectories
+                .map(|interval| Trajectories::new(map, opts.run_name.clone(), interval)),
             run_name: opts.run_name,
             step_count: 0,
             alerts: opts.alerts,
 
             analytics: Analytics::new(),
+            checkpoint_interval: opts.checkpoint_interval,
+            checkpoints: Vec::new(),
+            gridlock,
         }
     }
 
@@ -594,6 +664,16 @@ impl Sim {
                     &mut self.scheduler,
                 );
             }
+            Command::CheckForGridlock => {
+                // Still scheduled after loading a savestate, but the detector is gone
+                if self.gridlock.is_some() {
+                    self.scheduler.push(
+                        self.time + GRIDLOCK_CHECK_FREQUENCY,
+                        Command::CheckForGridlock,
+                    );
+                    self.check_for_gridlock(map, &mut events);
+                }
+            }
         }
 
         // Record events at precisely the time they occur.
@@ -602,6 +682,31 @@ impl Sim {
         halt
     }
 
+    fn check_for_gridlock(&mut self, map: &Map, events: &mut Vec<Event>) {
+        let mut detector = self.gridlock.take().unwrap();
+        let mut removed = BTreeSet::new();
+        for mut gridlock in
+            self.driving
+                .find_gridlock(self.time, detector.threshold, map, &self.intersections)
+        {
+            if !detector.is_new(&gridlock) {
+                continue;
+            }
+            // Removing a car for an earlier cycle might've already broken this one
+            if detector.resolve && gridlock.cars().iter().all(|c| !removed.contains(c)) {
+                if let Some(GridlockResolution::RemoveCar(car)) = gridlock.resolutions.get(0) {
+                    let car = *car;
+                    self.kill_stuck_car(car, map);
+                    removed.insert(car);
+                    gridlock.applied = Some(GridlockResolution::RemoveCar(car));
+                }
+            }
+            events.push(Event::Alert(gridlock.location(), gridlock.describe()));
+            detector.reports.push(gridlock);
+        }
+        self.gridlock = Some(detector);
+    }
+
     fn dispatch_events(&mut self, mut events: Vec<Event>, map: &Map) {
         events.extend(self.trips.collect_events());
         events.extend(self.transit.collect_events());
@@ -631,7 +736,8 @@ impl Sim {
 
         timer.start(format!("Advance sim to {}", end_time));
         while self.time < end_time {
-            if self.minimal_step(map, end_time - self.time, maybe_cb) {
+            let max_dt = self.periodic_work(map, end_time);
+            if self.minimal_step(map, max_dt, maybe_cb) {
                 break;
             }
             if !self.analytics.alerts.is_empty() {
@@ -684,7 +790,8 @@ impl Sim {
         let end_time = self.time + dt;
 
         while self.time < end_time && Duration::realtime_elapsed(started_at) < real_time_limit {
-            if self.minimal_step(map, end_time - self.time, maybe_cb) {
+            let max_dt = self.periodic_work(map, end_time);
+            if self.minimal_step(map, max_dt, maybe_cb) {
                 break;
             }
             if !self.analytics.alerts.is_empty() {
@@ -708,6 +815,41 @@ impl Sim {
         }
     }
 
+    // Records a trajectory frame and takes a checkpoint if either is due, then returns how far the
+    // sim can step without skipping the next one.
+    fn periodic_work(&mut self, map: &Map, end_time: Time) -> Duration {
+        if let Some(next) = self.trajectories.as_ref().map(|t| t.next_sample()) {
+            if next <= self.time {
+                let mut trajectories = self.trajectories.take().unwrap();
+                trajectories.record(self, map);
+                self.trajectories = Some(trajectories);
+            }
+        }
+        if let Some(next) = self.next_checkpoint() {
+            if next <= self.time {
+                self.take_checkpoint();
+            }
+        }
+
+        let mut stop_at = end_time;
+        for next in vec![
+            self.trajectories.as_ref().map(|t| t.next_sample()),
+            self.next_checkpoint(),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            if next < stop_at {
+                stop_at = next;
+            }
+        }
+        stop_at - self.time
+    }
+
+    pub fn get_trajectories(&self) -> Option<&Trajectories> {
+        self.trajectories.as_ref()
+    }
+
     pub fn dump_before_abort(&self) {
         println!("At {}", self.time);
         if let Some(path) = self.find_previous_savestate(self.time) {
@@ -780,6 +922,96 @@ impl Sim {
     }
 }
 
+// Exporting
+impl Sim {
+    // Writes trips, road throughput, and any recorded trajectories so far to CSV and GeoJSON,
+    // returning the directory
+    pub fn export_results(&self, map: &Map) -> Result<String, std::io::Error> {
+        let dir = format!(
+            "{}/{}_{}/{}",
+            abstutil::path_exports(&self.map_name),
+            self.edits_name,
+            self.run_name,
+            self.time.as_filename()
+        );
+        crate::export::export_trips_csv(self, map, &format!("{}/trips.csv", dir))?;
+        crate::export::export_trips_geojson(self, map, &format!("{}/trips.geojson", dir))?;
+        crate::export::export_road_throughput_geojson(
+            &self.analytics,
+            map,
+            &format!("{}/road_throughput.geojson", dir),
+        )?;
+        if let Some(ref trajectories) = self.trajectories {
+            crate::export::export_trajectories_csv(
+                trajectories,
+                map,
+                &format!("{}/trajectories.csv", dir),
+            )?;
+        }
+        if !self.get_gridlock_reports().is_empty() {
+            abstutil::write_json(
+                format!("{}/gridlock.json", dir),
+                &self.get_gridlock_reports(),
+            );
+        }
+        Ok(dir)
+    }
+}
+
+// Rewinding
+impl Sim {
+    // The first checkpoint isn't at midnight, since events scheduled then haven't happened yet.
+    fn next_checkpoint(&self) -> Option<Time> {
+        let interval = self.checkpoint_interval?;
+        Some(match self.checkpoints.last() {
+            Some(c) => c.sim.time + interval,
+            None => Time::START_OF_DAY + interval,
+        })
+    }
+
+    fn take_checkpoint(&mut self) {
+        // Don't copy the things that'd just be thrown away when restoring
+        let checkpoints = std::mem::replace(&mut self.checkpoints, Vec::new());
+        let analytics = std::mem::replace(&mut self.analytics, Analytics::new());
+        let trajectories = self.trajectories.take();
+        let sim = Box::new(self.clone());
+        self.checkpoints = checkpoints;
+        self.analytics = analytics;
+        self.trajectories = trajectories;
+
+        self.checkpoints.push(Checkpoint {
+            sim,
+            analytics: self.analytics.checkpoint(self.time),
+        });
+    }
+
+    pub fn checkpoint_times(&self) -> Vec<Time> {
+        self.checkpoints.iter().map(|c| c.sim.time).collect()
+    }
+
+    // Restores the latest checkpoint at or before the target time, forgetting everything recorded
+    // since then. The caller has to simulate forwards to exactly reach the target. Returns the
+    // checkpoint's time, or None if there isn't one early enough.
+    pub fn rewind_to_checkpoint(&mut self, target: Time) -> Option<Time> {
+        let idx = self
+            .checkpoints
+            .iter()
+            .rposition(|c| c.sim.time <= target)?;
+        self.checkpoints.truncate(idx + 1);
+
+        let mut sim = (*self.checkpoints[idx].sim).clone();
+        sim.checkpoints = std::mem::replace(&mut self.checkpoints, Vec::new());
+        sim.analytics = std::mem::replace(&mut self.analytics, Analytics::new());
+        sim.analytics.rewind(&sim.checkpoints[idx].analytics);
+        sim.trajectories = self.trajectories.take();
+        if let Some(ref mut trajectories) = sim.trajectories {
+            trajectories.truncate(sim.time);
+        }
+        *self = sim;
+        Some(self.time)
+    }
+}
+
 // Savestating
 impl Sim {
     pub fn save_dir(&self) -> String {
@@ -862,12 +1094,67 @@ impl Sim {
         let paths = timer.parallelize(
             "calculate paths",
             self.scheduler.get_requests_for_savestate(),
-            |req| map.pathfind(req).unwrap(),
+            |(req, time)| map.pathfind_at(req, time).unwrap(),
         );
         self.scheduler.after_savestate(paths);
     }
 }
 
+// Verifying determinism
+impl Sim {
+    // None if the two sims are in exactly the same state. Otherwise, narrows down the difference
+    // to a single agent if possible.
+    pub fn find_divergence(&self, other: &Sim) -> Option<Divergence> {
+        if self == other {
+            return None;
+        }
+        let (system, agent, diff) = if self.time != other.time {
+            ("time", None, diff_json(&self.time, &other.time))
+        } else if let Some((id, diff)) = self.driving.find_divergence(&other.driving) {
+            ("driving", Some(AgentID::Car(id)), diff)
+        } else if let Some((id, diff)) = self.walking.find_divergence(&other.walking) {
+            ("walking", Some(AgentID::Pedestrian(id)), diff)
+        } else if self.driving != other.driving {
+            ("driving", None, diff_json(&self.driving, &other.driving))
+        } else if self.walking != other.walking {
+            ("walking", None, diff_json(&self.walking, &other.walking))
+        } else if self.parking != other.parking {
+            ("parking", None, diff_json(&self.parking, &other.parking))
+        } else if self.intersections != other.intersections {
+            (
+                "intersections",
+                None,
+                diff_json(&self.intersections, &other.intersections),
+            )
+        } else if self.transit != other.transit {
+            ("transit", None, diff_json(&self.transit, &other.transit))
+        } else if self.trips != other.trips {
+            ("trips", None, diff_json(&self.trips, &other.trips))
+        } else if self.scheduler != other.scheduler {
+            (
+                "scheduler",
+                None,
+                diff_json(&self.scheduler, &other.scheduler),
+            )
+        } else {
+            (
+                "map or edits",
+                None,
+                diff_json(
+                    &(&self.map_name, &self.edits_name),
+                    &(&other.map_name, &other.edits_name),
+                ),
+            )
+        };
+        Some(Divergence {
+            time: self.time,
+            system,
+            agent,
+            diff,
+        })
+    }
+}
+
 // Queries of all sorts
 impl Sim {
     pub fn time(&self) -> Time {
@@ -971,6 +1258,17 @@ impl Sim {
     pub fn trip_to_person(&self, id: TripID) -> PersonID {
         self.trips.trip_to_person(id)
     }
+    // None if the trip isn't finished or doesn't go between two buildings
+    pub fn trip_counterfactuals(&self, trip: TripID, map: &Map) -> Option<TripCounterfactuals> {
+        trip_counterfactuals(self, trip, map)
+    }
+    pub fn person_counterfactuals(&self, person: PersonID, map: &Map) -> Vec<TripCounterfactuals> {
+        self.get_person(person)
+            .trips
+            .iter()
+            .filter_map(|t| self.trip_counterfactuals(*t, map))
+            .collect()
+    }
     // TODO This returns None for parked cars owned by people! That's confusing. Dedupe with
     // get_owner_of_car.
     pub fn agent_to_person(&self, id: AgentID) -> Option<PersonID> {
@@ -1107,6 +1405,14 @@ impl Sim {
         &self.analytics
     }
 
+    // Empty unless SimOptions asked to detect gridlock
+    pub fn get_gridlock_reports(&self) -> &[Gridlock] {
+        match self.gridlock {
+            Some(ref detector) => &detector.reports,
+            None => &[],
+        }
+    }
+
     pub fn find_blockage_front(&self, car: CarID, map: &Map) -> String {
         self.driving
             .find_blockage_front(car, map, &self.intersections)
@@ -0,0 +1,353 @@
+use crate::{
+    AgentID, CarID, CarStatus, DrawCarInput, DrawPedCrowdInput, DrawPedestrianInput, GetDrawAgents,
+    PedestrianID, PersonID, Sim, UnzoomedAgent,
+};
+use abstutil::Timer;
+use geom::{Angle, Distance, Duration, PolyLine, Pt2D, Time};
+use map_model::{Map, Traversable, TurnID};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// Where every agent was, sampled at a fixed interval. Enough to scrub back and forth through a run
+// without simulating anything, or to hand off to other tools for animation.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Trajectories {
+    pub map_name: String,
+    pub edits_name: String,
+    pub run_name: String,
+    pub interval: Duration,
+    pub frames: Vec<TrajectoryFrame>,
+
+    // Every parked car as of the last frame, to figure out what changed in the next one
+    #[serde(skip_serializing, skip_deserializing)]
+    parked: BTreeMap<CarID, ParkedSample>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TrajectoryFrame {
+    pub time: Time,
+    // Only moving cars
+    pub cars: Vec<CarSample>,
+    pub peds: Vec<PedSample>,
+    // Parked cars don't move, so they're only recorded in the frame where they park or leave
+    pub newly_parked: Vec<ParkedSample>,
+    pub unparked: Vec<CarID>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CarSample {
+    pub id: CarID,
+    // None for buses
+    pub person: Option<PersonID>,
+    pub on: Traversable,
+    pub front: Pt2D,
+    // The direction the front of the car is facing
+    pub angle: Angle,
+    pub length: Distance,
+    pub waiting_for_turn: Option<TurnID>,
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParkedSample {
+    pub id: CarID,
+    pub on: Traversable,
+    pub body: PolyLine,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PedSample {
+    pub id: PedestrianID,
+    pub person: Option<PersonID>,
+    pub on: Traversable,
+    pub pos: Pt2D,
+    pub facing: Angle,
+    pub waiting_for_turn: Option<TurnID>,
+    pub preparing_bike: bool,
+    pub waiting_for_bus: bool,
+}
+
+impl Trajectories {
+    pub(crate) fn new(map: &Map, run_name: String, interval: Duration) -> Trajectories {
+        assert!(
+            interval > Duration::ZERO,
+            "record_trajectories interval must be positive"
+        );
+        Trajectories {
+            map_name: map.get_name().to_string(),
+            edits_name: map.get_edits().edits_name.clone(),
+            run_name,
+            interval,
+            frames: Vec::new(),
+            parked: BTreeMap::new(),
+        }
+    }
+
+    // When the next frame should be recorded
+    pub(crate) fn next_sample(&self) -> Time {
+        match self.frames.last() {
+            Some(f) => f.time + self.interval,
+            None => Time::START_OF_DAY,
+        }
+    }
+
+    pub(crate) fn record(&mut self, sim: &Sim, map: &Map) {
+        let mut frame = TrajectoryFrame {
+            time: sim.time(),
+            cars: Vec::new(),
+            peds: Vec::new(),
+            newly_parked: Vec::new(),
+            unparked: Vec::new(),
+        };
+        let mut parked = BTreeMap::new();
+        for c in sim.get_all_draw_cars(map) {
+            if c.status == CarStatus::Parked {
+                parked.insert(
+                    c.id,
+                    ParkedSample {
+                        id: c.id,
+                        on: c.on,
+                        body: c.body,
+                    },
+                );
+                continue;
+            }
+            frame.cars.push(CarSample {
+                id: c.id,
+                person: sim.agent_to_person(AgentID::Car(c.id)),
+                on: c.on,
+                front: c.body.last_pt(),
+                angle: c.body.last_line().angle(),
+                length: c.body.length(),
+                waiting_for_turn: c.waiting_for_turn,
+            });
+        }
+        for (id, before) in &self.parked {
+            if parked.get(id) != Some(before) {
+                frame.unparked.push(*id);
+            }
+        }
+        for (id, now) in &parked {
+            if self.parked.get(id) != Some(now) {
+                frame.newly_parked.push(now.clone());
+            }
+        }
+        self.parked = parked;
+        for p in sim.get_all_draw_peds(map) {
+            frame.peds.push(PedSample {
+                id: p.id,
+                person: sim.agent_to_person(AgentID::Pedestrian(p.id)),
+                on: p.on,
+                pos: p.pos,
+                facing: p.facing,
+                waiting_for_turn: p.waiting_for_turn,
+                preparing_bike: p.preparing_bike,
+                waiting_for_bus: p.waiting_for_bus,
+            });
+        }
+        self.frames.push(frame);
+    }
+
+    // After rewinding the sim, drop frames it hasn't reached again yet
+    pub(crate) fn truncate(&mut self, time: Time) {
+        self.frames.retain(|f| f.time <= time);
+        self.parked.clear();
+        for frame in &self.frames {
+            frame.apply_parking(&mut self.parked);
+        }
+    }
+
+    pub fn save(&self) -> String {
+        let path = abstutil::path_trajectories(&self.map_name, &self.run_name);
+        abstutil::write_binary(path.clone(), self);
+        path
+    }
+
+    pub fn load(map_name: &str, name: &str, timer: &mut Timer) -> Result<Trajectories, String> {
+        abstutil::maybe_read_binary(abstutil::path_trajectories(map_name, name), timer)
+            .map_err(|err| err.to_string())
+    }
+
+    pub fn list_all(map_name: &str) -> Vec<String> {
+        abstutil::list_all_objects(abstutil::path_all_trajectories(map_name))
+    }
+
+    pub fn end_time(&self) -> Time {
+        self.frames
+            .last()
+            .map(|f| f.time)
+            .unwrap_or(Time::START_OF_DAY)
+    }
+}
+
+impl TrajectoryFrame {
+    fn apply_parking(&self, parked: &mut BTreeMap<CarID, ParkedSample>) {
+        for id in &self.unparked {
+            parked.remove(id);
+        }
+        for p in &self.newly_parked {
+            parked.insert(p.id, p.clone());
+        }
+    }
+}
+
+// Draws agents from recorded trajectories instead of a live simulation. Always shows the last
+// frame at or before the requested time; nothing is interpolated.
+pub struct TrajectoryPlayback {
+    pub trajectories: Trajectories,
+    current: usize,
+    // Parked cars as of the current frame
+    parked: BTreeMap<CarID, ParkedSample>,
+}
+
+impl TrajectoryPlayback {
+    pub fn new(trajectories: Trajectories) -> TrajectoryPlayback {
+        assert!(!trajectories.frames.is_empty());
+        let mut parked = BTreeMap::new();
+        trajectories.frames[0].apply_parking(&mut parked);
+        TrajectoryPlayback {
+            trajectories,
+            current: 0,
+            parked,
+        }
+    }
+
+    pub fn set_time(&mut self, time: Time) {
+        let frames = &self.trajectories.frames;
+        let idx = match frames.binary_search_by(|f| f.time.partial_cmp(&time).unwrap()) {
+            Ok(idx) => idx,
+            Err(idx) => idx.max(1) - 1,
+        };
+        // Parking changes only go forwards, so start over when scrubbing back
+        let start = if idx < self.current {
+            self.parked.clear();
+            0
+        } else {
+            self.current + 1
+        };
+        for frame in frames.iter().take(idx + 1).skip(start) {
+            frame.apply_parking(&mut self.parked);
+        }
+        self.current = idx;
+    }
+
+    fn frame(&self) -> &TrajectoryFrame {
+        &self.trajectories.frames[self.current]
+    }
+}
+
+// Only the front is recorded, so the body is drawn straight
+fn car_input(c: &CarSample) -> DrawCarInput {
+    DrawCarInput {
+        id: c.id,
+        waiting_for_turn: c.waiting_for_turn,
+        status: CarStatus::Moving,
+        on: c.on,
+        label: None,
+        body: PolyLine::new(vec![
+            c.front.project_away(c.length, c.angle.opposite()),
+            c.front,
+        ]),
+    }
+}
+
+fn parked_input(p: &ParkedSample) -> DrawCarInput {
+    DrawCarInput {
+        id: p.id,
+        waiting_for_turn: None,
+        status: CarStatus::Parked,
+        on: p.on,
+        label: None,
+        body: p.body.clone(),
+    }
+}
+
+fn ped_input(p: &PedSample) -> DrawPedestrianInput {
+    DrawPedestrianInput {
+        id: p.id,
+        pos: p.pos,
+        facing: p.facing,
+        waiting_for_turn: p.waiting_for_turn,
+        preparing_bike: p.preparing_bike,
+        waiting_for_bus: p.waiting_for_bus,
+        on: p.on,
+    }
+}
+
+impl GetDrawAgents for TrajectoryPlayback {
+    fn time(&self) -> Time {
+        self.frame().time
+    }
+    fn step_count(&self) -> usize {
+        self.current
+    }
+    fn get_draw_car(&self, id: CarID, _: &Map) -> Option<DrawCarInput> {
+        self.frame()
+            .cars
+            .iter()
+            .find(|c| c.id == id)
+            .map(car_input)
+            .or_else(|| self.parked.get(&id).map(parked_input))
+    }
+    fn get_draw_ped(&self, id: PedestrianID, _: &Map) -> Option<DrawPedestrianInput> {
+        self.frame().peds.iter().find(|p| p.id == id).map(ped_input)
+    }
+    fn get_draw_cars(&self, on: Traversable, _: &Map) -> Vec<DrawCarInput> {
+        let mut result: Vec<DrawCarInput> = self
+            .frame()
+            .cars
+            .iter()
+            .filter(|c| c.on == on)
+            .map(car_input)
+            .collect();
+        result.extend(
+            self.parked
+                .values()
+                .filter(|p| p.on == on)
+                .map(parked_input),
+        );
+        result
+    }
+    // Crowds aren't recorded; everyone is drawn individually
+    fn get_draw_peds(
+        &self,
+        on: Traversable,
+        _: &Map,
+    ) -> (Vec<DrawPedestrianInput>, Vec<DrawPedCrowdInput>) {
+        (
+            self.frame()
+                .peds
+                .iter()
+                .filter(|p| p.on == on)
+                .map(ped_input)
+                .collect(),
+            Vec::new(),
+        )
+    }
+    fn get_all_draw_cars(&self, _: &Map) -> Vec<DrawCarInput> {
+        let mut result: Vec<DrawCarInput> = self.frame().cars.iter().map(car_input).collect();
+        result.extend(self.parked.values().map(parked_input));
+        result
+    }
+    fn get_all_draw_peds(&self, _: &Map) -> Vec<DrawPedestrianInput> {
+        self.frame().peds.iter().map(ped_input).collect()
+    }
+    fn get_unzoomed_agents(&self, _: &Map) -> Vec<UnzoomedAgent> {
+        let mut result = Vec::new();
+        // The live sim doesn't show parked cars unzoomed either
+        for c in &self.frame().cars {
+            result.push(UnzoomedAgent {
+                vehicle_type: Some(c.id.1),
+                pos: c.front,
+                person: c.person,
+            });
+        }
+        for p in &self.frame().peds {
+            result.push(UnzoomedAgent {
+                vehicle_type: None,
+                pos: p.pos,
+                person: p.person,
+            });
+        }
+        result
+    }
+}
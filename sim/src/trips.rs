@@ -269,7 +269,7 @@ impl TripManager {
             end,
             constraints: PathConstraints::Car,
         };
-        let path = if let Some(p) = map.pathfind(req.clone()) {
+        let path = if let Some(p) = map.pathfind_at(req.clone(), now) {
             p
         } else {
             self.events.push(Event::Alert(
@@ -928,7 +928,7 @@ impl TripManager {
     ) {
         assert!(!self.trips[trip.0].aborted);
         if !self.pathfinding_upfront && maybe_path.is_none() && maybe_req.is_some() {
-            maybe_path = map.pathfind(maybe_req.clone().unwrap());
+            maybe_path = map.pathfind_at(maybe_req.clone().unwrap(), now);
         }
 
         let person = &mut self.people[self.trips[trip.0].person.0];